@@ -1,9 +1,19 @@
 #![allow(dead_code)]
 use crate::ipc_error::IpcError;
-use crate::ssh::auth::AuthMethod;
+use crate::ssh::auth::{AuthMethod, CertificateInfo};
 use crate::ssh::actor::{spawn_connection_actor, ConnectionRequest};
-use crate::ssh::client::{SshConnection, SshError};
+use crate::ssh::client::{JumpHost, SshConnection, SshError};
+use crate::ssh::config::{self as ssh_config, ResolvedHost, SshConfig, SshConfigError};
+use crate::ssh::exec;
+use crate::ssh::follow;
+use crate::ssh::forward;
+use crate::ssh::keyboard_interactive;
 use crate::ssh::known_hosts;
+use crate::ssh::lsp;
+use crate::ssh::search;
+use crate::ssh::tasks;
+use crate::ssh::transfer;
+use crate::ssh::watch;
 use crate::state::AppState;
 use crate::trace::{emit_trace, TraceEvent};
 use serde::{Deserialize, Serialize};
@@ -27,6 +37,200 @@ pub struct ConnectionProfile {
     pub username: String,
     pub auth_method: String,
     pub key_path: Option<String>,
+    /// ProxyJump chain, outermost bastion first.
+    #[serde(default)]
+    pub jump_hosts: Vec<JumpHostProfile>,
+    /// OpenSSH user certificate for key auth; defaults to `<keyPath>-cert.pub` when present.
+    #[serde(default)]
+    pub certificate_path: Option<String>,
+    /// Follow the primary method with keyboard-interactive (server-side 2FA such as TOTP).
+    #[serde(default)]
+    pub keyboard_interactive: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JumpHostProfile {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub auth_method: String,
+    pub key_path: Option<String>,
+    #[serde(default)]
+    pub certificate_path: Option<String>,
+    #[serde(default)]
+    pub keyboard_interactive: bool,
+}
+
+/// Build an `AuthMethod` from a profile's `authMethod`/`keyPath` plus the secret supplied by the UI
+/// (password, or key passphrase).
+///
+/// With `keyboard_interactive` set, a keyboard-interactive step (e.g. TOTP) follows the primary method.
+fn auth_from_profile(
+    auth_method: &str,
+    key_path: Option<String>,
+    certificate_path: Option<String>,
+    secret: Option<String>,
+    keyboard_interactive: bool,
+) -> Result<AuthMethod, IpcError> {
+    let primary = match auth_method {
+        "key" => {
+            let key_path = key_path
+                .ok_or_else(|| IpcError::new("invalid_key_path", "Key path required for key authentication"))?;
+            AuthMethod::Key {
+                path: key_path,
+                passphrase: secret,
+                certificate: certificate_path,
+            }
+        }
+        "password" => AuthMethod::Password(secret.ok_or_else(|| {
+            IpcError::new("missing_password", "Password required for password authentication")
+        })?),
+        "agent" => AuthMethod::Agent,
+        "keyboard-interactive" => AuthMethod::KeyboardInteractive { password: secret },
+        _ => return Err(IpcError::new("invalid_auth_method", "Invalid authentication method")),
+    };
+
+    if keyboard_interactive && !matches!(primary, AuthMethod::KeyboardInteractive { .. }) {
+        return Ok(AuthMethod::Chain(vec![
+            primary,
+            AuthMethod::KeyboardInteractive { password: None },
+        ]));
+    }
+    Ok(primary)
+}
+
+/// Resolve the profile's jump hosts; `secrets[i]` is the password/passphrase for `jump_hosts[i]`.
+fn jump_hosts_from_profile(
+    profile: &ConnectionProfile,
+    secrets: Option<Vec<Option<String>>>,
+) -> Result<Vec<JumpHost>, IpcError> {
+    let mut secrets = secrets.unwrap_or_default().into_iter();
+    profile
+        .jump_hosts
+        .iter()
+        .map(|jump| {
+            let secret = secrets.next().flatten();
+            let auth = auth_from_profile(
+                &jump.auth_method,
+                jump.key_path.clone(),
+                jump.certificate_path.clone(),
+                secret,
+                jump.keyboard_interactive,
+            )
+            .map_err(|e| e.with_context(json!({ "jumpHost": jump.host, "port": jump.port })))?;
+            Ok(JumpHost {
+                host: jump.host.clone(),
+                port: jump.port,
+                username: jump.username.clone(),
+                auth,
+            })
+        })
+        .collect()
+}
+
+/// Auth method for a config host: its key if one is known, else the agent if running, else password.
+fn config_auth_method(key_path: &Option<String>) -> String {
+    if key_path.is_some() {
+        "key"
+    } else if std::env::var_os("SSH_AUTH_SOCK").is_some() {
+        "agent"
+    } else {
+        "password"
+    }
+    .to_string()
+}
+
+/// Convert a host resolved from `~/.ssh/config` into a profile the UI can save or connect with.
+fn profile_from_ssh_config(resolved: &ResolvedHost) -> ConnectionProfile {
+    let key_path = resolved
+        .identity_files
+        .first()
+        .cloned()
+        .or_else(ssh_config::default_identity_file);
+    ConnectionProfile {
+        id: format!("ssh-config:{}", resolved.alias),
+        name: resolved.alias.clone(),
+        host: resolved.host_name.clone(),
+        port: resolved.port,
+        username: resolved.user.clone().unwrap_or_else(ssh_config::local_user),
+        auth_method: config_auth_method(&key_path),
+        key_path,
+        jump_hosts: resolved
+            .jump_hosts
+            .iter()
+            .map(|jump| {
+                let key_path = jump
+                    .identity_files
+                    .first()
+                    .cloned()
+                    .or_else(ssh_config::default_identity_file);
+                JumpHostProfile {
+                    host: jump.host_name.clone(),
+                    port: jump.port,
+                    username: jump.user.clone().unwrap_or_else(ssh_config::local_user),
+                    auth_method: config_auth_method(&key_path),
+                    key_path,
+                    certificate_path: jump.certificate_files.first().cloned(),
+                    keyboard_interactive: false,
+                }
+            })
+            .collect(),
+        certificate_path: resolved.certificate_files.first().cloned(),
+        keyboard_interactive: false,
+    }
+}
+
+fn map_ssh_config_error(error: SshConfigError) -> IpcError {
+    match error {
+        SshConfigError::UnknownHost(alias) => IpcError::new(
+            "ssh_config_host_not_found",
+            "No matching Host entry in the SSH config.",
+        )
+        .with_context(json!({ "alias": alias })),
+        other => IpcError::new("ssh_config_failed", "Failed to read SSH config").with_raw(other.to_string()),
+    }
+}
+
+async fn load_ssh_config(path: Option<String>) -> Result<SshConfig, IpcError> {
+    tauri::async_runtime::spawn_blocking(move || match path {
+        Some(path) => SshConfig::load_file(std::path::Path::new(&path)),
+        None => SshConfig::load_default(),
+    })
+    .await
+    .map_err(|e| IpcError::new("ssh_config_failed", "Failed to read SSH config").with_raw(e.to_string()))?
+    .map_err(map_ssh_config_error)
+}
+
+/// Overlay the settings resolved for `alias` from `~/.ssh/config` onto `profile`.
+///
+/// Host, port and ProxyJump always come from the config; user and key only when the config sets them.
+async fn apply_config_alias(
+    profile: ConnectionProfile,
+    alias: Option<String>,
+) -> Result<ConnectionProfile, IpcError> {
+    let Some(alias) = alias.filter(|a| !a.trim().is_empty()) else {
+        return Ok(profile);
+    };
+
+    let config = load_ssh_config(None).await?;
+    let resolved = config.resolve(alias.trim()).map_err(map_ssh_config_error)?;
+    let from_config = profile_from_ssh_config(&resolved);
+
+    Ok(ConnectionProfile {
+        host: from_config.host,
+        port: from_config.port,
+        username: resolved.user.clone().unwrap_or(profile.username),
+        auth_method: if resolved.identity_files.is_empty() {
+            profile.auth_method
+        } else {
+            "key".to_string()
+        },
+        key_path: resolved.identity_files.first().cloned().or(profile.key_path),
+        certificate_path: from_config.certificate_path.or(profile.certificate_path),
+        jump_hosts: from_config.jump_hosts,
+        ..profile
+    })
 }
 
 fn map_connect_error(profile: &ConnectionProfile, error: SshError) -> IpcError {
@@ -35,6 +239,11 @@ fn map_connect_error(profile: &ConnectionProfile, error: SshError) -> IpcError {
         "port": profile.port,
         "username": profile.username,
         "authMethod": profile.auth_method,
+        "jumpHosts": profile
+            .jump_hosts
+            .iter()
+            .map(|j| format!("{}@{}:{}", j.username, j.host, j.port))
+            .collect::<Vec<_>>(),
     });
 
     match error {
@@ -115,6 +324,26 @@ fn map_connect_error(profile: &ConnectionProfile, error: SshError) -> IpcError {
             "actualPublicKeyOpenssh": actual_public_key_openssh,
             "profile": base_context,
         })),
+        SshError::HostKeyRevoked {
+            host,
+            port,
+            fingerprint_sha256,
+        } => IpcError::new(
+            "ssh_hostkey_revoked",
+            "The server's host key has been revoked and will not be trusted.",
+        )
+        .with_context(json!({
+            "host": host,
+            "port": port,
+            "fingerprintSha256": fingerprint_sha256,
+            "profile": base_context,
+        })),
+        SshError::JumpHostFailed { hop, via, detail } => IpcError::new(
+            "ssh_jump_host_failed",
+            "Could not reach the next hop through the jump host. Check that the bastion allows TCP forwarding.",
+        )
+        .with_raw(detail)
+        .with_context(json!({ "hop": hop, "via": via, "profile": base_context })),
         SshError::AuthenticationFailed(source) => IpcError::new(
             "ssh_auth_failed",
             "SSH authentication failed. Verify username and credentials.",
@@ -134,31 +363,25 @@ pub async fn ssh_connect(
     state: State<'_, Arc<Mutex<AppState>>>,
     profile: ConnectionProfile,
     password: Option<String>,
+    jump_host_secrets: Option<Vec<Option<String>>>,
+    config_alias: Option<String>,
 ) -> Result<String, IpcError> {
-    let auth = match profile.auth_method.as_str() {
-        "key" => {
-            let key_path = profile
-                .key_path
-                .clone()
-                .ok_or_else(|| IpcError::new("invalid_key_path", "Key path required for key authentication"))?;
-            AuthMethod::Key {
-                path: key_path,
-                passphrase: password,
-            }
-        }
-        "password" => AuthMethod::Password(
-            password.ok_or_else(|| {
-                IpcError::new("missing_password", "Password required for password authentication")
-            })?,
-        ),
-        _ => return Err(IpcError::new("invalid_auth_method", "Invalid authentication method")),
-    };
+    let profile = apply_config_alias(profile, config_alias).await?;
+    let auth = auth_from_profile(
+        &profile.auth_method,
+        profile.key_path.clone(),
+        profile.certificate_path.clone(),
+        password,
+        profile.keyboard_interactive,
+    )?;
+    let jump_hosts = jump_hosts_from_profile(&profile, jump_host_secrets)?;
 
     let mut connection = SshConnection::connect(
         &profile.host,
         profile.port,
         &profile.username,
         auth,
+        &jump_hosts,
         &app,
     )
     .await
@@ -206,6 +429,8 @@ pub async fn ssh_reconnect(
     conn_id: String,
     profile: ConnectionProfile,
     password: Option<String>,
+    jump_host_secrets: Option<Vec<Option<String>>>,
+    config_alias: Option<String>,
 ) -> Result<(), IpcError> {
     // Best-effort: remove any existing handle for this connection ID (stale or active).
     // Also drop any existing PTY sessions for this connection; the UI will re-open them after reconnect.
@@ -222,24 +447,15 @@ pub async fn ssh_reconnect(
         let _ = timeout(Duration::from_millis(500), terminal.close()).await;
     }
 
-    let auth = match profile.auth_method.as_str() {
-        "key" => {
-            let key_path = profile
-                .key_path
-                .clone()
-                .ok_or_else(|| IpcError::new("invalid_key_path", "Key path required for key authentication"))?;
-            AuthMethod::Key {
-                path: key_path,
-                passphrase: password,
-            }
-        }
-        "password" => AuthMethod::Password(
-            password.ok_or_else(|| {
-                IpcError::new("missing_password", "Password required for password authentication")
-            })?,
-        ),
-        _ => return Err(IpcError::new("invalid_auth_method", "Invalid authentication method")),
-    };
+    let profile = apply_config_alias(profile, config_alias).await?;
+    let auth = auth_from_profile(
+        &profile.auth_method,
+        profile.key_path.clone(),
+        profile.certificate_path.clone(),
+        password,
+        profile.keyboard_interactive,
+    )?;
+    let jump_hosts = jump_hosts_from_profile(&profile, jump_host_secrets)?;
 
     emit_trace(
         &app,
@@ -252,6 +468,7 @@ pub async fn ssh_reconnect(
         profile.port,
         &profile.username,
         auth,
+        &jump_hosts,
         &app,
     )
     .await
@@ -280,8 +497,32 @@ pub async fn ssh_reconnect(
     emit_trace(&app, TraceEvent::new("actor", "spawn", "Spawning connection actor").with_detail(&conn_id));
     let handle = spawn_connection_actor(app.clone(), conn_id.clone(), connection);
 
-    let mut app_state = state.lock().await;
-    app_state.add_connection(conn_id.clone(), handle);
+    state.lock().await.add_connection(conn_id.clone(), handle);
+
+    // Local/dynamic listeners stay bound across reconnects and pick up the new actor by ID;
+    // remote forwards have to be requested again on the new session.
+    let forwards = forward::list(Some(&conn_id)).len();
+    if forwards > 0 {
+        let (_, failed) = forward::reattach(&app, &conn_id).await;
+        emit_trace(
+            &app,
+            TraceEvent::new(
+                "forward",
+                "reattached",
+                &format!("{} forward(s) re-attached, {} failed", forwards - failed, failed),
+            )
+            .with_detail(&conn_id),
+        );
+    }
+
+    // Language servers died with the old session's channels.
+    let servers = lsp::restart_for_connection(&app, &conn_id).await;
+    if servers > 0 {
+        emit_trace(
+            &app,
+            TraceEvent::new("lsp", "restarted", &format!("{} language server(s) restarted", servers)).with_detail(&conn_id),
+        );
+    }
 
     emit_trace(&app, TraceEvent::new("connect", "complete", &format!("Connection ready: {}", conn_id)));
     Ok(())
@@ -326,6 +567,15 @@ pub async fn ssh_disconnect(
         let _ = timeout(Duration::from_millis(500), terminal.close()).await;
     }
 
+    forward::close_for_connection(&conn_id);
+    transfer::cancel_for_connection(&conn_id);
+    search::cancel_for_connection(&conn_id);
+    exec::cancel_for_connection(&conn_id);
+    tasks::forget_connection(&conn_id);
+    lsp::stop_for_connection(&conn_id);
+    follow::stop_for_connection(&conn_id);
+    watch::stop(&conn_id);
+
     Ok(())
 }
 
@@ -377,35 +627,41 @@ pub async fn ssh_check_tmux(
         .map_err(|e| IpcError::new("tmux_check_failed", "Failed to check tmux availability").with_raw(e.to_string()))
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTestResult {
+    pub success: bool,
+    /// User certificate presented to the target host, if any (carries its expiry).
+    pub certificate: Option<CertificateInfo>,
+}
+
 /// Test a connection without persisting it
 #[tauri::command]
 pub async fn ssh_test_connection(
     app: AppHandle,
     profile: ConnectionProfile,
     password: Option<String>,
-) -> Result<bool, IpcError> {
-    let auth = match profile.auth_method.as_str() {
-        "key" => {
-            let key_path = profile
-                .key_path
-                .clone()
-                .ok_or_else(|| IpcError::new("invalid_key_path", "Key path required for key authentication"))?;
-            AuthMethod::Key {
-                path: key_path,
-                passphrase: password,
-            }
-        }
-        "password" => AuthMethod::Password(
-            password.ok_or_else(|| {
-                IpcError::new("missing_password", "Password required for password authentication")
-            })?,
-        ),
-        _ => return Err(IpcError::new("invalid_auth_method", "Invalid authentication method")),
-    };
+    jump_host_secrets: Option<Vec<Option<String>>>,
+    config_alias: Option<String>,
+) -> Result<ConnectionTestResult, IpcError> {
+    let profile = apply_config_alias(profile, config_alias).await?;
+    let auth = auth_from_profile(
+        &profile.auth_method,
+        profile.key_path.clone(),
+        profile.certificate_path.clone(),
+        password,
+        profile.keyboard_interactive,
+    )?;
+    let certificate = auth.certificate_info().await.map_err(|e| {
+        IpcError::new("certificate_invalid", "Failed to read SSH certificate")
+            .with_raw(e.to_string())
+            .with_context(json!({ "certificatePath": profile.certificate_path, "keyPath": profile.key_path }))
+    })?;
+    let jump_hosts = jump_hosts_from_profile(&profile, jump_host_secrets)?;
 
     emit_trace(&app, TraceEvent::new("test", "start", &format!("Testing connection to {}:{}", profile.host, profile.port)));
 
-    match SshConnection::connect(&profile.host, profile.port, &profile.username, auth, &app).await {
+    match SshConnection::connect(&profile.host, profile.port, &profile.username, auth, &jump_hosts, &app).await {
         Ok(mut conn) => {
             emit_trace(&app, TraceEvent::new("sftp", "verify", "Verifying SFTP availability (test)"));
             if let Err(e) = conn.get_home_dir().await {
@@ -433,7 +689,10 @@ pub async fn ssh_test_connection(
             emit_trace(&app, TraceEvent::new("test", "grace_period", "Waiting 150ms for socket release"));
             sleep(Duration::from_millis(150)).await;
             emit_trace(&app, TraceEvent::new("test", "success", "Connection test passed"));
-            Ok(true)
+            Ok(ConnectionTestResult {
+                success: true,
+                certificate,
+            })
         }
         Err(e) => {
             emit_trace(&app, TraceEvent::new("test", "failed", "Connection test failed").with_detail(e.to_string()).error());
@@ -514,16 +773,197 @@ pub async fn ssh_trust_host_key(app: AppHandle, request: TrustHostKeyRequest) ->
     Ok(())
 }
 
-/// Forget a previously trusted host key for `host:port`.
+/// Fetch every trusted host key for `host:port`.
 #[tauri::command]
-pub async fn ssh_forget_host_key(app: AppHandle, host: String, port: u16) -> Result<(), IpcError> {
-    known_hosts::remove(&app, &host, port)
+pub async fn ssh_get_trusted_host_keys(
+    app: AppHandle,
+    host: String,
+    port: u16,
+) -> Result<Vec<known_hosts::KnownHostEntry>, IpcError> {
+    known_hosts::get_all(&app, &host, port)
+        .await
+        .map_err(|e| IpcError::new("hostkey_store_failed", "Failed to read trusted host keys").with_raw(e))
+}
+
+/// Forget a previously trusted host key for `host:port` (one key by fingerprint, or all of them).
+#[tauri::command]
+pub async fn ssh_forget_host_key(
+    app: AppHandle,
+    host: String,
+    port: u16,
+    fingerprint_sha256: Option<String>,
+) -> Result<(), IpcError> {
+    known_hosts::remove(&app, &host, port, fingerprint_sha256.as_deref())
         .await
         .map_err(|e| IpcError::new("hostkey_store_failed", "Failed to remove trusted host key").with_raw(e))?;
     emit_trace(
         &app,
-        TraceEvent::new("hostkey", "trusted_removed", "Trusted host key removed")
-            .with_detail(format!("{}:{}", host, port)),
+        TraceEvent::new("hostkey", "trusted_removed", "Trusted host key removed").with_detail(format!(
+            "{}:{} {}",
+            host,
+            port,
+            fingerprint_sha256.as_deref().unwrap_or("all")
+        )),
     );
     Ok(())
 }
+
+/// History of host keys added/retired (by the user, imports, or server announcements).
+#[tauri::command]
+pub async fn ssh_host_key_audit(
+    app: AppHandle,
+    host: Option<String>,
+    port: Option<u16>,
+) -> Result<Vec<known_hosts::HostKeyAuditEvent>, IpcError> {
+    known_hosts::audit_log(&app, host.as_deref(), port)
+        .await
+        .map_err(|e| IpcError::new("hostkey_store_failed", "Failed to read host key audit log").with_raw(e))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostsHost {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostsReadThrough {
+    pub enabled: bool,
+    pub path: Option<String>,
+}
+
+fn known_hosts_path(path: Option<String>) -> Result<std::path::PathBuf, IpcError> {
+    path.filter(|p| !p.trim().is_empty())
+        .map(|p| match p.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|h| h.join(rest)).unwrap_or_else(|| p.clone().into()),
+            None => p.into(),
+        })
+        .or_else(known_hosts::default_system_path)
+        .ok_or_else(|| IpcError::new("known_hosts_path_unavailable", "Could not determine the known_hosts path"))
+}
+
+/// Import trust decisions from an OpenSSH `known_hosts` file (default `~/.ssh/known_hosts`).
+///
+/// Hashed entries are matched against `hosts` (e.g. the saved profiles), since they cannot be reversed.
+#[tauri::command]
+pub async fn ssh_import_known_hosts(
+    app: AppHandle,
+    path: Option<String>,
+    hosts: Option<Vec<KnownHostsHost>>,
+) -> Result<known_hosts::ImportReport, IpcError> {
+    let path = known_hosts_path(path)?;
+    let text = tokio::fs::read_to_string(&path).await.map_err(|e| {
+        IpcError::new("known_hosts_read_failed", "Failed to read known_hosts file")
+            .with_raw(e.to_string())
+            .with_context(json!({ "path": path.display().to_string() }))
+    })?;
+    let candidates: Vec<(String, u16)> = hosts
+        .unwrap_or_default()
+        .into_iter()
+        .map(|h| (h.host, h.port))
+        .collect();
+
+    let report = known_hosts::import_openssh(&app, &text, &candidates)
+        .await
+        .map_err(|e| IpcError::new("hostkey_store_failed", "Failed to import known_hosts").with_raw(e))?;
+
+    emit_trace(
+        &app,
+        TraceEvent::new("hostkey", "imported", "Imported OpenSSH known_hosts").with_detail(format!(
+            "{} keys, {} revoked, {} skipped from {}",
+            report.host_keys,
+            report.revoked,
+            report.skipped.len(),
+            path.display()
+        )),
+    );
+    Ok(report)
+}
+
+/// Export trusted keys, host CAs and revocations in OpenSSH `known_hosts` format.
+///
+/// Returns the file contents; also writes them to `path` when given.
+#[tauri::command]
+pub async fn ssh_export_known_hosts(
+    app: AppHandle,
+    path: Option<String>,
+    hash_hostnames: Option<bool>,
+) -> Result<String, IpcError> {
+    let text = known_hosts::export_openssh(&app, hash_hostnames.unwrap_or(false))
+        .await
+        .map_err(|e| IpcError::new("hostkey_store_failed", "Failed to export known_hosts").with_raw(e))?;
+
+    if path.as_deref().is_some_and(|p| !p.trim().is_empty()) {
+        let path = known_hosts_path(path)?;
+        tokio::fs::write(&path, &text).await.map_err(|e| {
+            IpcError::new("known_hosts_write_failed", "Failed to write known_hosts file")
+                .with_raw(e.to_string())
+                .with_context(json!({ "path": path.display().to_string() }))
+        })?;
+    }
+    Ok(text)
+}
+
+/// Also trust keys from the system known_hosts (default `~/.ssh/known_hosts`) when checking host keys.
+#[tauri::command]
+pub async fn ssh_set_known_hosts_read_through(
+    app: AppHandle,
+    enabled: bool,
+    path: Option<String>,
+) -> Result<KnownHostsReadThrough, IpcError> {
+    let path = if enabled { Some(known_hosts_path(path)?) } else { None };
+    known_hosts::set_read_through(&app, path.clone())
+        .await
+        .map_err(|e| IpcError::new("hostkey_store_failed", "Failed to save known_hosts settings").with_raw(e))?;
+    Ok(KnownHostsReadThrough {
+        enabled,
+        path: path.map(|p| p.display().to_string()),
+    })
+}
+
+#[tauri::command]
+pub async fn ssh_get_known_hosts_read_through(app: AppHandle) -> Result<KnownHostsReadThrough, IpcError> {
+    let path = known_hosts::read_through(&app)
+        .await
+        .map_err(|e| IpcError::new("hostkey_store_failed", "Failed to read known_hosts settings").with_raw(e))?;
+    Ok(KnownHostsReadThrough {
+        enabled: path.is_some(),
+        path: path.map(|p| p.display().to_string()),
+    })
+}
+
+/// List hosts declared in the OpenSSH client config, resolved into connection profiles.
+///
+/// Reads `~/.ssh/config` (plus `/etc/ssh/ssh_config`) unless `path` points at a specific file.
+#[tauri::command]
+pub async fn ssh_list_config_hosts(path: Option<String>) -> Result<Vec<ConnectionProfile>, IpcError> {
+    let config = load_ssh_config(path).await?;
+
+    let mut profiles = Vec::new();
+    for alias in config.aliases() {
+        match config.resolve(&alias) {
+            Ok(resolved) => profiles.push(profile_from_ssh_config(&resolved)),
+            Err(e) => log::warn!("Skipping SSH config host {}: {}", alias, e),
+        }
+    }
+    Ok(profiles)
+}
+
+/// Answer (or cancel, with `responses: null`) a pending keyboard-interactive prompt.
+#[tauri::command]
+pub async fn ssh_keyboard_interactive_respond(
+    request_id: String,
+    responses: Option<Vec<String>>,
+) -> Result<(), IpcError> {
+    if keyboard_interactive::respond(&request_id, responses).await {
+        Ok(())
+    } else {
+        Err(IpcError::new(
+            "keyboard_interactive_not_pending",
+            "No keyboard-interactive prompt is waiting for this request",
+        )
+        .with_context(json!({ "requestId": request_id })))
+    }
+}
@@ -0,0 +1,82 @@
+use crate::ipc_error::IpcError;
+use crate::problems::{self, Diagnostic, MatcherKind};
+use crate::ssh::exec::{self, ExecError, ExecInfo, ExecOptions, ExecOutput};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+fn map_exec_error(error: ExecError, context: Value) -> IpcError {
+    match error {
+        ExecError::ConnectionNotFound => IpcError::new("connection_not_found", "Connection not found"),
+        ExecError::ConnectionClosed => IpcError::new("connection_closed", "Connection is closed"),
+        e @ ExecError::InvalidEnv(_) => IpcError::new("exec_invalid_env", "Invalid environment variable name")
+            .with_raw(e.to_string())
+            .with_context(context),
+        e @ ExecError::DuplicateId(_) => IpcError::new("exec_duplicate_id", "A command with this ID is already running")
+            .with_raw(e.to_string())
+            .with_context(context),
+        e => IpcError::new("exec_failed", "Could not run the command")
+            .with_raw(e.to_string())
+            .with_context(context),
+    }
+}
+
+/// Run a command without a PTY. stdout and stderr arrive separately as `exec_output` events; the
+/// exit status or signal arrives with `exec_done`.
+#[tauri::command]
+pub async fn ssh_exec_start(
+    app: AppHandle,
+    conn_id: String,
+    command: String,
+    options: Option<ExecOptions>,
+) -> Result<ExecInfo, IpcError> {
+    let context = json!({ "command": command });
+
+    exec::start(app, conn_id, command, options.unwrap_or_default())
+        .await
+        .map_err(|e| map_exec_error(e, context))
+}
+
+/// Run a command to completion and return its exit status with the collected output (up to
+/// 1 MiB per stream). A non-zero exit is not an error; check `exitCode`. Set `options.id` to be
+/// able to cancel it with `ssh_exec_cancel`.
+#[tauri::command]
+pub async fn ssh_exec_run(
+    app: AppHandle,
+    conn_id: String,
+    command: String,
+    options: Option<ExecOptions>,
+) -> Result<ExecOutput, IpcError> {
+    let context = json!({ "command": command });
+
+    exec::run(&app, &conn_id, &command, options.unwrap_or_default())
+        .await
+        .map_err(|e| map_exec_error(e, context))
+}
+
+/// Stop a command started with `ssh_exec_start`, or one `ssh_exec_run` is waiting for that was
+/// given an `id`.
+#[tauri::command]
+pub async fn ssh_exec_cancel(exec_id: String) -> Result<(), IpcError> {
+    if exec::cancel(&exec_id) {
+        Ok(())
+    } else {
+        Err(IpcError::new("exec_not_found", "Command not found").with_context(json!({ "execId": exec_id })))
+    }
+}
+
+/// Commands still running, optionally only those of one connection.
+#[tauri::command]
+pub async fn ssh_exec_list(conn_id: Option<String>) -> Result<Vec<ExecInfo>, IpcError> {
+    Ok(exec::list(conn_id.as_deref()))
+}
+
+/// Match a piece of command output (e.g. copied from a terminal) for diagnostics. `matchers`
+/// defaults to every built-in matcher; relative paths resolve against `root`.
+#[tauri::command]
+pub async fn problems_parse(
+    root: String,
+    text: String,
+    matchers: Option<Vec<MatcherKind>>,
+) -> Result<Vec<Diagnostic>, IpcError> {
+    Ok(problems::parse(&root, &matchers.unwrap_or_default(), &text))
+}
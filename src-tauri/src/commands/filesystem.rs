@@ -1,7 +1,17 @@
+use crate::encoding::{self, DetectedEncoding, EncodingError};
 use crate::ipc_error::IpcError;
 use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::SshError;
+use crate::ssh::tree;
+use crate::ssh::follow::{self, FollowError, FollowInfo};
+use crate::ssh::watch::{self, WatchInfo};
+use crate::ssh::sftp::{
+    self, CopyReport, DeleteReport, EntryType, MoveReport, RangeRead, SaveReport, SftpStat, WritePrecondition,
+};
 use crate::state::AppState;
 use crate::trace::{emit_trace, TraceEvent};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::Arc;
@@ -14,10 +24,21 @@ use tokio::sync::oneshot;
 pub struct FileEntry {
     pub name: String,
     pub path: String,
+    /// The entry itself; false for a symlink to a directory (see `target_is_directory`)
     pub is_directory: bool,
+    pub is_symlink: bool,
+    pub target_is_directory: bool,
     pub size: u64,
     pub mtime: i64,
     pub permissions: Option<String>,
+    pub file_type: EntryType,
+    pub symlink_target: Option<String>,
+    pub broken_link: bool,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub user: Option<String>,
+    pub group: Option<String>,
+    pub atime: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +47,9 @@ pub struct FileMeta {
     pub path: String,
     pub size: u64,
     pub mtime: i64,
+    /// How the file was written, for write commands
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save: Option<SaveReport>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +59,82 @@ pub struct FileReadResult {
     pub content: String,
     pub size: u64,
     pub mtime: i64,
+    /// SHA-256 of the content, for `expectedHash` on save
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileBytesResult {
+    pub path: String,
+    pub content_base64: String,
+    pub size: u64,
+    pub mtime: i64,
+    pub sha256: String,
+    pub encoding: DetectedEncoding,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTextResult {
+    pub path: String,
+    /// Decoded text, without the BOM (reported in `encoding.bom`)
+    pub content: String,
+    pub size: u64,
+    pub mtime: i64,
+    /// SHA-256 of the raw bytes as stored (not of `content`)
+    pub sha256: String,
+    pub encoding: DetectedEncoding,
+}
+
+/// A slice of a file from a ranged or tail read.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRangeResult {
+    pub path: String,
+    /// Offset of the first returned byte; for tail reads, the start of the first returned line
+    pub offset: u64,
+    pub content_base64: String,
+    /// Whole-file size and mtime when read; `size` is also the offset to follow from
+    pub size: u64,
+    pub mtime: i64,
+    /// The slice reaches the end of the file
+    pub eof: bool,
+}
+
+impl FileRangeResult {
+    fn new(path: String, read: RangeRead) -> Self {
+        Self {
+            path,
+            offset: read.offset,
+            eof: read.offset + read.data.len() as u64 >= read.size,
+            content_base64: BASE64.encode(&read.data),
+            size: read.size,
+            mtime: read.mtime,
+        }
+    }
+}
+
+fn map_write_error(error: SshError, path: &str) -> IpcError {
+    match error {
+        SshError::WriteConflict { path, current } => {
+            IpcError::new("write_conflict", "The file changed on the server since it was opened")
+                .with_context(json!({ "path": path, "current": current }))
+        }
+        other => IpcError::new("sftp_write_file_failed", "SFTP write file failed")
+            .with_raw(other.to_string())
+            .with_context(json!({ "path": path })),
+    }
+}
+
+fn map_encoding_error(error: EncodingError, path: &str) -> IpcError {
+    let code = match error {
+        EncodingError::UnknownEncoding(_) => "encoding_unknown",
+        EncodingError::Binary => "encoding_binary",
+        EncodingError::Malformed(_) => "encoding_malformed",
+        EncodingError::Unmappable(_) => "encoding_unmappable",
+    };
+    IpcError::new(code, error.to_string()).with_context(json!({ "path": path }))
 }
 
 /// List directory contents
@@ -103,9 +203,19 @@ pub async fn sftp_list_dir(
                 format!("{}/{}", path, e.name)
             },
             is_directory: e.is_directory,
+            is_symlink: e.is_symlink,
+            target_is_directory: e.target_is_directory,
             size: e.size,
             mtime: e.mtime,
             permissions: e.permissions,
+            file_type: e.file_type,
+            symlink_target: e.symlink_target,
+            broken_link: e.broken_link,
+            uid: e.uid,
+            gid: e.gid,
+            user: e.user,
+            group: e.group,
+            atime: e.atime,
         })
         .collect();
 
@@ -146,6 +256,7 @@ pub async fn sftp_read_file_with_stat(
 
     Ok(FileReadResult {
         path,
+        sha256: sftp::sha256_hex(content.as_bytes()),
         content,
         size: stat.size,
         mtime: stat.mtime,
@@ -190,7 +301,9 @@ pub async fn sftp_write_file(
     conn_id: String,
     path: String,
     content: String,
+    precondition: Option<WritePrecondition>,
 ) -> Result<FileMeta, IpcError> {
+    let precondition = precondition.unwrap_or_default();
     let tx = {
         let app_state = state.lock().await;
         app_state
@@ -202,19 +315,100 @@ pub async fn sftp_write_file(
     tx.send(ConnectionRequest::WriteFile {
         path: path.clone(),
         content,
+        precondition,
         respond_to,
     })
     .await
     .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?;
 
-    rx.await
+    let save = rx
+        .await
+        .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?
+        .map_err(|e| map_write_error(e, &path))?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(ConnectionRequest::Stat {
+        path: path.clone(),
+        respond_to,
+    })
+    .await
+    .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?;
+
+    let stat = rx
+        .await
         .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?
         .map_err(|e| {
-            IpcError::new("sftp_write_file_failed", "SFTP write file failed")
+            IpcError::new("sftp_stat_failed", "SFTP stat failed")
                 .with_raw(e.to_string())
                 .with_context(json!({ "path": path }))
         })?;
 
+    Ok(FileMeta {
+        path,
+        size: stat.size,
+        mtime: stat.mtime,
+        save: Some(save),
+    })
+}
+
+async fn read_file_bytes(
+    state: &State<'_, Arc<Mutex<AppState>>>,
+    conn_id: &str,
+    path: &str,
+) -> Result<(Vec<u8>, SftpStat), IpcError> {
+    let tx = {
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(conn_id)
+            .ok_or_else(|| IpcError::new("connection_not_found", "Connection not found"))?
+    };
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(ConnectionRequest::ReadFileBytes {
+        path: path.to_string(),
+        respond_to,
+    })
+    .await
+    .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?;
+
+    rx.await
+        .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?
+        .map_err(|e| {
+            IpcError::new("sftp_read_file_failed", "SFTP read file failed")
+                .with_raw(e.to_string())
+                .with_context(json!({ "path": path }))
+        })
+}
+
+async fn write_file_bytes(
+    state: &State<'_, Arc<Mutex<AppState>>>,
+    conn_id: &str,
+    path: String,
+    content: Vec<u8>,
+    precondition: WritePrecondition,
+) -> Result<FileMeta, IpcError> {
+    let tx = {
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(conn_id)
+            .ok_or_else(|| IpcError::new("connection_not_found", "Connection not found"))?
+    };
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(ConnectionRequest::WriteFileBytes {
+        path: path.clone(),
+        content,
+        precondition,
+        respond_to,
+    })
+    .await
+    .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?;
+
+    let save = rx
+        .await
+        .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?
+        .map_err(|e| map_write_error(e, &path))?;
+
     let (respond_to, rx) = oneshot::channel();
     tx.send(ConnectionRequest::Stat {
         path: path.clone(),
@@ -236,9 +430,88 @@ pub async fn sftp_write_file(
         path,
         size: stat.size,
         mtime: stat.mtime,
+        save: Some(save),
     })
 }
 
+/// Read a file as raw bytes (base64), with its stat and a best-guess encoding.
+#[tauri::command]
+pub async fn sftp_read_file_bytes(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+) -> Result<FileBytesResult, IpcError> {
+    let (content, stat) = read_file_bytes(&state, &conn_id, &path).await?;
+
+    Ok(FileBytesResult {
+        encoding: encoding::detect(&content),
+        sha256: sftp::sha256_hex(&content),
+        content_base64: BASE64.encode(&content),
+        path,
+        size: stat.size,
+        mtime: stat.mtime,
+    })
+}
+
+/// Read a file as text in any encoding. `encoding` overrides detection (WHATWG labels such as
+/// `utf-8`, `latin1`, `utf-16le`); binary files are rejected unless an encoding is given.
+#[tauri::command]
+pub async fn sftp_read_file_text(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    encoding: Option<String>,
+) -> Result<FileTextResult, IpcError> {
+    let (bytes, stat) = read_file_bytes(&state, &conn_id, &path).await?;
+    let (content, encoding) =
+        encoding::decode(&bytes, encoding.as_deref()).map_err(|e| map_encoding_error(e, &path))?;
+
+    Ok(FileTextResult {
+        path,
+        content,
+        size: stat.size,
+        mtime: stat.mtime,
+        sha256: sftp::sha256_hex(&bytes),
+        encoding,
+    })
+}
+
+/// Write raw bytes (base64) to a file
+#[tauri::command]
+pub async fn sftp_write_file_bytes(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    content_base64: String,
+    precondition: Option<WritePrecondition>,
+) -> Result<FileMeta, IpcError> {
+    let content = BASE64.decode(content_base64.trim()).map_err(|e| {
+        IpcError::new("invalid_base64", "File content is not valid base64")
+            .with_raw(e.to_string())
+            .with_context(json!({ "path": path }))
+    })?;
+
+    write_file_bytes(&state, &conn_id, path, content, precondition.unwrap_or_default()).await
+}
+
+/// Write text in the given encoding, optionally with a BOM, so files keep the encoding they
+/// were opened with.
+#[tauri::command]
+pub async fn sftp_write_file_text(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    content: String,
+    encoding: String,
+    bom: Option<bool>,
+    precondition: Option<WritePrecondition>,
+) -> Result<FileMeta, IpcError> {
+    let bytes = encoding::encode(&content, &encoding, bom.unwrap_or(false))
+        .map_err(|e| map_encoding_error(e, &path))?;
+
+    write_file_bytes(&state, &conn_id, path, bytes, precondition.unwrap_or_default()).await
+}
+
 /// Get file metadata
 #[tauri::command]
 pub async fn sftp_stat(
@@ -274,6 +547,7 @@ pub async fn sftp_stat(
         path,
         size: stat.size,
         mtime: stat.mtime,
+        save: None,
     })
 }
 
@@ -402,3 +676,351 @@ pub async fn sftp_rename(
                 .with_context(json!({ "oldPath": old_path, "newPath": new_path }))
         })
 }
+
+/// Send a metadata-changing request and map its failure to `code`.
+async fn send_attribute_request(
+    state: &State<'_, Arc<Mutex<AppState>>>,
+    conn_id: &str,
+    path: &str,
+    code: &str,
+    message: &str,
+    request: impl FnOnce(oneshot::Sender<Result<(), SshError>>) -> ConnectionRequest,
+) -> Result<(), IpcError> {
+    let tx = {
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(conn_id)
+            .ok_or_else(|| IpcError::new("connection_not_found", "Connection not found"))?
+    };
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?;
+
+    rx.await
+        .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?
+        .map_err(|e| {
+            IpcError::new(code, message)
+                .with_raw(e.to_string())
+                .with_context(json!({ "path": path }))
+        })
+}
+
+/// Set the permission bits of a file or directory (e.g. `0o755`)
+#[tauri::command]
+pub async fn sftp_chmod(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    mode: u32,
+) -> Result<(), IpcError> {
+    send_attribute_request(&state, &conn_id, &path, "sftp_chmod_failed", "SFTP chmod failed", |respond_to| {
+        ConnectionRequest::Chmod {
+            path: path.clone(),
+            mode,
+            respond_to,
+        }
+    })
+    .await
+}
+
+/// Change the owner and/or group of a file or directory by numeric id
+#[tauri::command]
+pub async fn sftp_chown(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> Result<(), IpcError> {
+    send_attribute_request(&state, &conn_id, &path, "sftp_chown_failed", "SFTP chown failed", |respond_to| {
+        ConnectionRequest::Chown {
+            path: path.clone(),
+            uid,
+            gid,
+            respond_to,
+        }
+    })
+    .await
+}
+
+/// Set access and modification times (now, unless `mtime` is given), creating the file if needed
+#[tauri::command]
+pub async fn sftp_touch(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    mtime: Option<i64>,
+) -> Result<(), IpcError> {
+    send_attribute_request(&state, &conn_id, &path, "sftp_touch_failed", "SFTP touch failed", |respond_to| {
+        ConnectionRequest::Touch {
+            path: path.clone(),
+            mtime,
+            respond_to,
+        }
+    })
+    .await
+}
+
+/// Create a symlink at `path` pointing to `target`
+#[tauri::command]
+pub async fn sftp_create_symlink(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    target: String,
+) -> Result<(), IpcError> {
+    send_attribute_request(
+        &state,
+        &conn_id,
+        &path,
+        "sftp_create_symlink_failed",
+        "SFTP create symlink failed",
+        |respond_to| ConnectionRequest::CreateSymlink {
+            path: path.clone(),
+            target,
+            respond_to,
+        },
+    )
+    .await
+}
+
+fn map_tree_error(error: SshError, code: &str, message: &str, context: serde_json::Value) -> IpcError {
+    match error {
+        SshError::TargetExists(path) => IpcError::new("target_exists", "The target path already exists")
+            .with_context(json!({ "path": path })),
+        SshError::Cancelled => IpcError::new("cancelled", "The operation was cancelled").with_context(context),
+        SshError::MoveIncomplete { from, to, detail } => IpcError::new(
+            "move_incomplete",
+            "The target was copied but the source could not be fully deleted",
+        )
+        .with_raw(detail)
+        .with_context(json!({ "fromPath": from, "toPath": to })),
+        other => IpcError::new(code, message)
+            .with_raw(other.to_string())
+            .with_context(context),
+    }
+}
+
+/// Delete a file or a directory with everything in it. With `dryRun`, nothing is deleted and the
+/// report lists what would be. Pass an `operationId` to be able to stop it with
+/// `sftp_delete_cancel`.
+#[tauri::command]
+pub async fn sftp_delete_recursive(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    dry_run: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<DeleteReport, IpcError> {
+    let tx = {
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(&conn_id)
+            .ok_or_else(|| IpcError::new("connection_not_found", "Connection not found"))?
+    };
+
+    tree::delete(&tx, &path, dry_run.unwrap_or(false), operation_id.as_deref())
+        .await
+        .map_err(|e| {
+            map_tree_error(e, "sftp_delete_failed", "SFTP delete failed", json!({ "path": path }))
+        })
+}
+
+/// Stop a recursive delete started with an `operationId`. It fails with `cancelled`, leaving
+/// whatever was not removed yet.
+#[tauri::command]
+pub async fn sftp_delete_cancel(operation_id: String) -> Result<(), IpcError> {
+    if tree::cancel_delete(&operation_id) {
+        Ok(())
+    } else {
+        Err(IpcError::new("operation_not_found", "Delete not found")
+            .with_context(json!({ "operationId": operation_id })))
+    }
+}
+
+/// Copy a file or directory tree to a path that does not exist yet
+#[tauri::command]
+pub async fn sftp_copy(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    from_path: String,
+    to_path: String,
+) -> Result<CopyReport, IpcError> {
+    let tx = {
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(&conn_id)
+            .ok_or_else(|| IpcError::new("connection_not_found", "Connection not found"))?
+    };
+
+    tree::copy(&tx, &from_path, &to_path)
+        .await
+        .map_err(|e| {
+            map_tree_error(
+                e,
+                "sftp_copy_failed",
+                "SFTP copy failed",
+                json!({ "fromPath": from_path, "toPath": to_path }),
+            )
+        })
+}
+
+/// Move a file or directory tree to a path that does not exist yet, also across filesystems
+#[tauri::command]
+pub async fn sftp_move(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    from_path: String,
+    to_path: String,
+) -> Result<MoveReport, IpcError> {
+    let tx = {
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(&conn_id)
+            .ok_or_else(|| IpcError::new("connection_not_found", "Connection not found"))?
+    };
+
+    tree::move_path(&tx, &from_path, &to_path)
+        .await
+        .map_err(|e| {
+            map_tree_error(
+                e,
+                "sftp_move_failed",
+                "SFTP move failed",
+                json!({ "fromPath": from_path, "toPath": to_path }),
+            )
+        })
+}
+
+async fn send_range_request(
+    state: &State<'_, Arc<Mutex<AppState>>>,
+    conn_id: &str,
+    path: &str,
+    request: impl FnOnce(oneshot::Sender<Result<RangeRead, SshError>>) -> ConnectionRequest,
+) -> Result<FileRangeResult, IpcError> {
+    let tx = {
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(conn_id)
+            .ok_or_else(|| IpcError::new("connection_not_found", "Connection not found"))?
+    };
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?;
+
+    let read = rx
+        .await
+        .map_err(|_| IpcError::new("connection_closed", "Connection is closed"))?
+        .map_err(|e| {
+            IpcError::new("sftp_read_file_failed", "SFTP read file failed")
+                .with_raw(e.to_string())
+                .with_context(json!({ "path": path }))
+        })?;
+
+    Ok(FileRangeResult::new(path.to_string(), read))
+}
+
+/// Read `len` bytes at `offset` without fetching the rest of the file (at most 4 MiB per call).
+#[tauri::command]
+pub async fn sftp_read_range(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    offset: u64,
+    len: u64,
+) -> Result<FileRangeResult, IpcError> {
+    send_range_request(&state, &conn_id, &path, |respond_to| ConnectionRequest::ReadRange {
+        path: path.clone(),
+        offset,
+        len,
+        respond_to,
+    })
+    .await
+}
+
+/// Read the last `lines` lines of a file by seeking backwards from EOF.
+#[tauri::command]
+pub async fn sftp_read_tail(
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    path: String,
+    lines: usize,
+) -> Result<FileRangeResult, IpcError> {
+    send_range_request(&state, &conn_id, &path, |respond_to| ConnectionRequest::ReadTail {
+        path: path.clone(),
+        lines,
+        respond_to,
+    })
+    .await
+}
+
+/// Push bytes appended to a file as `file_follow_data` events. `offset` defaults to the current
+/// end; pass a tail read's `size` to continue it without a gap.
+#[tauri::command]
+pub async fn sftp_follow_start(
+    app: AppHandle,
+    conn_id: String,
+    path: String,
+    offset: Option<u64>,
+) -> Result<FollowInfo, IpcError> {
+    follow::start(app, conn_id, path.clone(), offset)
+        .await
+        .map_err(|e| match e {
+            FollowError::ConnectionNotFound => IpcError::new("connection_not_found", "Connection not found"),
+            FollowError::ConnectionClosed => IpcError::new("connection_closed", "Connection is closed"),
+            FollowError::Ssh(e) => IpcError::new("sftp_stat_failed", "SFTP stat failed")
+                .with_raw(e.to_string())
+                .with_context(json!({ "path": path })),
+        })
+}
+
+/// Stop following a file.
+#[tauri::command]
+pub async fn sftp_follow_stop(follow_id: String) -> Result<FollowInfo, IpcError> {
+    follow::stop(&follow_id).ok_or_else(|| {
+        IpcError::new("follow_not_found", "File follower not found")
+            .with_context(json!({ "followId": follow_id }))
+    })
+}
+
+/// List active file followers, optionally for a single connection.
+#[tauri::command]
+pub async fn sftp_follow_list(conn_id: Option<String>) -> Result<Vec<FollowInfo>, IpcError> {
+    Ok(follow::list(conn_id.as_deref()))
+}
+
+/// Watch directories (their entries) and files for outside changes, replacing the connection's
+/// previous set. Changes arrive as `fs_changed` events.
+#[tauri::command]
+pub async fn sftp_watch_set(
+    app: AppHandle,
+    state: State<'_, Arc<Mutex<AppState>>>,
+    conn_id: String,
+    directories: Vec<String>,
+    files: Vec<String>,
+) -> Result<WatchInfo, IpcError> {
+    {
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(&conn_id)
+            .ok_or_else(|| IpcError::new("connection_not_found", "Connection not found"))?;
+    }
+
+    Ok(watch::set(app, conn_id, directories, files))
+}
+
+/// Stop watching for a connection. Stopping when nothing is watched is not an error.
+#[tauri::command]
+pub async fn sftp_watch_stop(conn_id: String) -> Result<Option<WatchInfo>, IpcError> {
+    Ok(watch::stop(&conn_id))
+}
+
+/// What is watched for a connection and how (`inotify` or `poll`).
+#[tauri::command]
+pub async fn sftp_watch_status(conn_id: String) -> Result<Option<WatchInfo>, IpcError> {
+    Ok(watch::status(&conn_id))
+}
@@ -0,0 +1,109 @@
+use crate::ipc_error::IpcError;
+use crate::ssh::client::SshError;
+use crate::ssh::forward::{self, ForwardError, ForwardInfo};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+fn default_loopback(host: Option<String>) -> String {
+    host.filter(|h| !h.trim().is_empty())
+        .unwrap_or_else(|| "127.0.0.1".to_string())
+}
+
+fn map_forward_error(error: ForwardError, context: Value) -> IpcError {
+    match error {
+        ForwardError::ConnectionNotFound => IpcError::new("connection_not_found", "Connection not found"),
+        ForwardError::ConnectionClosed => IpcError::new("connection_closed", "Connection is closed"),
+        ForwardError::Bind(e) => IpcError::new("forward_bind_failed", "Could not listen on the local port")
+            .with_raw(e.to_string())
+            .with_context(context),
+        ForwardError::Ssh(e @ SshError::ForwardRejected(_)) => {
+            IpcError::new("forward_rejected", "The server refused the port forward")
+                .with_raw(e.to_string())
+                .with_context(context)
+        }
+        ForwardError::Ssh(e) => IpcError::new("forward_failed", "Port forward failed")
+            .with_raw(e.to_string())
+            .with_context(context),
+    }
+}
+
+/// Open a local port forward (`ssh -L bind_host:bind_port:remote_host:remote_port`) on a connection.
+/// `bind_port` 0 picks a free port; the actual port is in the returned info.
+#[tauri::command]
+pub async fn ssh_forward_local_open(
+    app: AppHandle,
+    conn_id: String,
+    bind_host: Option<String>,
+    bind_port: u16,
+    remote_host: String,
+    remote_port: u16,
+) -> Result<ForwardInfo, IpcError> {
+    let bind_host = default_loopback(bind_host);
+    let context = json!({
+        "bindHost": bind_host,
+        "bindPort": bind_port,
+        "remoteHost": remote_host,
+        "remotePort": remote_port,
+    });
+
+    forward::open_local(app, conn_id, bind_host, bind_port, remote_host, remote_port)
+        .await
+        .map_err(|e| map_forward_error(e, context))
+}
+
+/// Open a remote port forward (`ssh -R bind_host:bind_port:local_host:local_port`): the server
+/// listens and connections are relayed to `local_host:local_port` on this machine.
+/// `bind_host` defaults to the server's loopback; `bind_port` 0 lets the server pick.
+#[tauri::command]
+pub async fn ssh_forward_remote_open(
+    app: AppHandle,
+    conn_id: String,
+    bind_host: Option<String>,
+    bind_port: u16,
+    local_host: Option<String>,
+    local_port: u16,
+) -> Result<ForwardInfo, IpcError> {
+    let bind_host = default_loopback(bind_host);
+    let local_host = default_loopback(local_host);
+    let context = json!({
+        "bindHost": bind_host,
+        "bindPort": bind_port,
+        "localHost": local_host,
+        "localPort": local_port,
+    });
+
+    forward::open_remote(app, conn_id, bind_host, bind_port, local_host, local_port)
+        .await
+        .map_err(|e| map_forward_error(e, context))
+}
+
+/// Start a SOCKS5 proxy (`ssh -D bind_host:bind_port`) that tunnels every CONNECT through the server.
+#[tauri::command]
+pub async fn ssh_forward_dynamic_open(
+    app: AppHandle,
+    conn_id: String,
+    bind_host: Option<String>,
+    bind_port: u16,
+) -> Result<ForwardInfo, IpcError> {
+    let bind_host = default_loopback(bind_host);
+    let context = json!({ "bindHost": bind_host, "bindPort": bind_port });
+
+    forward::open_dynamic(app, conn_id, bind_host, bind_port)
+        .await
+        .map_err(|e| map_forward_error(e, context))
+}
+
+/// List port forwards, optionally for a single connection, with live byte counters.
+#[tauri::command]
+pub async fn ssh_forward_list(conn_id: Option<String>) -> Result<Vec<ForwardInfo>, IpcError> {
+    Ok(forward::list(conn_id.as_deref()))
+}
+
+/// Close a port forward and any connections tunnelled through it.
+#[tauri::command]
+pub async fn ssh_forward_close(app: AppHandle, forward_id: String) -> Result<ForwardInfo, IpcError> {
+    forward::close(&app, &forward_id).await.ok_or_else(|| {
+        IpcError::new("forward_not_found", "Port forward not found")
+            .with_context(json!({ "forwardId": forward_id }))
+    })
+}
@@ -0,0 +1,118 @@
+use crate::ipc_error::IpcError;
+use crate::ssh::git::{self, FileDiff, GitCommit, GitError, GitStash, GitStatus, StashAction};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+const DEFAULT_LOG_LIMIT: u32 = 100;
+
+fn map_git_error(error: GitError, context: Value) -> IpcError {
+    match error {
+        GitError::ConnectionNotFound => IpcError::new("connection_not_found", "Connection not found"),
+        GitError::ConnectionClosed => IpcError::new("connection_closed", "Connection is closed"),
+        e @ GitError::NotARepository(_) => IpcError::new("git_not_repository", "Not a git repository")
+            .with_raw(e.to_string())
+            .with_context(context),
+        e => IpcError::new("git_failed", "git command failed")
+            .with_raw(e.to_string())
+            .with_context(context),
+    }
+}
+
+/// Status of the repository containing `root`, with a decoration per changed file. Also emitted
+/// as a `git_status` event.
+#[tauri::command]
+pub async fn git_status(app: AppHandle, conn_id: String, root: String) -> Result<GitStatus, IpcError> {
+    git::status(&app, &conn_id, &root)
+        .await
+        .map_err(|e| map_git_error(e, json!({ "root": root })))
+}
+
+/// Unstaged changes (staged ones with `staged`), parsed into files and hunks.
+#[tauri::command]
+pub async fn git_diff(
+    app: AppHandle,
+    conn_id: String,
+    root: String,
+    paths: Option<Vec<String>>,
+    staged: Option<bool>,
+) -> Result<Vec<FileDiff>, IpcError> {
+    let paths = paths.unwrap_or_default();
+    git::diff(&app, &conn_id, &root, &paths, staged.unwrap_or(false))
+        .await
+        .map_err(|e| map_git_error(e, json!({ "root": root, "paths": paths })))
+}
+
+/// Commit history, newest first. `limit` defaults to 100.
+#[tauri::command]
+pub async fn git_log(
+    app: AppHandle,
+    conn_id: String,
+    root: String,
+    limit: Option<u32>,
+    skip: Option<u32>,
+    path: Option<String>,
+) -> Result<Vec<GitCommit>, IpcError> {
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT);
+    git::log(&app, &conn_id, &root, limit, skip.unwrap_or(0), path.as_deref())
+        .await
+        .map_err(|e| map_git_error(e, json!({ "root": root, "path": path })))
+}
+
+/// Stage files (or everything with `all`); returns the new status.
+#[tauri::command]
+pub async fn git_add(
+    app: AppHandle,
+    conn_id: String,
+    root: String,
+    paths: Option<Vec<String>>,
+    all: Option<bool>,
+) -> Result<GitStatus, IpcError> {
+    let paths = paths.unwrap_or_default();
+    git::add(&app, &conn_id, &root, &paths, all.unwrap_or(false))
+        .await
+        .map_err(|e| map_git_error(e, json!({ "root": root, "paths": paths })))
+}
+
+/// Commit the index; returns the new commit.
+#[tauri::command]
+pub async fn git_commit(
+    app: AppHandle,
+    conn_id: String,
+    root: String,
+    message: String,
+    amend: Option<bool>,
+    all: Option<bool>,
+) -> Result<GitCommit, IpcError> {
+    git::commit(&app, &conn_id, &root, message, amend.unwrap_or(false), all.unwrap_or(false))
+        .await
+        .map_err(|e| map_git_error(e, json!({ "root": root })))
+}
+
+/// Switch branches (`create` makes a new one), or restore `paths` from `target` or the index.
+#[tauri::command]
+pub async fn git_checkout(
+    app: AppHandle,
+    conn_id: String,
+    root: String,
+    target: Option<String>,
+    create: Option<bool>,
+    paths: Option<Vec<String>>,
+) -> Result<GitStatus, IpcError> {
+    let paths = paths.unwrap_or_default();
+    git::checkout(&app, &conn_id, &root, target.as_deref(), create.unwrap_or(false), &paths)
+        .await
+        .map_err(|e| map_git_error(e, json!({ "root": root, "target": target, "paths": paths })))
+}
+
+/// `list`, `push`, `pop`, `apply` or `drop` a stash; returns the stash list afterwards.
+#[tauri::command]
+pub async fn git_stash(
+    app: AppHandle,
+    conn_id: String,
+    root: String,
+    action: StashAction,
+) -> Result<Vec<GitStash>, IpcError> {
+    git::stash(&app, &conn_id, &root, action)
+        .await
+        .map_err(|e| map_git_error(e, json!({ "root": root })))
+}
@@ -0,0 +1,59 @@
+use crate::ipc_error::IpcError;
+use crate::ssh::lsp::{self, LspError, LspInfo};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+fn map_lsp_error(error: LspError, context: Value) -> IpcError {
+    match error {
+        LspError::ConnectionNotFound => IpcError::new("connection_not_found", "Connection not found"),
+        LspError::ConnectionClosed => IpcError::new("connection_closed", "Connection is closed"),
+        LspError::NotFound => IpcError::new("lsp_not_found", "Language server not found").with_context(context),
+        LspError::NotRunning => IpcError::new("lsp_not_running", "Language server is not running").with_context(context),
+        e => IpcError::new("lsp_failed", "Language server failed")
+            .with_raw(e.to_string())
+            .with_context(context),
+    }
+}
+
+/// Start a language server in `root` on the remote host. `server` is a preset
+/// (`rust-analyzer`, `pyright`, `gopls`) or a command line speaking LSP on stdio. Its messages
+/// arrive as `lsp_message` events, stderr as `lsp_log` and state changes as `lsp_status`.
+#[tauri::command]
+pub async fn lsp_start(app: AppHandle, conn_id: String, root: String, server: String) -> Result<LspInfo, IpcError> {
+    let context = json!({ "root": root, "server": server });
+
+    lsp::start(app, conn_id, root, server)
+        .await
+        .map_err(|e| map_lsp_error(e, context))
+}
+
+/// Send a JSON-RPC message to a server. `driftcode://<connectionId>/path` URIs are rewritten to
+/// `file:///path`.
+#[tauri::command]
+pub async fn lsp_send(server_id: String, message: Value) -> Result<(), IpcError> {
+    lsp::send(&server_id, message)
+        .await
+        .map_err(|e| map_lsp_error(e, json!({ "serverId": server_id })))
+}
+
+/// Start a server again; the frontend has to initialize it anew.
+#[tauri::command]
+pub async fn lsp_restart(app: AppHandle, server_id: String) -> Result<LspInfo, IpcError> {
+    lsp::restart(app, &server_id)
+        .await
+        .map_err(|e| map_lsp_error(e, json!({ "serverId": server_id })))
+}
+
+/// Stop a server.
+#[tauri::command]
+pub async fn lsp_stop(server_id: String) -> Result<LspInfo, IpcError> {
+    lsp::stop(&server_id).ok_or_else(|| {
+        IpcError::new("lsp_not_found", "Language server not found").with_context(json!({ "serverId": server_id }))
+    })
+}
+
+/// Language servers, optionally only those of one connection.
+#[tauri::command]
+pub async fn lsp_list(conn_id: Option<String>) -> Result<Vec<LspInfo>, IpcError> {
+    Ok(lsp::list(conn_id.as_deref()))
+}
@@ -1,5 +1,12 @@
 pub mod connection;
 pub mod android_persistence;
 pub mod debug;
+pub mod exec;
 pub mod filesystem;
+pub mod forward;
+pub mod git;
+pub mod lsp;
+pub mod search;
+pub mod tasks;
 pub mod terminal;
+pub mod transfer;
@@ -0,0 +1,44 @@
+use crate::ipc_error::IpcError;
+use crate::ssh::search::{self, SearchError, SearchInfo, SearchOptions};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+fn map_search_error(error: SearchError, context: Value) -> IpcError {
+    match error {
+        SearchError::ConnectionNotFound => IpcError::new("connection_not_found", "Connection not found"),
+        SearchError::ConnectionClosed => IpcError::new("connection_closed", "Connection is closed"),
+        e @ SearchError::InvalidPattern(_) => IpcError::new("search_invalid_pattern", "Invalid search pattern")
+            .with_raw(e.to_string())
+            .with_context(context),
+        e => IpcError::new("search_failed", "Search failed")
+            .with_raw(e.to_string())
+            .with_context(context),
+    }
+}
+
+/// Search file contents under `root`. Matches arrive in batches as `search_results` events and
+/// the search ends with a `search_done` event carrying the final `SearchInfo`. Uses ripgrep when
+/// installed, then grep, then a scan over SFTP.
+#[tauri::command]
+pub async fn sftp_search_start(
+    app: AppHandle,
+    conn_id: String,
+    root: String,
+    options: SearchOptions,
+) -> Result<SearchInfo, IpcError> {
+    let context = json!({ "root": root, "query": options.query });
+
+    search::start(app, conn_id, root, options)
+        .await
+        .map_err(|e| map_search_error(e, context))
+}
+
+/// Cancel a running search.
+#[tauri::command]
+pub async fn sftp_search_cancel(search_id: String) -> Result<(), IpcError> {
+    if search::cancel(&search_id) {
+        Ok(())
+    } else {
+        Err(IpcError::new("search_not_found", "Search not found").with_context(json!({ "searchId": search_id })))
+    }
+}
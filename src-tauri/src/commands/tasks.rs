@@ -0,0 +1,71 @@
+use crate::ipc_error::IpcError;
+use crate::ssh::tasks::{self, TaskDef, TaskError, TaskRun};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+fn map_task_error(error: TaskError, context: Value) -> IpcError {
+    match error {
+        TaskError::ConnectionNotFound => IpcError::new("connection_not_found", "Connection not found"),
+        TaskError::ConnectionClosed => IpcError::new("connection_closed", "Connection is closed"),
+        e @ TaskError::NotFound(_) => IpcError::new("task_not_found", "Task not found")
+            .with_raw(e.to_string())
+            .with_context(context),
+        e @ TaskError::Ambiguous(..) => IpcError::new("task_ambiguous", "Task name matches several tasks")
+            .with_raw(e.to_string())
+            .with_context(context),
+        TaskError::NoLastTask => IpcError::new("task_no_last", "No task has been run yet").with_context(context),
+        e => IpcError::new("task_failed", "Could not run the task")
+            .with_raw(e.to_string())
+            .with_context(context),
+    }
+}
+
+/// Tasks declared in the project root by a Makefile, package.json, Cargo.toml or justfile.
+#[tauri::command]
+pub async fn task_list(app: AppHandle, conn_id: String, root: String) -> Result<Vec<TaskDef>, IpcError> {
+    tasks::discover(&app, &conn_id, &root)
+        .await
+        .map_err(|e| map_task_error(e, json!({ "root": root })))
+}
+
+/// Run a task by ID (`npm:test`) or unique name. Output arrives as `exec_output` events for the
+/// returned run ID, and the exit code with `exec_done`.
+#[tauri::command]
+pub async fn task_run(
+    app: AppHandle,
+    conn_id: String,
+    root: String,
+    task: String,
+    env: Option<BTreeMap<String, String>>,
+) -> Result<TaskRun, IpcError> {
+    let context = json!({ "root": root, "task": task });
+
+    tasks::run(app, conn_id, root, &task, env.unwrap_or_default())
+        .await
+        .map_err(|e| map_task_error(e, context))
+}
+
+/// Run the connection's last task again.
+#[tauri::command]
+pub async fn task_rerun(app: AppHandle, conn_id: String) -> Result<TaskRun, IpcError> {
+    tasks::rerun(app, conn_id)
+        .await
+        .map_err(|e| map_task_error(e, Value::Null))
+}
+
+/// Tasks still running, optionally only those of one connection.
+#[tauri::command]
+pub async fn task_running(conn_id: Option<String>) -> Result<Vec<TaskRun>, IpcError> {
+    Ok(tasks::running(conn_id.as_deref()))
+}
+
+/// Stop a running task by run ID.
+#[tauri::command]
+pub async fn task_kill(run_id: String) -> Result<(), IpcError> {
+    if tasks::kill(&run_id) {
+        Ok(())
+    } else {
+        Err(IpcError::new("task_not_running", "Task is not running").with_context(json!({ "runId": run_id })))
+    }
+}
@@ -0,0 +1,113 @@
+use crate::ipc_error::IpcError;
+use crate::ssh::transfer::{self, ConflictPolicy, TransferError, TransferInfo, TransferOptions};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+fn transfer_options(conflict: Option<ConflictPolicy>, preserve_mtime: Option<bool>) -> TransferOptions {
+    let defaults = TransferOptions::default();
+    TransferOptions {
+        conflict: conflict.unwrap_or(defaults.conflict),
+        preserve_mtime: preserve_mtime.unwrap_or(defaults.preserve_mtime),
+    }
+}
+
+fn map_transfer_error(error: TransferError, context: Value) -> IpcError {
+    match error {
+        TransferError::ConnectionNotFound => IpcError::new("connection_not_found", "Connection not found"),
+        TransferError::ConnectionClosed => IpcError::new("connection_closed", "Connection is closed"),
+        TransferError::NotFound => IpcError::new("transfer_not_found", "Transfer not found").with_context(context),
+        e @ TransferError::NotResumable(_) => {
+            IpcError::new("transfer_not_resumable", "Only failed or cancelled transfers can be resumed")
+                .with_raw(e.to_string())
+                .with_context(context)
+        }
+        e @ TransferError::NoFreeName(_) => {
+            IpcError::new("transfer_conflict_unresolved", "Could not find a free name for a conflicting file")
+                .with_raw(e.to_string())
+                .with_context(context)
+        }
+        e @ TransferError::Local(_) => IpcError::new("transfer_local_io_failed", "Could not access the local file")
+            .with_raw(e.to_string())
+            .with_context(context),
+        e => IpcError::new("transfer_failed", "Transfer failed")
+            .with_raw(e.to_string())
+            .with_context(context),
+    }
+}
+
+/// Download a remote file, or a directory recursively, to `local_path`; progress arrives as
+/// `transfer_progress` events. `conflict` (`overwrite`, `skip`, `rename`) applies per file and
+/// defaults to `overwrite`; `preserveMtime` defaults to true. `offset` continues a partial
+/// single-file download already at `local_path` (clamped to its length).
+#[tauri::command]
+pub async fn sftp_transfer_download(
+    app: AppHandle,
+    conn_id: String,
+    remote_path: String,
+    local_path: String,
+    offset: Option<u64>,
+    conflict: Option<ConflictPolicy>,
+    preserve_mtime: Option<bool>,
+) -> Result<TransferInfo, IpcError> {
+    let context = json!({ "remotePath": remote_path, "localPath": local_path });
+    let options = transfer_options(conflict, preserve_mtime);
+
+    transfer::start_download(app, conn_id, remote_path, local_path, offset.unwrap_or(0), options)
+        .await
+        .map_err(|e| map_transfer_error(e, context))
+}
+
+/// Upload a local file, or a directory recursively, to `remote_path`. Options as for
+/// [`sftp_transfer_download`]; `offset` continues a partial single-file upload already at
+/// `remote_path` (clamped to its size).
+#[tauri::command]
+pub async fn sftp_transfer_upload(
+    app: AppHandle,
+    conn_id: String,
+    local_path: String,
+    remote_path: String,
+    offset: Option<u64>,
+    conflict: Option<ConflictPolicy>,
+    preserve_mtime: Option<bool>,
+) -> Result<TransferInfo, IpcError> {
+    let context = json!({ "remotePath": remote_path, "localPath": local_path });
+    let options = transfer_options(conflict, preserve_mtime);
+
+    transfer::start_upload(app, conn_id, local_path, remote_path, offset.unwrap_or(0), options)
+        .await
+        .map_err(|e| map_transfer_error(e, context))
+}
+
+/// Continue a failed or cancelled transfer on the connection's current session: finished files
+/// are not copied again and the interrupted one continues from the bytes it already committed.
+#[tauri::command]
+pub async fn sftp_transfer_resume(app: AppHandle, transfer_id: String) -> Result<TransferInfo, IpcError> {
+    let context = json!({ "transferId": transfer_id });
+
+    transfer::resume(app, &transfer_id)
+        .await
+        .map_err(|e| map_transfer_error(e, context))
+}
+
+/// Stop a running transfer. The partial destination is kept so the transfer can be resumed.
+#[tauri::command]
+pub async fn sftp_transfer_cancel(transfer_id: String) -> Result<TransferInfo, IpcError> {
+    transfer::cancel(&transfer_id).ok_or_else(|| {
+        IpcError::new("transfer_not_found", "Transfer not found")
+            .with_context(json!({ "transferId": transfer_id }))
+    })
+}
+
+/// List transfers, optionally for a single connection. Completed transfers are dropped after their
+/// final `transfer_progress` event; failed and cancelled ones are listed until resumed or cleared.
+#[tauri::command]
+pub async fn sftp_transfer_list(conn_id: Option<String>) -> Result<Vec<TransferInfo>, IpcError> {
+    Ok(transfer::list(conn_id.as_deref()))
+}
+
+/// Forget failed and cancelled transfers, optionally for a single connection; they can no longer
+/// be resumed. Returns the IDs removed.
+#[tauri::command]
+pub async fn sftp_transfer_clear(conn_id: Option<String>) -> Result<Vec<String>, IpcError> {
+    Ok(transfer::clear(conn_id.as_deref()))
+}
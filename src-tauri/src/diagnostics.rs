@@ -95,6 +95,8 @@ pub fn export() -> Value {
             "os": std::env::consts::OS,
             "arch": std::env::consts::ARCH,
         },
+        "forwards": crate::ssh::forward::list(None),
+        "transfers": crate::ssh::transfer::list(None),
         "panics": guard.panics.iter().cloned().collect::<Vec<_>>(),
         "connectAttempts": guard.connect_attempts.iter().cloned().collect::<Vec<_>>(),
         "traces": guard.traces.iter().cloned().collect::<Vec<_>>(),
@@ -0,0 +1,136 @@
+//! Text encoding detection and round-tripping for remote files.
+//!
+//! Detection order: a BOM wins; otherwise valid UTF-8 is UTF-8; otherwise content that looks like
+//! text is treated as windows-1252 (the WHATWG superset of Latin-1); anything else is binary.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use serde::Serialize;
+use thiserror::Error;
+
+/// How many leading bytes are inspected when deciding whether content is binary.
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+#[derive(Debug, Error)]
+pub enum EncodingError {
+    #[error("Unknown encoding: {0}")]
+    UnknownEncoding(String),
+    #[error("File looks binary; pick an encoding explicitly or open it as bytes")]
+    Binary,
+    #[error("Content is not valid {0}")]
+    Malformed(String),
+    #[error("Text contains characters that cannot be represented in {0}")]
+    Unmappable(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedEncoding {
+    /// WHATWG encoding name (`UTF-8`, `UTF-16LE`, `windows-1252`, ...); `None` for binary content.
+    pub charset: Option<String>,
+    /// Whether the content starts with a byte order mark for `charset`.
+    pub bom: bool,
+    pub binary: bool,
+}
+
+/// Guess the encoding of `bytes`.
+pub fn detect(bytes: &[u8]) -> DetectedEncoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return DetectedEncoding {
+            charset: Some(encoding.name().to_string()),
+            bom: true,
+            binary: false,
+        };
+    }
+
+    let sample = &bytes[..bytes.len().min(BINARY_SNIFF_LEN)];
+    if looks_binary(sample) {
+        return DetectedEncoding {
+            charset: None,
+            bom: false,
+            binary: true,
+        };
+    }
+
+    let encoding = if std::str::from_utf8(bytes).is_ok() {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    };
+    DetectedEncoding {
+        charset: Some(encoding.name().to_string()),
+        bom: false,
+        binary: false,
+    }
+}
+
+/// NUL bytes, or a high share of control characters other than common whitespace/escapes.
+fn looks_binary(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+        .count();
+    control * 10 > sample.len() * 3
+}
+
+fn lookup(label: &str) -> Result<&'static Encoding, EncodingError> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| EncodingError::UnknownEncoding(label.to_string()))
+}
+
+/// Decode `bytes` as text, using `charset` if given and detection otherwise.
+/// A leading BOM for the chosen encoding is stripped and reported in the result.
+pub fn decode(bytes: &[u8], charset: Option<&str>) -> Result<(String, DetectedEncoding), EncodingError> {
+    let detected = detect(bytes);
+    let encoding = match charset {
+        Some(label) => lookup(label)?,
+        None => match &detected.charset {
+            Some(name) => lookup(name)?,
+            None => return Err(EncodingError::Binary),
+        },
+    };
+
+    let bom = Encoding::for_bom(bytes).is_some_and(|(bom_encoding, _)| bom_encoding == encoding);
+    let (text, had_errors) = encoding.decode_with_bom_removal(bytes);
+    if had_errors {
+        return Err(EncodingError::Malformed(encoding.name().to_string()));
+    }
+
+    Ok((
+        text.into_owned(),
+        DetectedEncoding {
+            charset: Some(encoding.name().to_string()),
+            bom,
+            binary: false,
+        },
+    ))
+}
+
+/// Encode `text` in `charset`, optionally prefixed with its BOM (UTF-8/UTF-16 only).
+/// Fails instead of substituting characters the encoding cannot represent.
+pub fn encode(text: &str, charset: &str, bom: bool) -> Result<Vec<u8>, EncodingError> {
+    let encoding = lookup(charset)?;
+
+    // encoding_rs only encodes to ASCII-compatible encodings; UTF-16 is done by hand.
+    let (prefix, body): (&[u8], Vec<u8>) = if encoding == UTF_16LE {
+        (&[0xFF, 0xFE], text.encode_utf16().flat_map(u16::to_le_bytes).collect())
+    } else if encoding == UTF_16BE {
+        (&[0xFE, 0xFF], text.encode_utf16().flat_map(u16::to_be_bytes).collect())
+    } else if encoding == UTF_8 {
+        (&[0xEF, 0xBB, 0xBF], text.as_bytes().to_vec())
+    } else {
+        let (bytes, _, had_errors) = encoding.encode(text);
+        if had_errors {
+            return Err(EncodingError::Unmappable(encoding.name().to_string()));
+        }
+        (&[], bytes.into_owned())
+    };
+
+    let mut out = Vec::with_capacity(prefix.len() + body.len());
+    if bom {
+        out.extend_from_slice(prefix);
+    }
+    out.extend_from_slice(&body);
+    Ok(out)
+}
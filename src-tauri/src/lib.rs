@@ -1,7 +1,9 @@
 mod commands;
 mod credentials;
 mod diagnostics;
+mod encoding;
 mod ipc_error;
+mod problems;
 mod ssh;
 mod state;
 pub mod trace;
@@ -53,17 +55,87 @@ pub fn run() {
             commands::connection::ssh_list_trusted_host_keys,
             commands::connection::ssh_get_trusted_host_key,
             commands::connection::ssh_trust_host_key,
+            commands::connection::ssh_get_trusted_host_keys,
             commands::connection::ssh_forget_host_key,
+            commands::connection::ssh_host_key_audit,
+            commands::connection::ssh_import_known_hosts,
+            commands::connection::ssh_export_known_hosts,
+            commands::connection::ssh_set_known_hosts_read_through,
+            commands::connection::ssh_get_known_hosts_read_through,
+            commands::connection::ssh_list_config_hosts,
+            commands::connection::ssh_keyboard_interactive_respond,
             // File system commands
             commands::filesystem::sftp_list_dir,
             commands::filesystem::sftp_read_file,
             commands::filesystem::sftp_read_file_with_stat,
             commands::filesystem::sftp_write_file,
+            commands::filesystem::sftp_read_file_bytes,
+            commands::filesystem::sftp_read_file_text,
+            commands::filesystem::sftp_write_file_bytes,
+            commands::filesystem::sftp_write_file_text,
+            commands::filesystem::sftp_read_range,
+            commands::filesystem::sftp_read_tail,
+            commands::filesystem::sftp_follow_start,
+            commands::filesystem::sftp_follow_stop,
+            commands::filesystem::sftp_follow_list,
+            commands::filesystem::sftp_watch_set,
+            commands::filesystem::sftp_watch_stop,
+            commands::filesystem::sftp_watch_status,
             commands::filesystem::sftp_stat,
             commands::filesystem::sftp_create_file,
             commands::filesystem::sftp_create_dir,
             commands::filesystem::sftp_delete,
             commands::filesystem::sftp_rename,
+            commands::filesystem::sftp_chmod,
+            commands::filesystem::sftp_chown,
+            commands::filesystem::sftp_touch,
+            commands::filesystem::sftp_create_symlink,
+            commands::filesystem::sftp_delete_recursive,
+            commands::filesystem::sftp_delete_cancel,
+            commands::filesystem::sftp_copy,
+            commands::filesystem::sftp_move,
+            // Transfer commands
+            commands::transfer::sftp_transfer_download,
+            commands::transfer::sftp_transfer_upload,
+            commands::transfer::sftp_transfer_resume,
+            commands::transfer::sftp_transfer_cancel,
+            commands::transfer::sftp_transfer_list,
+            commands::transfer::sftp_transfer_clear,
+            // Search commands
+            commands::search::sftp_search_start,
+            commands::search::sftp_search_cancel,
+            // Exec commands
+            commands::exec::ssh_exec_start,
+            commands::exec::ssh_exec_run,
+            commands::exec::ssh_exec_cancel,
+            commands::exec::ssh_exec_list,
+            commands::exec::problems_parse,
+            // Task commands
+            commands::tasks::task_list,
+            commands::tasks::task_run,
+            commands::tasks::task_rerun,
+            commands::tasks::task_running,
+            commands::tasks::task_kill,
+            // Language server commands
+            commands::lsp::lsp_start,
+            commands::lsp::lsp_send,
+            commands::lsp::lsp_restart,
+            commands::lsp::lsp_stop,
+            commands::lsp::lsp_list,
+            // Git commands
+            commands::git::git_status,
+            commands::git::git_diff,
+            commands::git::git_log,
+            commands::git::git_add,
+            commands::git::git_commit,
+            commands::git::git_checkout,
+            commands::git::git_stash,
+            // Port forwarding commands
+            commands::forward::ssh_forward_local_open,
+            commands::forward::ssh_forward_remote_open,
+            commands::forward::ssh_forward_dynamic_open,
+            commands::forward::ssh_forward_list,
+            commands::forward::ssh_forward_close,
             // Terminal commands
             commands::terminal::terminal_create,
             commands::terminal::terminal_reopen,
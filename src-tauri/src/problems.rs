@@ -0,0 +1,575 @@
+//! Problem matchers: turn compiler and tool output into structured diagnostics.
+//!
+//! Output is fed in chunks as it streams; complete lines are run through the enabled matchers and
+//! ANSI colour codes are stripped first. stdout and stderr are matched separately, so a report
+//! spanning several lines is not broken up by the other stream's output. Relative paths are resolved against the project root the
+//! command ran in. Built-in matchers cover rustc/cargo (JSON and human-readable), tsc, gcc/clang,
+//! eslint's `unix` format and Python tracebacks.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Longest partial line kept between chunks
+const MAX_LINE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatcherKind {
+    Rustc,
+    Tsc,
+    Gcc,
+    Eslint,
+    Python,
+}
+
+impl MatcherKind {
+    pub const ALL: [MatcherKind; 5] = [
+        MatcherKind::Rustc,
+        MatcherKind::Tsc,
+        MatcherKind::Gcc,
+        MatcherKind::Eslint,
+        MatcherKind::Python,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    fn parse(level: &str) -> Self {
+        match level.to_ascii_lowercase().as_str() {
+            "error" | "fatal error" | "fatal" | "error: internal compiler error" => Severity::Error,
+            "warning" | "warn" => Severity::Warning,
+            _ => Severity::Info,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    /// Absolute remote path
+    pub path: String,
+    /// 1-based
+    pub line: u64,
+    /// 1-based; `None` when the tool only reports a line
+    pub column: Option<u64>,
+    pub end_line: Option<u64>,
+    pub end_column: Option<u64>,
+    pub severity: Severity,
+    pub message: String,
+    /// Error code or lint rule, e.g. `E0308`, `TS2322`, `no-unused-vars`
+    pub code: Option<String>,
+    pub source: MatcherKind,
+}
+
+struct Patterns {
+    ansi: Regex,
+    rustc_header: Regex,
+    rustc_location: Regex,
+    tsc: Regex,
+    tsc_pretty: Regex,
+    gcc: Regex,
+    eslint: Regex,
+    python_frame: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        ansi: Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").unwrap(),
+        rustc_header: Regex::new(r"^(error|warning)(?:\[(\w+)\])?: (.+)$").unwrap(),
+        rustc_location: Regex::new(r"^\s*--> (.+?):(\d+):(\d+)$").unwrap(),
+        tsc: Regex::new(r"^(.+?)\((\d+),(\d+)\): (error|warning|message) (TS\d+): (.*)$").unwrap(),
+        tsc_pretty: Regex::new(r"^(.+?):(\d+):(\d+) - (error|warning|message) (TS\d+): (.*)$").unwrap(),
+        gcc: Regex::new(r"^(.+?):(\d+):(?:(\d+):)? (fatal error|error|warning|note): (.*?)(?: \[([^\]\s]+)\])?$").unwrap(),
+        eslint: Regex::new(r"^(.+?):(\d+):(\d+): (.*) \[(Error|Warning)(?:/([^\]]+))?\]$").unwrap(),
+        python_frame: Regex::new(r#"^\s+File "(.+)", line (\d+)"#).unwrap(),
+    })
+}
+
+/// Resolve `path` against `root` and drop `.` and `..` components.
+fn resolve_path(root: &str, path: &str) -> String {
+    let joined = if path.starts_with('/') || root.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", root.trim_end_matches('/'), path)
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    let resolved = parts.join("/");
+    if joined.starts_with('/') {
+        format!("/{}", resolved)
+    } else {
+        resolved
+    }
+}
+
+/// A rustc error header waiting for its ` --> file:line:col` line
+struct PendingRustc {
+    severity: Severity,
+    code: Option<String>,
+    message: String,
+}
+
+/// A Python traceback being read: frames so far, innermost last
+#[derive(Default)]
+struct Traceback {
+    frames: Vec<(String, u64)>,
+}
+
+/// Matcher state of one output stream.
+#[derive(Default)]
+struct StreamState {
+    /// Partial line
+    buffer: String,
+    rustc: Option<PendingRustc>,
+    traceback: Option<Traceback>,
+}
+
+/// Stateful matcher for one command's output.
+pub struct ProblemMatcher {
+    root: String,
+    kinds: Vec<MatcherKind>,
+    stdout: StreamState,
+    stderr: StreamState,
+}
+
+impl ProblemMatcher {
+    /// `kinds` empty means every built-in matcher. `root` should be absolute; it is joined as is,
+    /// so `~` is not expanded.
+    pub fn new(root: &str, kinds: &[MatcherKind]) -> Self {
+        Self {
+            root: root.to_string(),
+            kinds: if kinds.is_empty() { MatcherKind::ALL.to_vec() } else { kinds.to_vec() },
+            stdout: StreamState::default(),
+            stderr: StreamState::default(),
+        }
+    }
+
+    /// Feed a chunk of output; returns diagnostics from the lines it completed.
+    pub fn push(&mut self, stderr: bool, text: &str) -> Vec<Diagnostic> {
+        // Taken out while its lines are matched, which needs the rest of `self`.
+        let mut state = std::mem::take(if stderr { &mut self.stderr } else { &mut self.stdout });
+        let mut found = Vec::new();
+        state.buffer.push_str(text);
+        match state.buffer.rfind('\n') {
+            Some(end) => {
+                let lines: String = state.buffer.drain(..=end).collect();
+                for line in lines.lines() {
+                    self.line(&mut state, line, &mut found);
+                }
+            }
+            None if state.buffer.len() > MAX_LINE => state.buffer.clear(),
+            None => {}
+        }
+        *(if stderr { &mut self.stderr } else { &mut self.stdout }) = state;
+        found
+    }
+
+    /// Flush unterminated lines at the end of the output.
+    pub fn finish(&mut self) -> Vec<Diagnostic> {
+        let mut found = Vec::new();
+        for mut state in [std::mem::take(&mut self.stdout), std::mem::take(&mut self.stderr)] {
+            let rest = std::mem::take(&mut state.buffer);
+            for line in rest.lines().filter(|line| !line.is_empty()) {
+                self.line(&mut state, line, &mut found);
+            }
+        }
+        found
+    }
+
+    fn enabled(&self, kind: MatcherKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+
+    /// A diagnostic at a position, to be completed with struct update syntax.
+    fn at(&self, source: MatcherKind, path: &str, line: u64, column: Option<u64>) -> Diagnostic {
+        Diagnostic {
+            path: resolve_path(&self.root, path),
+            line,
+            column,
+            end_line: None,
+            end_column: None,
+            severity: Severity::Error,
+            message: String::new(),
+            code: None,
+            source,
+        }
+    }
+
+    fn line(&self, state: &mut StreamState, raw: &str, found: &mut Vec<Diagnostic>) {
+        let patterns = patterns();
+        let stripped = patterns.ansi.replace_all(raw, "");
+        let line = stripped.trim_end_matches('\r');
+
+        if self.enabled(MatcherKind::Python) && self.python_line(state, line, found) {
+            return;
+        }
+        if self.enabled(MatcherKind::Rustc) {
+            if line.starts_with('{') {
+                if let Some(diagnostic) = self.rustc_json(line) {
+                    found.push(diagnostic);
+                }
+                return;
+            }
+            if let Some(caps) = patterns.rustc_header.captures(line) {
+                state.rustc = Some(PendingRustc {
+                    severity: Severity::parse(&caps[1]),
+                    code: caps.get(2).map(|m| m.as_str().to_string()),
+                    message: caps[3].to_string(),
+                });
+                return;
+            }
+            if let Some(caps) = patterns.rustc_location.captures(line) {
+                if let Some(pending) = state.rustc.take() {
+                    found.push(Diagnostic {
+                        severity: pending.severity,
+                        message: pending.message,
+                        code: pending.code,
+                        ..self.at(MatcherKind::Rustc, &caps[1], caps[2].parse().unwrap_or(1), caps[3].parse().ok())
+                    });
+                }
+                return;
+            }
+        }
+        if self.enabled(MatcherKind::Tsc) {
+            if let Some(caps) = patterns.tsc.captures(line).or_else(|| patterns.tsc_pretty.captures(line)) {
+                found.push(Diagnostic {
+                    severity: Severity::parse(&caps[4]),
+                    message: caps[6].trim().to_string(),
+                    code: Some(caps[5].to_string()),
+                    ..self.at(MatcherKind::Tsc, &caps[1], caps[2].parse().unwrap_or(1), caps[3].parse().ok())
+                });
+                return;
+            }
+        }
+        if self.enabled(MatcherKind::Eslint) {
+            if let Some(caps) = patterns.eslint.captures(line) {
+                found.push(Diagnostic {
+                    severity: Severity::parse(&caps[5]),
+                    message: caps[4].trim().to_string(),
+                    code: caps.get(6).map(|m| m.as_str().to_string()),
+                    ..self.at(MatcherKind::Eslint, &caps[1], caps[2].parse().unwrap_or(1), caps[3].parse().ok())
+                });
+                return;
+            }
+        }
+        if self.enabled(MatcherKind::Gcc) {
+            if let Some(caps) = patterns.gcc.captures(line) {
+                let column = caps.get(3).and_then(|m| m.as_str().parse().ok());
+                found.push(Diagnostic {
+                    severity: Severity::parse(&caps[4]),
+                    message: caps[5].trim().to_string(),
+                    code: caps.get(6).map(|m| m.as_str().to_string()),
+                    ..self.at(MatcherKind::Gcc, &caps[1], caps[2].parse().unwrap_or(1), column)
+                });
+            }
+        }
+    }
+
+    /// One JSON line from `cargo --message-format=json` or `rustc --error-format=json`. The
+    /// diagnostic is placed at its primary span; messages without one (summaries) are skipped.
+    fn rustc_json(&self, line: &str) -> Option<Diagnostic> {
+        let value: serde_json::Value = serde_json::from_str(line).ok()?;
+        let message = match value.get("reason").and_then(|r| r.as_str()) {
+            Some("compiler-message") => value.get("message")?,
+            Some(_) => return None,
+            None => &value,
+        };
+        let span = message
+            .get("spans")?
+            .as_array()?
+            .iter()
+            .find(|span| span.get("is_primary").and_then(|p| p.as_bool()).unwrap_or(false))?;
+        let position = self.at(
+            MatcherKind::Rustc,
+            span.get("file_name")?.as_str()?,
+            span.get("line_start")?.as_u64()?,
+            span.get("column_start").and_then(|c| c.as_u64()),
+        );
+        Some(Diagnostic {
+            end_line: span.get("line_end").and_then(|l| l.as_u64()),
+            end_column: span.get("column_end").and_then(|c| c.as_u64()),
+            severity: Severity::parse(message.get("level")?.as_str()?),
+            message: message.get("message")?.as_str()?.trim().to_string(),
+            code: message
+                .get("code")
+                .and_then(|c| c.get("code"))
+                .and_then(|c| c.as_str())
+                .map(str::to_string),
+            ..position
+        })
+    }
+
+    /// Track `Traceback (most recent call last):` blocks and `SyntaxError` reports. The exception
+    /// line ending a block becomes a diagnostic at the innermost frame inside the project root,
+    /// or the innermost frame if none is. Returns whether the line was consumed.
+    fn python_line(&self, state: &mut StreamState, line: &str, found: &mut Vec<Diagnostic>) -> bool {
+        if line.starts_with("Traceback (most recent call last)") {
+            state.traceback = Some(Traceback::default());
+            return true;
+        }
+        if let Some(caps) = patterns().python_frame.captures(line) {
+            let frame = (caps[1].to_string(), caps[2].parse().unwrap_or(1));
+            // Syntax errors are reported with a single frame and no Traceback header.
+            state.traceback.get_or_insert_with(Traceback::default).frames.push(frame);
+            return true;
+        }
+        if state.traceback.is_none() {
+            return false;
+        }
+        if line.starts_with([' ', '\t']) || line.is_empty() {
+            return true;
+        }
+        let traceback = state.traceback.take().unwrap_or_default();
+        let is_exception = line
+            .split(':')
+            .next()
+            .is_some_and(|name| !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.'));
+        if !is_exception || traceback.frames.is_empty() {
+            return false;
+        }
+        let root = format!("{}/", self.root.trim_end_matches('/'));
+        let (path, line_number) = traceback
+            .frames
+            .iter()
+            .rev()
+            .find(|(path, _)| resolve_path(&self.root, path).starts_with(&root))
+            .or(traceback.frames.last())
+            .cloned()
+            .unwrap_or_default();
+        let code = line.split(':').next().map(str::to_string);
+        found.push(Diagnostic {
+            message: line.trim().to_string(),
+            code,
+            ..self.at(MatcherKind::Python, &path, line_number, None)
+        });
+        true
+    }
+}
+
+/// Match a complete piece of output at once, e.g. text copied from a terminal.
+pub fn parse(root: &str, kinds: &[MatcherKind], text: &str) -> Vec<Diagnostic> {
+    let mut matcher = ProblemMatcher::new(root, kinds);
+    let mut found = matcher.push(false, text);
+    found.extend(matcher.finish());
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT: &str = "/home/me/project";
+
+    /// Path, line, column, severity, code and message
+    type Summary<'a> = (&'a str, u64, Option<u64>, Severity, Option<&'a str>, &'a str);
+
+    fn summary(found: &[Diagnostic]) -> Vec<Summary<'_>> {
+        found
+            .iter()
+            .map(|d| (d.path.as_str(), d.line, d.column, d.severity, d.code.as_deref(), d.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn rustc_human_readable() {
+        let output = "\
+   Compiling app v0.1.0 (/home/me/project)
+\x1b[0m\x1b[1m\x1b[38;5;9merror[E0308]\x1b[0m\x1b[0m\x1b[1m: mismatched types\x1b[0m
+  --> src/main.rs:4:18
+   |
+4  |     let x: u32 = \"a\";
+   |            ---   ^^^ expected `u32`, found `&str`
+warning: unused variable: `y`
+ --> src/lib.rs:10:9
+";
+        let found = parse(ROOT, &[MatcherKind::Rustc], output);
+        assert_eq!(
+            summary(&found),
+            vec![
+                ("/home/me/project/src/main.rs", 4, Some(18), Severity::Error, Some("E0308"), "mismatched types"),
+                ("/home/me/project/src/lib.rs", 10, Some(9), Severity::Warning, None, "unused variable: `y`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rustc_json_multi_line_span() {
+        let output = concat!(
+            r#"{"reason":"compiler-artifact","target":{"name":"dep"}}"#,
+            "\n",
+            r#"{"reason":"compiler-message","message":{"message":"unclosed delimiter","code":{"code":"E0000"},"level":"error","spans":[{"file_name":"src/other.rs","line_start":1,"line_end":1,"column_start":1,"column_end":2,"is_primary":false},{"file_name":"src/main.rs","line_start":3,"line_end":7,"column_start":5,"column_end":2,"is_primary":true}]}}"#,
+            "\n",
+            r#"{"reason":"compiler-message","message":{"message":"2 warnings emitted","code":null,"level":"warning","spans":[]}}"#,
+            "\n",
+        );
+        let found = parse(ROOT, &[MatcherKind::Rustc], output);
+        assert_eq!(
+            summary(&found),
+            vec![("/home/me/project/src/main.rs", 3, Some(5), Severity::Error, Some("E0000"), "unclosed delimiter")]
+        );
+        assert_eq!((found[0].end_line, found[0].end_column), (Some(7), Some(2)));
+    }
+
+    #[test]
+    fn tsc_plain_and_pretty() {
+        let output = "\
+src/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.
+web/../src/util.ts:3:1 - warning TS6133: 'x' is declared but its value is never read.
+Found 2 errors.
+";
+        let found = parse(ROOT, &[MatcherKind::Tsc], output);
+        assert_eq!(
+            summary(&found),
+            vec![
+                (
+                    "/home/me/project/src/app.ts",
+                    12,
+                    Some(5),
+                    Severity::Error,
+                    Some("TS2322"),
+                    "Type 'string' is not assignable to type 'number'."
+                ),
+                (
+                    "/home/me/project/src/util.ts",
+                    3,
+                    Some(1),
+                    Severity::Warning,
+                    Some("TS6133"),
+                    "'x' is declared but its value is never read."
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn gcc_with_and_without_column() {
+        let output = "\
+main.c: In function 'main':
+main.c:5:12: warning: unused variable 'n' [-Wunused-variable]
+/usr/include/stdio.h:27: error: expected ';' before 'int'
+lib/x.c:1:10: fatal error: missing.h: No such file or directory
+";
+        let found = parse(ROOT, &[MatcherKind::Gcc], output);
+        assert_eq!(
+            summary(&found),
+            vec![
+                ("/home/me/project/main.c", 5, Some(12), Severity::Warning, Some("-Wunused-variable"), "unused variable 'n'"),
+                ("/usr/include/stdio.h", 27, None, Severity::Error, None, "expected ';' before 'int'"),
+                ("/home/me/project/lib/x.c", 1, Some(10), Severity::Error, None, "missing.h: No such file or directory"),
+            ]
+        );
+    }
+
+    #[test]
+    fn eslint_unix() {
+        let output = "\
+/home/me/project/src/a.js:3:7: 'x' is assigned a value but never used. [Error/no-unused-vars]
+src/b.js:1:1: Parsing error: Unexpected token [Warning]
+
+2 problems
+";
+        let found = parse(ROOT, &[MatcherKind::Eslint], output);
+        assert_eq!(
+            summary(&found),
+            vec![
+                (
+                    "/home/me/project/src/a.js",
+                    3,
+                    Some(7),
+                    Severity::Error,
+                    Some("no-unused-vars"),
+                    "'x' is assigned a value but never used."
+                ),
+                ("/home/me/project/src/b.js", 1, Some(1), Severity::Warning, None, "Parsing error: Unexpected token"),
+            ]
+        );
+    }
+
+    #[test]
+    fn python_traceback_prefers_project_frame() {
+        let output = "\
+Traceback (most recent call last):
+  File \"/home/me/project/app.py\", line 8, in <module>
+    main()
+  File \"pkg/run.py\", line 3, in main
+    json.loads(data)
+  File \"/usr/lib/python3.11/json/__init__.py\", line 346, in loads
+    return _default_decoder.decode(s)
+json.decoder.JSONDecodeError: Expecting value: line 1 column 1 (char 0)
+";
+        let found = parse(ROOT, &[MatcherKind::Python], output);
+        assert_eq!(
+            summary(&found),
+            vec![(
+                "/home/me/project/pkg/run.py",
+                3,
+                None,
+                Severity::Error,
+                Some("json.decoder.JSONDecodeError"),
+                "json.decoder.JSONDecodeError: Expecting value: line 1 column 1 (char 0)"
+            )]
+        );
+    }
+
+    #[test]
+    fn python_syntax_error() {
+        // No Traceback header, just the frame.
+        let output = "  File \"/home/me/project/bad.py\", line 2\n    def f(:\n          ^\nSyntaxError: invalid syntax\n";
+        let found = parse(ROOT, &[MatcherKind::Python], output);
+        assert_eq!(
+            summary(&found),
+            vec![("/home/me/project/bad.py", 2, None, Severity::Error, Some("SyntaxError"), "SyntaxError: invalid syntax")]
+        );
+    }
+
+    #[test]
+    fn streams_keep_their_own_state() {
+        let mut matcher = ProblemMatcher::new(ROOT, &[]);
+        let mut found = matcher.push(true, "Traceback (most recent call last):\n");
+        found.extend(matcher.push(false, "progress: 50%\n"));
+        found.extend(matcher.push(true, "  File \"/home/me/project/a.py\", line 4, in <module>\n"));
+        found.extend(matcher.push(false, "error[E0425]: cannot find value `z`\n"));
+        found.extend(matcher.push(true, "    boom()\nNameError: name 'boom' is not defined\n"));
+        found.extend(matcher.push(false, "  --> src/"));
+        found.extend(matcher.push(false, "main.rs:2:5\n"));
+        found.extend(matcher.finish());
+        assert_eq!(
+            summary(&found),
+            vec![
+                (
+                    "/home/me/project/a.py",
+                    4,
+                    None,
+                    Severity::Error,
+                    Some("NameError"),
+                    "NameError: name 'boom' is not defined"
+                ),
+                ("/home/me/project/src/main.rs", 2, Some(5), Severity::Error, Some("E0425"), "cannot find value `z`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn finish_flushes_unterminated_lines() {
+        let mut matcher = ProblemMatcher::new(ROOT, &[MatcherKind::Gcc]);
+        assert!(matcher.push(true, "x.c:1:2: error: oops").is_empty());
+        assert_eq!(summary(&matcher.finish()), vec![("/home/me/project/x.c", 1, Some(2), Severity::Error, None, "oops")]);
+    }
+}
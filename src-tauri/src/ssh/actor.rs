@@ -1,4 +1,5 @@
 use crate::ssh::client::{SshConnection, SshError};
+use crate::ssh::forward::RemoteRoute;
 use crate::ssh::pty::PtySession;
 use crate::trace::{emit_trace, TraceEvent};
 use serde::Serialize;
@@ -31,7 +32,29 @@ pub enum ConnectionRequest {
     WriteFile {
         path: String,
         content: String,
-        respond_to: oneshot::Sender<Result<(), SshError>>,
+        precondition: crate::ssh::sftp::WritePrecondition,
+        respond_to: oneshot::Sender<Result<crate::ssh::sftp::SaveReport, SshError>>,
+    },
+    ReadFileBytes {
+        path: String,
+        respond_to: oneshot::Sender<Result<(Vec<u8>, crate::ssh::sftp::SftpStat), SshError>>,
+    },
+    WriteFileBytes {
+        path: String,
+        content: Vec<u8>,
+        precondition: crate::ssh::sftp::WritePrecondition,
+        respond_to: oneshot::Sender<Result<crate::ssh::sftp::SaveReport, SshError>>,
+    },
+    ReadRange {
+        path: String,
+        offset: u64,
+        len: u64,
+        respond_to: oneshot::Sender<Result<crate::ssh::sftp::RangeRead, SshError>>,
+    },
+    ReadTail {
+        path: String,
+        lines: usize,
+        respond_to: oneshot::Sender<Result<crate::ssh::sftp::RangeRead, SshError>>,
     },
     Stat {
         path: String,
@@ -54,6 +77,27 @@ pub enum ConnectionRequest {
         new_path: String,
         respond_to: oneshot::Sender<Result<(), SshError>>,
     },
+    Chmod {
+        path: String,
+        mode: u32,
+        respond_to: oneshot::Sender<Result<(), SshError>>,
+    },
+    Chown {
+        path: String,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        respond_to: oneshot::Sender<Result<(), SshError>>,
+    },
+    Touch {
+        path: String,
+        mtime: Option<i64>,
+        respond_to: oneshot::Sender<Result<(), SshError>>,
+    },
+    CreateSymlink {
+        path: String,
+        target: String,
+        respond_to: oneshot::Sender<Result<(), SshError>>,
+    },
     CreatePty {
         terminal_id: String,
         working_dir: Option<String>,
@@ -63,6 +107,45 @@ pub enum ConnectionRequest {
     CheckTmux {
         respond_to: oneshot::Sender<Result<bool, SshError>>,
     },
+    OpenDirectTcpip {
+        host: String,
+        port: u16,
+        originator_address: String,
+        originator_port: u16,
+        respond_to: oneshot::Sender<Result<russh::Channel<russh::client::Msg>, SshError>>,
+    },
+    OpenSftpChannel {
+        timeout_secs: u64,
+        respond_to: oneshot::Sender<Result<russh_sftp::client::RawSftpSession, SshError>>,
+    },
+    /// A dedicated `SftpSession` (plus extensions session) for copies, moves and deletes.
+    OpenSftpSession {
+        timeout_secs: u64,
+        extensions: bool,
+        respond_to: oneshot::Sender<
+            Result<(russh_sftp::client::SftpSession, Option<crate::ssh::sftp::SftpExtensions>), SshError>,
+        >,
+    },
+    OpenExecChannel {
+        command: String,
+        respond_to: oneshot::Sender<Result<russh::Channel<russh::client::Msg>, SshError>>,
+    },
+    /// Drop cached listings of `paths`, their subtrees and parents after an outside change.
+    InvalidateDirCache {
+        paths: Vec<String>,
+        respond_to: oneshot::Sender<Result<(), SshError>>,
+    },
+    RequestRemoteForward {
+        bind_host: String,
+        bind_port: u16,
+        route: RemoteRoute,
+        respond_to: oneshot::Sender<Result<u16, SshError>>,
+    },
+    CancelRemoteForward {
+        bind_host: String,
+        bind_port: u16,
+        respond_to: oneshot::Sender<Result<(), SshError>>,
+    },
     Disconnect {
         respond_to: oneshot::Sender<Result<(), SshError>>,
     },
@@ -80,10 +163,14 @@ const LIST_DIR_TIMEOUT: Duration = Duration::from_secs(45);
 const READ_FILE_TIMEOUT: Duration = Duration::from_secs(60);
 const READ_FILE_WITH_STAT_TIMEOUT: Duration = Duration::from_secs(75);
 const WRITE_FILE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Ranged and tail reads are capped at `sftp::MAX_RANGE_LEN`, so this bounds a few MiB.
+const READ_RANGE_TIMEOUT: Duration = Duration::from_secs(45);
 const STAT_TIMEOUT: Duration = Duration::from_secs(30);
 const MUTATION_TIMEOUT: Duration = Duration::from_secs(30);
 const PTY_TIMEOUT: Duration = Duration::from_secs(20);
 const CHECK_TMUX_TIMEOUT: Duration = Duration::from_secs(5);
+const CHANNEL_OPEN_TIMEOUT: Duration = Duration::from_secs(15);
+const REMOTE_FORWARD_TIMEOUT: Duration = Duration::from_secs(15);
 
 const DIR_CACHE_TTL: Duration = Duration::from_secs(10);
 const DIR_CACHE_MAX_ENTRIES: usize = 128;
@@ -130,6 +217,10 @@ pub fn spawn_connection_actor(
                     // If the watch channel closed or carried no detail, keep waiting for requests.
                     continue;
                 }
+                Some(announcement) = connection.next_host_key_announcement() => {
+                    connection.prove_announced_host_keys(&app, announcement).await;
+                    continue;
+                }
             };
 
             let Some(request) = request else { break; };
@@ -153,6 +244,22 @@ pub fn spawn_connection_actor(
                     emit_trace(&app, TraceEvent::new("actor", "write_file", &format!("WriteFile: {}", path)));
                     "WriteFile"
                 }
+                ConnectionRequest::ReadFileBytes { path, .. } => {
+                    emit_trace(&app, TraceEvent::new("actor", "read_file_bytes", &format!("ReadFileBytes: {}", path)));
+                    "ReadFileBytes"
+                }
+                ConnectionRequest::WriteFileBytes { path, content, .. } => {
+                    emit_trace(&app, TraceEvent::new("actor", "write_file_bytes", &format!("WriteFileBytes: {} ({} bytes)", path, content.len())));
+                    "WriteFileBytes"
+                }
+                ConnectionRequest::ReadRange { path, offset, len, .. } => {
+                    emit_trace(&app, TraceEvent::new("actor", "read_range", &format!("ReadRange: {} @{}+{}", path, offset, len)));
+                    "ReadRange"
+                }
+                ConnectionRequest::ReadTail { path, lines, .. } => {
+                    emit_trace(&app, TraceEvent::new("actor", "read_tail", &format!("ReadTail: {} ({} lines)", path, lines)));
+                    "ReadTail"
+                }
                 ConnectionRequest::Stat { path, .. } => {
                     emit_trace(&app, TraceEvent::new("actor", "stat", &format!("Stat: {}", path)));
                     "Stat"
@@ -161,8 +268,25 @@ pub fn spawn_connection_actor(
                 ConnectionRequest::CreateDir { .. } => "CreateDir",
                 ConnectionRequest::Delete { .. } => "Delete",
                 ConnectionRequest::Rename { .. } => "Rename",
+                ConnectionRequest::Chmod { .. } => "Chmod",
+                ConnectionRequest::Chown { .. } => "Chown",
+                ConnectionRequest::Touch { .. } => "Touch",
+                ConnectionRequest::CreateSymlink { .. } => "CreateSymlink",
                 ConnectionRequest::CreatePty { .. } => "CreatePty",
                 ConnectionRequest::CheckTmux { .. } => "CheckTmux",
+                ConnectionRequest::OpenDirectTcpip { host, port, .. } => {
+                    emit_trace(&app, TraceEvent::new("actor", "direct_tcpip", &format!("OpenDirectTcpip: {}:{}", host, port)));
+                    "OpenDirectTcpip"
+                }
+                ConnectionRequest::OpenSftpChannel { .. } => "OpenSftpChannel",
+                ConnectionRequest::OpenSftpSession { .. } => "OpenSftpSession",
+                ConnectionRequest::OpenExecChannel { .. } => "OpenExecChannel",
+                ConnectionRequest::InvalidateDirCache { .. } => "InvalidateDirCache",
+                ConnectionRequest::RequestRemoteForward { bind_host, bind_port, .. } => {
+                    emit_trace(&app, TraceEvent::new("actor", "remote_forward", &format!("RequestRemoteForward: {}:{}", bind_host, bind_port)));
+                    "RequestRemoteForward"
+                }
+                ConnectionRequest::CancelRemoteForward { .. } => "CancelRemoteForward",
                 ConnectionRequest::Disconnect { .. } => {
                     emit_trace(&app, TraceEvent::new("actor", "disconnect_req", "Disconnect request received"));
                     "Disconnect"
@@ -249,11 +373,59 @@ pub fn spawn_connection_actor(
                 ConnectionRequest::WriteFile {
                     path,
                     content,
+                    precondition,
+                    respond_to,
+                } => {
+                    let result = match tokio::time::timeout(
+                        WRITE_FILE_TIMEOUT,
+                        connection.write_file(&path, &content, &precondition),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => {
+                            connection.reset_sftp();
+                            Err(SshError::SftpTimeout)
+                        }
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    } else {
+                        dir_cache.invalidate_parent_of_path(&path);
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::ReadFileBytes { path, respond_to } => {
+                    let result = match tokio::time::timeout(
+                        READ_FILE_WITH_STAT_TIMEOUT,
+                        connection.read_file_bytes(&path),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => {
+                            connection.reset_sftp();
+                            Err(SshError::SftpTimeout)
+                        }
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::WriteFileBytes {
+                    path,
+                    content,
+                    precondition,
                     respond_to,
                 } => {
                     let result = match tokio::time::timeout(
                         WRITE_FILE_TIMEOUT,
-                        connection.write_file(&path, &content),
+                        connection.write_file_bytes(&path, &content, &precondition),
                     )
                     .await
                     {
@@ -272,6 +444,51 @@ pub fn spawn_connection_actor(
                     }
                     let _ = respond_to.send(result);
                 }
+                ConnectionRequest::ReadRange {
+                    path,
+                    offset,
+                    len,
+                    respond_to,
+                } => {
+                    let result = match tokio::time::timeout(
+                        READ_RANGE_TIMEOUT,
+                        connection.read_range(&path, offset, len),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => {
+                            connection.reset_sftp();
+                            Err(SshError::SftpTimeout)
+                        }
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::ReadTail { path, lines, respond_to } => {
+                    let result = match tokio::time::timeout(
+                        READ_RANGE_TIMEOUT,
+                        connection.read_tail(&path, lines),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => {
+                            connection.reset_sftp();
+                            Err(SshError::SftpTimeout)
+                        }
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    }
+                    let _ = respond_to.send(result);
+                }
                 ConnectionRequest::Stat { path, respond_to } => {
                     let result = match tokio::time::timeout(STAT_TIMEOUT, connection.stat(&path)).await {
                         Ok(r) => r,
@@ -365,8 +582,89 @@ pub fn spawn_connection_actor(
                             disconnect_reason = Some(e.to_string());
                         }
                     } else {
-                        dir_cache.invalidate_parent_of_path(&old_path);
-                        dir_cache.invalidate_parent_of_path(&new_path);
+                        dir_cache.invalidate_tree(&old_path);
+                        dir_cache.invalidate_tree(&new_path);
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::Chmod { path, mode, respond_to } => {
+                    let result =
+                        match tokio::time::timeout(MUTATION_TIMEOUT, connection.chmod(&path, mode)).await {
+                            Ok(r) => r,
+                            Err(_) => {
+                                connection.reset_sftp();
+                                Err(SshError::SftpTimeout)
+                            }
+                        };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    } else {
+                        dir_cache.invalidate_parent_of_path(&path);
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::Chown {
+                    path,
+                    uid,
+                    gid,
+                    respond_to,
+                } => {
+                    let result =
+                        match tokio::time::timeout(MUTATION_TIMEOUT, connection.chown(&path, uid, gid)).await {
+                            Ok(r) => r,
+                            Err(_) => {
+                                connection.reset_sftp();
+                                Err(SshError::SftpTimeout)
+                            }
+                        };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    } else {
+                        dir_cache.invalidate_parent_of_path(&path);
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::Touch { path, mtime, respond_to } => {
+                    let result =
+                        match tokio::time::timeout(MUTATION_TIMEOUT, connection.touch(&path, mtime)).await {
+                            Ok(r) => r,
+                            Err(_) => {
+                                connection.reset_sftp();
+                                Err(SshError::SftpTimeout)
+                            }
+                        };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    } else {
+                        dir_cache.invalidate_parent_of_path(&path);
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::CreateSymlink {
+                    path,
+                    target,
+                    respond_to,
+                } => {
+                    let result =
+                        match tokio::time::timeout(MUTATION_TIMEOUT, connection.create_symlink(&path, &target)).await {
+                            Ok(r) => r,
+                            Err(_) => {
+                                connection.reset_sftp();
+                                Err(SshError::SftpTimeout)
+                            }
+                        };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    } else {
+                        dir_cache.invalidate_parent_of_path(&path);
                     }
                     let _ = respond_to.send(result);
                 }
@@ -412,6 +710,142 @@ pub fn spawn_connection_actor(
                     }
                     let _ = respond_to.send(result);
                 }
+                ConnectionRequest::OpenDirectTcpip {
+                    host,
+                    port,
+                    originator_address,
+                    originator_port,
+                    respond_to,
+                } => {
+                    let result = match tokio::time::timeout(
+                        CHANNEL_OPEN_TIMEOUT,
+                        connection.open_direct_tcpip(&host, port, &originator_address, originator_port),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => Err(SshError::ChannelOpenFailed(format!(
+                            "direct-tcpip to {}:{} timed out",
+                            host, port
+                        ))),
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::OpenSftpChannel { timeout_secs, respond_to } => {
+                    let result = match tokio::time::timeout(
+                        CHANNEL_OPEN_TIMEOUT,
+                        connection.open_sftp_channel(timeout_secs),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => Err(SshError::SftpTimeout),
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::OpenSftpSession {
+                    timeout_secs,
+                    extensions,
+                    respond_to,
+                } => {
+                    let result = match tokio::time::timeout(
+                        CHANNEL_OPEN_TIMEOUT,
+                        connection.open_sftp_session(timeout_secs, extensions),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => Err(SshError::SftpTimeout),
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::OpenExecChannel { command, respond_to } => {
+                    let result = match tokio::time::timeout(
+                        CHANNEL_OPEN_TIMEOUT,
+                        connection.open_exec_channel(&command),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => Err(SshError::ChannelOpenFailed("exec channel open timed out".to_string())),
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::InvalidateDirCache { paths, respond_to } => {
+                    for path in &paths {
+                        dir_cache.invalidate_tree(path);
+                    }
+                    let _ = respond_to.send(Ok(()));
+                }
+                ConnectionRequest::RequestRemoteForward {
+                    bind_host,
+                    bind_port,
+                    route,
+                    respond_to,
+                } => {
+                    let result = match tokio::time::timeout(
+                        REMOTE_FORWARD_TIMEOUT,
+                        connection.request_remote_forward(&bind_host, bind_port, route),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => Err(SshError::ForwardRejected(format!(
+                            "tcpip-forward {}:{} timed out",
+                            bind_host, bind_port
+                        ))),
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    }
+                    let _ = respond_to.send(result);
+                }
+                ConnectionRequest::CancelRemoteForward {
+                    bind_host,
+                    bind_port,
+                    respond_to,
+                } => {
+                    let result = match tokio::time::timeout(
+                        REMOTE_FORWARD_TIMEOUT,
+                        connection.cancel_remote_forward(&bind_host, bind_port),
+                    )
+                    .await
+                    {
+                        Ok(r) => r,
+                        Err(_) => Err(SshError::ForwardRejected(format!(
+                            "cancel-tcpip-forward {}:{} timed out",
+                            bind_host, bind_port
+                        ))),
+                    };
+                    if let Err(e) = &result {
+                        if is_fatal_connection_error(e) {
+                            disconnect_reason = Some(e.to_string());
+                        }
+                    }
+                    let _ = respond_to.send(result);
+                }
                 ConnectionRequest::Disconnect { respond_to } => {
                     let result = connection.disconnect().await;
                     let _ = respond_to.send(result);
@@ -456,11 +890,17 @@ fn is_fatal_connection_error(error: &SshError) -> bool {
         SshError::HandshakeJoinAborted { .. } => true,
         SshError::HostKeyUntrusted { .. } => true,
         SshError::HostKeyMismatch { .. } => true,
+        SshError::HostKeyRevoked { .. } => true,
+        SshError::JumpHostFailed { .. } => true,
         SshError::ConnectionFailed(_) => true,
         SshError::AuthenticationFailed(_) => true,
         SshError::ChannelError(_) => true,
+        // The server refused one channel or forward request; the session itself is fine.
+        SshError::ChannelOpenFailed(_) | SshError::ForwardRejected(_) => false,
         // Timeouts and SFTP-level issues may be transient; caller can retry.
         SshError::SftpTimeout | SshError::SftpSessionClosed | SshError::SftpError(_) => false,
+        SshError::WriteConflict { .. } | SshError::TargetExists(_) | SshError::MoveIncomplete { .. } => false,
+        SshError::Cancelled => false,
         SshError::IoError(_) => true,
     }
 }
@@ -515,6 +955,18 @@ impl DirectoryCache {
         self.invalidate_parent_of_path(path);
     }
 
+    /// Drop `path`, every cached directory below it, and its parent.
+    fn invalidate_tree(&mut self, path: &str) {
+        let normalized = normalize_dir_path(path);
+        if normalized == "/" {
+            self.entries.clear();
+            return;
+        }
+        let prefix = format!("{}/", normalized);
+        self.entries.retain(|key, _| !key.starts_with(&prefix));
+        self.invalidate_path_and_parent(path);
+    }
+
     fn evict_if_needed(&mut self) {
         while self.entries.len() > self.max_entries {
             if let Some((oldest_key, _)) = self
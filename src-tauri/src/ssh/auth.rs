@@ -1,6 +1,8 @@
 #![allow(dead_code)]
-use ssh_key::PrivateKey;
-use std::path::Path;
+use serde::Serialize;
+use ssh_key::{Certificate, HashAlg, PrivateKey};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -14,6 +16,52 @@ pub enum AuthError {
     PassphraseRequired,
     #[error("Invalid passphrase")]
     InvalidPassphrase,
+    #[error("Failed to read certificate file: {0}")]
+    CertificateRead(String),
+    #[error("Failed to parse certificate: {0}")]
+    CertificateParse(String),
+}
+
+/// OpenSSH user certificate details, reported back to the UI so users know when to renew.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    pub path: String,
+    pub key_id: String,
+    pub principals: Vec<String>,
+    /// Unix seconds
+    pub valid_after: u64,
+    /// Unix seconds; `None` when the certificate never expires
+    pub valid_before: Option<u64>,
+    pub expired: bool,
+    pub ca_fingerprint_sha256: String,
+}
+
+impl CertificateInfo {
+    pub fn from_certificate(path: &Path, cert: &Certificate) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let valid_before = (cert.valid_before() != u64::MAX).then(|| cert.valid_before());
+        Self {
+            path: path.to_string_lossy().to_string(),
+            key_id: cert.key_id().to_string(),
+            principals: cert.valid_principals().to_vec(),
+            valid_after: cert.valid_after(),
+            valid_before,
+            expired: valid_before.is_some_and(|t| t <= now),
+            ca_fingerprint_sha256: cert.signature_key().fingerprint(HashAlg::Sha256).to_string(),
+        }
+    }
+}
+
+/// Expand a leading `~/` to the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
 }
 
 /// Authentication method for SSH connections
@@ -21,31 +69,31 @@ pub enum AuthError {
 pub enum AuthMethod {
     /// Password authentication
     Password(String),
-    /// Key-based authentication
+    /// Key-based authentication. An OpenSSH certificate (`certificate`, or `<path>-cert.pub` when unset)
+    /// is presented alongside the key when present.
     Key {
         path: String,
         passphrase: Option<String>,
+        certificate: Option<String>,
     },
+    /// Identities held by the local SSH agent (`SSH_AUTH_SOCK`)
+    Agent,
+    /// Keyboard-interactive (PAM prompts, TOTP). A stored password answers a lone password prompt.
+    KeyboardInteractive { password: Option<String> },
+    /// Methods tried in order, for servers that require more than one (partial success)
+    Chain(Vec<AuthMethod>),
 }
 
 impl AuthMethod {
     /// Load the key pair for key-based authentication
     pub async fn load_key_pair(&self) -> Result<Option<Arc<PrivateKey>>, AuthError> {
         match self {
-            AuthMethod::Password(_) => Ok(None),
-            AuthMethod::Key { path, passphrase } => {
-                let key_path = Path::new(path);
-
-                // Expand ~ to home directory
-                let expanded_path = if path.starts_with("~/") {
-                    if let Some(home) = dirs::home_dir() {
-                        home.join(&path[2..])
-                    } else {
-                        key_path.to_path_buf()
-                    }
-                } else {
-                    key_path.to_path_buf()
-                };
+            AuthMethod::Password(_)
+            | AuthMethod::Agent
+            | AuthMethod::KeyboardInteractive { .. }
+            | AuthMethod::Chain(_) => Ok(None),
+            AuthMethod::Key { path, passphrase, .. } => {
+                let expanded_path = expand_home(path);
 
                 let key_data = tokio::fs::read_to_string(&expanded_path)
                     .await
@@ -72,12 +120,74 @@ impl AuthMethod {
         }
     }
 
+    /// Load the OpenSSH user certificate for key-based authentication: the explicit `certificate` path,
+    /// else `<key>-cert.pub` if that file exists.
+    pub async fn load_certificate(&self) -> Result<Option<(PathBuf, Certificate)>, AuthError> {
+        let AuthMethod::Key { path, certificate, .. } = self else {
+            return Ok(None);
+        };
+
+        let cert_path = match certificate {
+            Some(explicit) => expand_home(explicit),
+            None => {
+                let mut candidate = expand_home(path).into_os_string();
+                candidate.push("-cert.pub");
+                let candidate = PathBuf::from(candidate);
+                if !tokio::fs::try_exists(&candidate).await.unwrap_or(false) {
+                    return Ok(None);
+                }
+                candidate
+            }
+        };
+
+        let cert_data = tokio::fs::read_to_string(&cert_path)
+            .await
+            .map_err(|e| AuthError::CertificateRead(e.to_string()))?;
+        let cert = Certificate::from_openssh(cert_data.trim())
+            .map_err(|e| AuthError::CertificateParse(e.to_string()))?;
+        Ok(Some((cert_path, cert)))
+    }
+
+    /// Details of the certificate the (first) key method would present, if any.
+    pub async fn certificate_info(&self) -> Result<Option<CertificateInfo>, AuthError> {
+        match self {
+            AuthMethod::Chain(methods) => {
+                for method in methods {
+                    if let Some(info) = Box::pin(method.certificate_info()).await? {
+                        return Ok(Some(info));
+                    }
+                }
+                Ok(None)
+            }
+            other => Ok(other
+                .load_certificate()
+                .await?
+                .map(|(path, cert)| CertificateInfo::from_certificate(&path, &cert))),
+        }
+    }
+
     /// Get the password for password authentication
     #[allow(dead_code)]
     pub fn password(&self) -> Option<&str> {
         match self {
             AuthMethod::Password(pass) => Some(pass),
-            AuthMethod::Key { .. } => None,
+            AuthMethod::KeyboardInteractive { password } => password.as_deref(),
+            AuthMethod::Key { .. } | AuthMethod::Agent | AuthMethod::Chain(_) => None,
+        }
+    }
+
+    /// Short method name for traces (e.g. `publickey+keyboard-interactive`)
+    pub fn label(&self) -> String {
+        match self {
+            AuthMethod::Password(_) => "password".to_string(),
+            AuthMethod::Key { .. } => "publickey".to_string(),
+            AuthMethod::Agent => "agent".to_string(),
+            AuthMethod::KeyboardInteractive { .. } => "keyboard-interactive".to_string(),
+            AuthMethod::Chain(methods) => methods
+                .iter()
+                .map(AuthMethod::label)
+                .collect::<Vec<_>>()
+                .join("+"),
         }
     }
 }
@@ -1,14 +1,19 @@
 use crate::diagnostics;
-use crate::ssh::auth::AuthMethod;
+use crate::ssh::auth::{AuthMethod, CertificateInfo};
+use crate::ssh::forward::{self, RemoteRoute, RemoteRoutes};
+use crate::ssh::keyboard_interactive;
 use crate::ssh::known_hosts;
 use crate::ssh::pty::PtySession;
-use crate::ssh::sftp::{SftpEntry, SftpStat};
+use crate::ssh::sftp::{
+    self, RangeRead, RemoteFileState, SaveReport, SftpEntry, SftpExtensions, SftpStat,
+    WritePrecondition,
+};
 use crate::trace::{emit_trace, TraceEvent};
 use async_trait::async_trait;
-use russh::client::{self, Config, Handle, Handler};
+use russh::client::{self, Config, Handle, Handler, KeyboardInteractiveAuthResponse};
 use russh::Disconnect;
 use russh_sftp::client::error::Error as SftpClientError;
-use russh_sftp::client::SftpSession;
+use russh_sftp::client::{RawSftpSession, SftpSession};
 use serde::Serialize;
 use ssh_key::public::PublicKey;
 use ssh_key::HashAlg;
@@ -19,9 +24,9 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tauri::AppHandle;
 use thiserror::Error;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
-use tokio::net::{lookup_host, TcpSocket, TcpStream};
-use tokio::sync::{Mutex, watch};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+use tokio::net::{lookup_host, TcpSocket};
+use tokio::sync::{mpsc, Mutex, watch};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize)]
@@ -106,18 +111,21 @@ impl HandshakeTranscript {
     }
 }
 
-struct InstrumentedTcpStream {
-    inner: TcpStream,
+/// Transport wrapper that records handshake bytes/identification lines.
+///
+/// Wraps either a direct `TcpStream` or a `direct-tcpip` channel stream when tunnelling through a jump host.
+struct InstrumentedStream<S> {
+    inner: S,
     transcript: Arc<HandshakeTranscript>,
 }
 
-impl InstrumentedTcpStream {
-    fn new(inner: TcpStream, transcript: Arc<HandshakeTranscript>) -> Self {
+impl<S> InstrumentedStream<S> {
+    fn new(inner: S, transcript: Arc<HandshakeTranscript>) -> Self {
         Self { inner, transcript }
     }
 }
 
-impl AsyncRead for InstrumentedTcpStream {
+impl<S: AsyncRead + Unpin> AsyncRead for InstrumentedStream<S> {
     fn poll_read(
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
@@ -135,7 +143,7 @@ impl AsyncRead for InstrumentedTcpStream {
     }
 }
 
-impl AsyncWrite for InstrumentedTcpStream {
+impl<S: AsyncWrite + Unpin> AsyncWrite for InstrumentedStream<S> {
     fn poll_write(
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
@@ -210,6 +218,18 @@ pub enum SshError {
         expected_public_key_openssh: String,
         actual_public_key_openssh: String,
     },
+    #[error("Host key for {host}:{port} has been revoked ({fingerprint_sha256})")]
+    HostKeyRevoked {
+        host: String,
+        port: u16,
+        fingerprint_sha256: String,
+    },
+    #[error("Connection to {hop} via jump host {via} failed: {detail}")]
+    JumpHostFailed {
+        hop: String,
+        via: String,
+        detail: String,
+    },
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
     #[error("Authentication failed: {0}")]
@@ -222,10 +242,31 @@ pub enum SshError {
     SftpSessionClosed,
     #[error("Channel error: {0}")]
     ChannelError(String),
+    #[error("Channel open refused: {0}")]
+    ChannelOpenFailed(String),
+    #[error("Port forward rejected by server: {0}")]
+    ForwardRejected(String),
+    #[error("Remote file changed since it was read: {path}")]
+    WriteConflict { path: String, current: RemoteFileState },
+    #[error("Target already exists: {0}")]
+    TargetExists(String),
+    #[error("Operation cancelled")]
+    Cancelled,
+    #[error("Copied {from} to {to} but could not delete the source: {detail}")]
+    MoveIncomplete { from: String, to: String, detail: String },
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
 
+/// One hop of a ProxyJump chain (or the final target), each with its own credentials.
+#[derive(Debug, Clone)]
+pub struct JumpHost {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub auth: AuthMethod,
+}
+
 /// SSH client handler
 #[derive(Clone)]
 struct ClientHandler {
@@ -234,6 +275,87 @@ struct ClientHandler {
     port: u16,
     correlation_id: String,
     disconnect_tx: watch::Sender<Option<String>>,
+    /// SHA256 fingerprint of the host key accepted for this session, once verified.
+    verified_fingerprint: Option<String>,
+    /// Position of this session in its ProxyJump chain and where its announcements go.
+    hop: HopContext,
+}
+
+/// Per-hop state shared with a session's handler.
+#[derive(Clone)]
+struct HopContext {
+    /// Index in the chain: 0 is dialled directly, later hops through the previous one.
+    index: usize,
+    /// Remote (`-R`) forwards requested on this session, for routing `forwarded-tcpip` channels.
+    remote_routes: RemoteRoutes,
+    announcements: mpsc::UnboundedSender<HostKeyAnnouncement>,
+}
+
+/// Host keys a server announced with `hostkeys-00@openssh.com` after its key was verified.
+pub struct HostKeyAnnouncement {
+    hop: usize,
+    host: String,
+    port: u16,
+    correlation_id: String,
+    verified_fingerprint: String,
+    keys: Vec<PublicKey>,
+}
+
+/// Bound on each step of reaching a hop: the TCP connect or `direct-tcpip` open, then the handshake.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// Give up on proving an announced key after this long.
+const HOST_KEY_PROOF_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Handler for a handshake-only session that accepts nothing but `expected`. russh verifies the
+/// server's signature over the exchange hash before the key exchange completes, so a finished
+/// handshake proves the server holds the private key, as `hostkeys-prove-00@openssh.com` would
+/// (which russh 0.48 cannot send).
+struct HostKeyProof {
+    expected: PublicKey,
+}
+
+#[async_trait]
+impl Handler for HostKeyProof {
+    type Error = russh::Error;
+
+    async fn check_server_key(&mut self, server_public_key: &PublicKey) -> Result<bool, Self::Error> {
+        Ok(server_public_key.key_data() == self.expected.key_data())
+    }
+}
+
+/// Run a handshake over `stream` that only offers `key`'s algorithm and only accepts `key`.
+async fn prove_host_key<S>(stream: S, key: &PublicKey) -> bool
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let algorithms: Vec<ssh_key::Algorithm> = russh::Preferred::DEFAULT
+        .key
+        .iter()
+        .filter(|a| match key.algorithm() {
+            ssh_key::Algorithm::Rsa { .. } => matches!(a, ssh_key::Algorithm::Rsa { .. }),
+            other => **a == other,
+        })
+        .cloned()
+        .collect();
+    if algorithms.is_empty() {
+        return false;
+    }
+    let config = Arc::new(Config {
+        preferred: russh::Preferred {
+            key: algorithms.into(),
+            ..russh::Preferred::DEFAULT
+        },
+        ..Default::default()
+    });
+    let handler = HostKeyProof { expected: key.clone() };
+    match tokio::time::timeout(HOST_KEY_PROOF_TIMEOUT, client::connect_stream(config, stream, handler)).await {
+        Ok(Ok(handle)) => {
+            let _ = handle.disconnect(Disconnect::ByApplication, "", "en").await;
+            true
+        }
+        _ => false,
+    }
 }
 
 #[derive(Debug, Error)]
@@ -260,8 +382,15 @@ enum ClientError {
         expected_public_key_openssh: String,
         actual_public_key_openssh: String,
     },
+    #[error("Host key revoked: {host}:{port} {fingerprint_sha256}")]
+    HostKeyRevoked {
+        host: String,
+        port: u16,
+        fingerprint_sha256: String,
+    },
 }
 
+
 #[async_trait]
 impl Handler for ClientHandler {
     type Error = ClientError;
@@ -285,27 +414,34 @@ impl Handler for ClientHandler {
                 .with_detail(format!("{}:{} {}", self.host, self.port, fingerprint)),
         );
 
-        let existing = known_hosts::get(&self.app, &self.host, self.port)
+        let check = known_hosts::check(&self.app, &self.host, self.port, server_public_key)
             .await
             .map_err(ClientError::HostKeyStore)?;
 
-        match existing {
-            None => Err(ClientError::HostKeyUntrusted {
+        match check {
+            known_hosts::HostKeyCheck::Unknown => Err(ClientError::HostKeyUntrusted {
                 host: self.host.clone(),
                 port: self.port,
                 key_type,
                 fingerprint_sha256: fingerprint,
                 public_key_openssh,
             }),
-            Some(entry) if entry.fingerprint_sha256 == fingerprint => {
+            known_hosts::HostKeyCheck::Trusted { source } => {
                 emit_trace(
                     &self.app,
                     TraceEvent::new("hostkey", "trusted", "Host key trusted")
-                        .with_correlation_id(self.correlation_id.clone()),
+                        .with_correlation_id(self.correlation_id.clone())
+                        .with_detail(source),
                 );
+                self.verified_fingerprint = Some(fingerprint);
                 Ok(true)
             }
-            Some(entry) => Err(ClientError::HostKeyMismatch {
+            known_hosts::HostKeyCheck::Revoked => Err(ClientError::HostKeyRevoked {
+                host: self.host.clone(),
+                port: self.port,
+                fingerprint_sha256: fingerprint,
+            }),
+            known_hosts::HostKeyCheck::Mismatch(entry) => Err(ClientError::HostKeyMismatch {
                 host: self.host.clone(),
                 port: self.port,
                 key_type,
@@ -317,6 +453,48 @@ impl Handler for ClientHandler {
         }
     }
 
+    /// Queue keys from OpenSSH's `hostkeys-00@openssh.com` announcement (sent after
+    /// authentication) for the connection to prove; see [`SshConnection::prove_announced_host_keys`].
+    async fn openssh_ext_host_keys_announced(
+        &mut self,
+        keys: Vec<PublicKey>,
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        let Some(verified) = self.verified_fingerprint.clone() else {
+            return Ok(());
+        };
+
+        let _ = self.hop.announcements.send(HostKeyAnnouncement {
+            hop: self.hop.index,
+            host: self.host.clone(),
+            port: self.port,
+            correlation_id: self.correlation_id.clone(),
+            verified_fingerprint: verified,
+            keys,
+        });
+        Ok(())
+    }
+
+    /// A connection arrived on one of our remote (`-R`) forwards.
+    async fn server_channel_open_forwarded_tcpip(
+        &mut self,
+        channel: russh::Channel<client::Msg>,
+        _connected_address: &str,
+        connected_port: u32,
+        originator_address: &str,
+        originator_port: u32,
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        forward::serve_remote_channel(
+            &self.app,
+            &self.hop.remote_routes,
+            channel,
+            connected_port,
+            format!("{}:{}", originator_address, originator_port),
+        );
+        Ok(())
+    }
+
     async fn disconnected(
         &mut self,
         reason: russh::client::DisconnectReason<Self::Error>,
@@ -343,10 +521,18 @@ impl Handler for ClientHandler {
 /// Represents an active SSH connection
 pub struct SshConnection {
     handle: Handle<ClientHandler>,
+    /// Sessions to jump hosts, outermost first. Kept alive because `handle` is tunnelled through them.
+    jump_handles: Vec<Handle<ClientHandler>>,
     sftp: Option<Arc<Mutex<SftpSession>>>,
+    /// Opened lazily for listings and saves; see [`SftpExtensions`].
+    sftp_extensions: Option<Arc<SftpExtensions>>,
+    /// The server refused the extension session once; not asked again on this connection.
+    sftp_extensions_refused: bool,
     #[allow(dead_code)]
     username: String,
     disconnect_rx: watch::Receiver<Option<String>>,
+    remote_routes: RemoteRoutes,
+    announcements: mpsc::UnboundedReceiver<HostKeyAnnouncement>,
 }
 
 impl SshConnection {
@@ -356,6 +542,7 @@ impl SshConnection {
 
     pub fn reset_sftp(&mut self) {
         self.sftp = None;
+        self.sftp_extensions = None;
     }
 
     /// Check whether `tmux` is available on the remote server.
@@ -397,20 +584,26 @@ impl SshConnection {
 
     /// Establish a new SSH connection
     ///
-    /// If `app` is provided, trace events will be emitted for debugging.
+    /// If `jump_hosts` is non-empty, the first hop is dialled directly and every later hop (and
+    /// finally the target) is reached through a `direct-tcpip` channel on the previous hop.
     pub async fn connect(
         host: &str,
         port: u16,
         username: &str,
         auth: AuthMethod,
+        jump_hosts: &[JumpHost],
         app: &AppHandle,
     ) -> Result<Self, SshError> {
         let host = host.trim();
         let username = username.trim();
 
+        // Every event of this connect (each hop's DNS, TCP, handshake, host key and auth steps) shares
+        // one correlation ID so the trace stream can be grouped.
+        let chain_id = Uuid::new_v4().to_string();
+
         // Helper to emit trace events
         let trace = |category: &str, step: &str, msg: &str, detail: Option<&str>, is_error: bool| {
-            let mut event = TraceEvent::new(category, step, msg);
+            let mut event = TraceEvent::new(category, step, msg).with_correlation_id(&chain_id);
             if let Some(d) = detail {
                 event = event.with_detail(d);
             }
@@ -420,7 +613,17 @@ impl SshConnection {
             emit_trace(app, event);
         };
 
-        trace("ssh", "start", &format!("Connecting to {}:{} as {}", host, port, username), None, false);
+        let via = jump_hosts
+            .iter()
+            .map(|j| format!("{}@{}:{}", j.username.trim(), j.host.trim(), j.port))
+            .collect::<Vec<_>>();
+        trace(
+            "ssh",
+            "start",
+            &format!("Connecting to {}:{} as {}", host, port, username),
+            (!via.is_empty()).then(|| format!("via {}", via.join(" -> "))).as_deref(),
+            false,
+        );
 
         let mut config = Config::default();
         // Mobile networks (and Wi‑Fi power saving) can silently drop idle TCP sessions within minutes.
@@ -436,6 +639,110 @@ impl SshConnection {
         config.keepalive_max = 0;
         let config = Arc::new(config);
 
+        let mut hops: Vec<JumpHost> = jump_hosts
+            .iter()
+            .map(|j| JumpHost {
+                host: j.host.trim().to_string(),
+                port: j.port,
+                username: j.username.trim().to_string(),
+                auth: j.auth.clone(),
+            })
+            .collect();
+        hops.push(JumpHost {
+            host: host.to_string(),
+            port,
+            username: username.to_string(),
+            auth,
+        });
+
+        // Remote (`-R`) forwards are only ever requested on the final session, so only its handler
+        // shares these routes; jump hops get their own, always empty, table.
+        let remote_routes = RemoteRoutes::default();
+        let (announce_tx, announcements) = mpsc::unbounded_channel();
+        let last_hop = hops.len() - 1;
+        let hop_context = |index: usize| HopContext {
+            index,
+            remote_routes: if index == last_hop {
+                remote_routes.clone()
+            } else {
+                RemoteRoutes::default()
+            },
+            announcements: announce_tx.clone(),
+        };
+
+        let first = &hops[0];
+        let (mut handle, mut disconnect_rx) = Self::dial_direct(
+            &first.host,
+            first.port,
+            &first.username,
+            config.clone(),
+            hop_context(0),
+            app,
+            &chain_id,
+        )
+        .await?;
+        Self::authenticate(&mut handle, first, app, &chain_id).await?;
+
+        let mut jump_handles = Vec::with_capacity(jump_hosts.len());
+        for (idx, pair) in hops.windows(2).enumerate() {
+            let (via_hop, next_hop) = (&pair[0], &pair[1]);
+            let (next_handle, next_disconnect_rx) = Self::dial_via_jump(
+                &handle,
+                via_hop,
+                next_hop,
+                config.clone(),
+                hop_context(idx + 1),
+                app,
+                &chain_id,
+            )
+            .await?;
+            // Keep the previous hop alive: the next session's transport is a channel on it.
+            jump_handles.push(std::mem::replace(&mut handle, next_handle));
+            disconnect_rx = next_disconnect_rx;
+            Self::authenticate(&mut handle, next_hop, app, &chain_id).await?;
+        }
+
+        trace("ssh", "connected", &format!("SSH connection established to {}:{}", host, port), None, false);
+
+        log::info!("SSH connection established to {}:{}", host, port);
+
+        Ok(Self {
+            handle,
+            jump_handles,
+            sftp: None,
+            sftp_extensions: None,
+            sftp_extensions_refused: false,
+            username: username.to_string(),
+            disconnect_rx,
+            remote_routes,
+            announcements,
+        })
+    }
+
+    /// Resolve `host:port` and run the TCP connect + SSH handshake, trying each address in turn.
+    ///
+    /// Events carry the connect's `correlation_id`; each TCP/handshake attempt's own ID (the key for
+    /// its diagnostics record) is only included in the event detail.
+    async fn dial_direct(
+        host: &str,
+        port: u16,
+        username: &str,
+        config: Arc<Config>,
+        hop: HopContext,
+        app: &AppHandle,
+        correlation_id: &str,
+    ) -> Result<(Handle<ClientHandler>, watch::Receiver<Option<String>>), SshError> {
+        let trace = |category: &str, step: &str, msg: &str, detail: Option<&str>, is_error: bool| {
+            let mut event = TraceEvent::new(category, step, msg).with_correlation_id(correlation_id);
+            if let Some(d) = detail {
+                event = event.with_detail(d);
+            }
+            if is_error {
+                event = event.error();
+            }
+            emit_trace(app, event);
+        };
+
         trace("dns", "lookup", &format!("Resolving {}:{}", host, port), None, false);
 
         let mut resolved: Vec<std::net::SocketAddr> = lookup_host((host, port))
@@ -497,11 +804,12 @@ impl SshConnection {
                                              msg: &str,
                                              detail: Option<&str>,
                                              is_error: bool| {
-                            let mut event =
-                                TraceEvent::new(category, step, msg).with_correlation_id(&attempt_id);
-                            if let Some(d) = detail {
-                                event = event.with_detail(d);
-                            }
+                            let mut event = TraceEvent::new(category, step, msg)
+                                .with_correlation_id(correlation_id)
+                                .with_detail(match detail {
+                                    Some(d) => format!("{}; attempt={}", d, attempt_id),
+                                    None => format!("attempt={}", attempt_id),
+                                });
                             if is_error {
                                 event = event.error();
                             }
@@ -524,11 +832,11 @@ impl SshConnection {
                     "tcp",
                     "connect",
                     &format!("TCP connecting to {}", addr),
-                    Some("8s timeout"),
+                    Some(&format!("{}s timeout", CONNECT_TIMEOUT.as_secs())),
                     false,
                 );
 
-                let socket = match tokio::time::timeout(Duration::from_secs(8), async {
+                let socket = match tokio::time::timeout(CONNECT_TIMEOUT, async {
                     let socket = match addr {
                         SocketAddr::V4(_) => TcpSocket::new_v4(),
                         SocketAddr::V6(_) => TcpSocket::new_v6(),
@@ -618,7 +926,7 @@ impl SshConnection {
                     false,
                 );
 
-                let socket = InstrumentedTcpStream::new(socket, transcript.clone());
+                let socket = InstrumentedStream::new(socket, transcript.clone());
 
                 let (disconnect_tx, disconnect_rx_for_attempt) =
                     watch::channel::<Option<String>>(None);
@@ -626,8 +934,10 @@ impl SshConnection {
                     app: app.clone(),
                     host: host.to_string(),
                     port,
-                    correlation_id: attempt_id.clone(),
+                    correlation_id: correlation_id.to_string(),
                     disconnect_tx,
+                    verified_fingerprint: None,
+                    hop: hop.clone(),
                 };
 
                 match client::connect_stream(config.clone(), socket, handler).await {
@@ -731,6 +1041,25 @@ impl SshConnection {
                                 });
                                 break;
                             }
+                            ClientError::HostKeyRevoked {
+                                host,
+                                port,
+                                fingerprint_sha256,
+                            } => {
+                                trace_attempt(
+                                    "hostkey",
+                                    "revoked",
+                                    "Host key is revoked",
+                                    Some(&fingerprint_sha256),
+                                    true,
+                                );
+                                last_error = Some(SshError::HostKeyRevoked {
+                                    host,
+                                    port,
+                                    fingerprint_sha256,
+                                });
+                                break;
+                            }
                             ClientError::Russh(russh::Error::Join(_)) => {
                                 let detail = format!(
                                     "attempt {}/2; err={}; server_id={}",
@@ -816,21 +1145,270 @@ impl SshConnection {
             }
         }
 
-        let mut handle = handle.ok_or_else(|| {
+        let handle = handle.ok_or_else(|| {
             trace("ssh", "all_failed", "All connection attempts failed", None, true);
             last_error.unwrap_or_else(|| {
                 SshError::ConnectionFailed("Failed to establish SSH connection".to_string())
             })
         })?;
 
-        // Authenticate
-        let auth_method_str = match &auth {
-            AuthMethod::Password(_) => "password",
-            AuthMethod::Key { .. } => "publickey",
+        Ok((handle, disconnect_rx.unwrap_or_else(|| watch::channel(None).1)))
+    }
+
+    /// Open a `direct-tcpip` channel to `hop` on `via_handle` and run the SSH handshake over it.
+    async fn dial_via_jump(
+        via_handle: &Handle<ClientHandler>,
+        via: &JumpHost,
+        hop: &JumpHost,
+        config: Arc<Config>,
+        context: HopContext,
+        app: &AppHandle,
+        correlation_id: &str,
+    ) -> Result<(Handle<ClientHandler>, watch::Receiver<Option<String>>), SshError> {
+        let via_label = format!("{}:{}", via.host, via.port);
+        let hop_label = format!("{}:{}", hop.host, hop.port);
+
+        let trace = |category: &str, step: &str, msg: &str, detail: Option<&str>, is_error: bool| {
+            let mut event = TraceEvent::new(category, step, msg).with_correlation_id(correlation_id);
+            if let Some(d) = detail {
+                event = event.with_detail(d);
+            }
+            if is_error {
+                event = event.error();
+            }
+            emit_trace(app, event);
+        };
+
+        trace(
+            "jump",
+            "channel_open",
+            &format!("Opening direct-tcpip channel to {} via {}", hop_label, via_label),
+            Some(&format!("{}s timeout", CONNECT_TIMEOUT.as_secs())),
+            false,
+        );
+
+        let channel = match tokio::time::timeout(
+            CONNECT_TIMEOUT,
+            via_handle.channel_open_direct_tcpip(hop.host.clone(), hop.port as u32, "127.0.0.1", 0),
+        )
+        .await
+        {
+            Ok(Ok(channel)) => channel,
+            Ok(Err(e)) => {
+                trace("jump", "channel_failed", "direct-tcpip channel open failed", Some(&e.to_string()), true);
+                return Err(SshError::JumpHostFailed {
+                    hop: hop_label,
+                    via: via_label,
+                    detail: e.to_string(),
+                });
+            }
+            Err(_) => {
+                trace("jump", "channel_timeout", "direct-tcpip channel open timed out", None, true);
+                return Err(SshError::JumpHostFailed {
+                    hop: hop_label,
+                    via: via_label,
+                    detail: "direct-tcpip channel open timed out".to_string(),
+                });
+            }
+        };
+
+        trace("jump", "channel_ok", &format!("Tunnel to {} open", hop_label), None, false);
+        trace("ssh", "handshake", "Starting SSH handshake", Some(&hop_label), false);
+
+        let transcript = Arc::new(HandshakeTranscript::default());
+        let stream = InstrumentedStream::new(channel.into_stream(), transcript.clone());
+        let (disconnect_tx, disconnect_rx) = watch::channel::<Option<String>>(None);
+        let handler = ClientHandler {
+            app: app.clone(),
+            host: hop.host.clone(),
+            port: hop.port,
+            correlation_id: correlation_id.to_string(),
+            disconnect_tx,
+            verified_fingerprint: None,
+            hop: context,
+        };
+
+        let result = match tokio::time::timeout(CONNECT_TIMEOUT, client::connect_stream(config, stream, handler)).await {
+            Ok(result) => result,
+            Err(_) => {
+                let detail = format!("SSH handshake timed out after {}s", CONNECT_TIMEOUT.as_secs());
+                let diag = transcript.snapshot(correlation_id);
+                diagnostics::record_connect_attempt(diagnostics::ConnectAttemptRecord {
+                    timestamp: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis() as u64,
+                    attempt_id: correlation_id.to_string(),
+                    host: hop.host.clone(),
+                    port: hop.port,
+                    username: hop.username.clone(),
+                    addr: Some(format!("via {}", via_label)),
+                    resolved_addrs: Vec::new(),
+                    client_id: diag.client_id,
+                    server_id: diag.server_id,
+                    bytes_written: diag.bytes_written,
+                    bytes_read: diag.bytes_read,
+                    outcome: "tunnel_handshake_timeout".to_string(),
+                    outcome_detail: Some(detail.clone()),
+                });
+                trace("ssh", "handshake_timeout", "SSH handshake timed out", Some(&hop_label), true);
+                return Err(SshError::JumpHostFailed {
+                    hop: hop_label,
+                    via: via_label,
+                    detail,
+                });
+            }
         };
-        trace("auth", "start", &format!("Authenticating as {} via {}", username, auth_method_str), None, false);
+        let diag = transcript.snapshot(correlation_id);
+        diagnostics::record_connect_attempt(diagnostics::ConnectAttemptRecord {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            attempt_id: correlation_id.to_string(),
+            host: hop.host.clone(),
+            port: hop.port,
+            username: hop.username.clone(),
+            addr: Some(format!("via {}", via_label)),
+            resolved_addrs: Vec::new(),
+            client_id: diag.client_id.clone(),
+            server_id: diag.server_id.clone(),
+            bytes_written: diag.bytes_written,
+            bytes_read: diag.bytes_read,
+            outcome: if result.is_ok() { "tunnel_handshake_ok" } else { "tunnel_handshake_failed" }.to_string(),
+            outcome_detail: result.as_ref().err().map(|e| e.to_string()),
+        });
+
+        match result {
+            Ok(handle) => {
+                trace("ssh", "handshake_ok", "SSH handshake successful", diag.server_id.as_deref(), false);
+                Ok((handle, disconnect_rx))
+            }
+            Err(e) => {
+                trace("ssh", "handshake_failed", "SSH handshake failed", Some(&e.to_string()), true);
+                Err(match e {
+                    ClientError::HostKeyStore(detail) => SshError::ConnectionFailed(detail),
+                    ClientError::HostKeyUntrusted {
+                        host,
+                        port,
+                        key_type,
+                        fingerprint_sha256,
+                        public_key_openssh,
+                    } => SshError::HostKeyUntrusted {
+                        host,
+                        port,
+                        key_type,
+                        fingerprint_sha256,
+                        public_key_openssh,
+                    },
+                    ClientError::HostKeyMismatch {
+                        host,
+                        port,
+                        key_type,
+                        expected_fingerprint_sha256,
+                        actual_fingerprint_sha256,
+                        expected_public_key_openssh,
+                        actual_public_key_openssh,
+                    } => SshError::HostKeyMismatch {
+                        host,
+                        port,
+                        key_type,
+                        expected_fingerprint_sha256,
+                        actual_fingerprint_sha256,
+                        expected_public_key_openssh,
+                        actual_public_key_openssh,
+                    },
+                    ClientError::HostKeyRevoked {
+                        host,
+                        port,
+                        fingerprint_sha256,
+                    } => SshError::HostKeyRevoked {
+                        host,
+                        port,
+                        fingerprint_sha256,
+                    },
+                    ClientError::Russh(other) => SshError::JumpHostFailed {
+                        hop: hop_label,
+                        via: via_label,
+                        detail: other.to_string(),
+                    },
+                })
+            }
+        }
+    }
 
-        let auth_result = match &auth {
+    /// Authenticate an established session, emitting `auth` trace events.
+    ///
+    /// `AuthMethod::Chain` runs each method in turn: servers that require several methods
+    /// (e.g. `AuthenticationMethods publickey,keyboard-interactive`) answer a partially successful
+    /// step with a failure that still lists the remaining methods, so we move on to the next one.
+    async fn authenticate(
+        handle: &mut Handle<ClientHandler>,
+        hop: &JumpHost,
+        app: &AppHandle,
+        correlation_id: &str,
+    ) -> Result<(), SshError> {
+        let trace = |category: &str, step: &str, msg: &str, detail: Option<&str>, is_error: bool| {
+            let mut event = TraceEvent::new(category, step, msg).with_correlation_id(correlation_id);
+            if let Some(d) = detail {
+                event = event.with_detail(d);
+            }
+            if is_error {
+                event = event.error();
+            }
+            emit_trace(app, event);
+        };
+
+        trace("auth", "start", &format!("Authenticating as {} via {}", hop.username, hop.auth.label()), None, false);
+
+        let steps: Vec<&AuthMethod> = match &hop.auth {
+            AuthMethod::Chain(methods) => methods.iter().collect(),
+            other => vec![other],
+        };
+
+        for (idx, method) in steps.iter().enumerate() {
+            if idx > 0 {
+                trace(
+                    "auth",
+                    "continue",
+                    "Previous method not sufficient; continuing with next method",
+                    Some(&method.label()),
+                    false,
+                );
+            }
+            if Self::authenticate_step(handle, hop, method, app, correlation_id).await? {
+                trace("auth", "success", "Authentication successful", None, false);
+                return Ok(());
+            }
+        }
+
+        trace("auth", "rejected", "Authentication rejected by server", None, true);
+        Err(SshError::AuthenticationFailed(
+            "Authentication rejected".to_string(),
+        ))
+    }
+
+    /// Run a single authentication method; `Ok(false)` means the server did not (fully) accept it.
+    async fn authenticate_step(
+        handle: &mut Handle<ClientHandler>,
+        hop: &JumpHost,
+        auth: &AuthMethod,
+        app: &AppHandle,
+        correlation_id: &str,
+    ) -> Result<bool, SshError> {
+        let trace = |category: &str, step: &str, msg: &str, detail: Option<&str>, is_error: bool| {
+            let mut event = TraceEvent::new(category, step, msg).with_correlation_id(correlation_id);
+            if let Some(d) = detail {
+                event = event.with_detail(d);
+            }
+            if is_error {
+                event = event.error();
+            }
+            emit_trace(app, event);
+        };
+        let username = hop.username.as_str();
+
+        let auth_result = match auth {
             AuthMethod::Password(password) => {
                 trace("auth", "password", "Sending password authentication", None, false);
                 handle
@@ -855,37 +1433,210 @@ impl SshConnection {
                         SshError::AuthenticationFailed("No key pair loaded".to_string())
                     })?;
 
-                trace("auth", "publickey", "Sending public key authentication", None, false);
-                handle
-                    .authenticate_publickey(username, key)
+                let certificate = auth.load_certificate().await.map_err(|e| {
+                    trace("auth", "cert_load_failed", "Failed to load certificate", Some(&e.to_string()), true);
+                    SshError::AuthenticationFailed(e.to_string())
+                })?;
+
+                let mut accepted = false;
+                if let Some((cert_path, cert)) = certificate {
+                    let info = CertificateInfo::from_certificate(&cert_path, &cert);
+                    let detail = format!(
+                        "{} id={} valid_before={}",
+                        info.path,
+                        info.key_id,
+                        info.valid_before.map_or("forever".to_string(), |t| t.to_string())
+                    );
+                    if cert.public_key() != key.public_key().key_data() {
+                        trace("auth", "cert_mismatch", "Certificate does not match private key; skipping", Some(&detail), true);
+                    } else {
+                        if info.expired {
+                            trace("auth", "cert_expired", "Certificate has expired", Some(&detail), true);
+                        }
+                        trace("auth", "cert", "Sending OpenSSH certificate authentication", Some(&detail), false);
+                        accepted = handle
+                            .authenticate_openssh_cert(username, key.clone(), cert)
+                            .await
+                            .map_err(|e| {
+                                trace("auth", "failed", "Certificate auth error", Some(&e.to_string()), true);
+                                SshError::AuthenticationFailed(e.to_string())
+                            })?;
+                        if !accepted {
+                            trace("auth", "cert_rejected", "Certificate rejected; trying plain key", None, false);
+                        }
+                    }
+                }
+
+                if accepted {
+                    true
+                } else {
+                    trace("auth", "publickey", "Sending public key authentication", None, false);
+                    handle
+                        .authenticate_publickey(username, key)
+                        .await
+                        .map_err(|e| {
+                            trace("auth", "failed", "Public key auth error", Some(&e.to_string()), true);
+                            SshError::AuthenticationFailed(e.to_string())
+                        })?
+                }
+            }
+            #[cfg(unix)]
+            AuthMethod::Agent => {
+                let socket = std::env::var("SSH_AUTH_SOCK").ok();
+                trace("auth", "agent_connect", "Connecting to SSH agent", socket.as_deref(), false);
+                let mut agent = russh_keys::agent::client::AgentClient::connect_env()
                     .await
                     .map_err(|e| {
-                        trace("auth", "failed", "Public key auth error", Some(&e.to_string()), true);
-                        SshError::AuthenticationFailed(e.to_string())
-                    })?
-            }
-        };
+                        trace("auth", "agent_unavailable", "SSH agent unavailable", Some(&e.to_string()), true);
+                        SshError::AuthenticationFailed(format!("SSH agent unavailable: {}", e))
+                    })?;
 
-        if !auth_result {
-            trace("auth", "rejected", "Authentication rejected by server", None, true);
-            return Err(SshError::AuthenticationFailed(
-                "Authentication rejected".to_string(),
-            ));
-        }
+                let identities = agent.request_identities().await.map_err(|e| {
+                    trace("auth", "agent_list_failed", "Failed to list agent identities", Some(&e.to_string()), true);
+                    SshError::AuthenticationFailed(format!("Failed to list SSH agent identities: {}", e))
+                })?;
+                trace(
+                    "auth",
+                    "agent_identities",
+                    &format!("SSH agent offered {} identities", identities.len()),
+                    None,
+                    false,
+                );
 
-        trace("auth", "success", "Authentication successful", None, false);
-        trace("ssh", "connected", &format!("SSH connection established to {}:{}", host, port), None, false);
+                let mut accepted = false;
+                for identity in identities {
+                    let label = format!(
+                        "{} {} {}",
+                        identity.algorithm().as_str(),
+                        identity.fingerprint(HashAlg::Sha256),
+                        identity.comment()
+                    );
+                    let label = label.trim_end();
+                    trace("auth", "agent_try", "Trying agent identity", Some(label), false);
+                    match handle.authenticate_publickey_with(username, identity, &mut agent).await {
+                        Ok(true) => {
+                            trace("auth", "agent_accepted", "Agent identity accepted", Some(label), false);
+                            accepted = true;
+                            break;
+                        }
+                        Ok(false) => {}
+                        Err(e) => {
+                            trace(
+                                "auth",
+                                "agent_sign_failed",
+                                "Agent failed to sign with identity",
+                                Some(&format!("{}: {}", label, e)),
+                                true,
+                            );
+                        }
+                    }
+                }
+                accepted
+            }
+            #[cfg(not(unix))]
+            AuthMethod::Agent => {
+                trace("auth", "agent_unavailable", "SSH agent not supported on this platform", None, true);
+                return Err(SshError::AuthenticationFailed(
+                    "SSH agent authentication requires a Unix-domain agent socket".to_string(),
+                ));
+            }
+            AuthMethod::KeyboardInteractive { password } => {
+                trace("auth", "keyboard_interactive", "Starting keyboard-interactive authentication", None, false);
+                let mut response = handle
+                    .authenticate_keyboard_interactive_start(username, None)
+                    .await
+                    .map_err(|e| {
+                        trace("auth", "failed", "Keyboard-interactive auth error", Some(&e.to_string()), true);
+                        SshError::AuthenticationFailed(e.to_string())
+                    })?;
 
-        log::info!("SSH connection established to {}:{}", host, port);
+                let mut password_used = false;
+                let mut rounds = 0usize;
+                loop {
+                    let (name, instructions, prompts) = match response {
+                        KeyboardInteractiveAuthResponse::Success => break true,
+                        KeyboardInteractiveAuthResponse::Failure => break false,
+                        KeyboardInteractiveAuthResponse::InfoRequest {
+                            name,
+                            instructions,
+                            prompts,
+                        } => (name, instructions, prompts),
+                    };
+
+                    rounds += 1;
+                    if rounds > 16 {
+                        trace("auth", "failed", "Too many keyboard-interactive rounds", None, true);
+                        return Err(SshError::AuthenticationFailed(
+                            "Too many keyboard-interactive rounds".to_string(),
+                        ));
+                    }
 
-        Ok(Self {
-            handle,
-            sftp: None,
-            username: username.to_string(),
-            disconnect_rx: disconnect_rx.unwrap_or_else(|| watch::channel(None).1),
-        })
+                    // A single hidden "Password:" prompt is answered with the stored password (once);
+                    // everything else (OTP codes, extra questions) goes to the user.
+                    let answers = if prompts.is_empty() {
+                        Vec::new()
+                    } else if let Some(pass) = password.as_ref().filter(|_| {
+                        !password_used
+                            && prompts.len() == 1
+                            && !prompts[0].echo
+                            && prompts[0].prompt.to_lowercase().contains("password")
+                    }) {
+                        password_used = true;
+                        trace("auth", "keyboard_interactive_password", "Answering password prompt", None, false);
+                        vec![pass.clone()]
+                    } else {
+                        trace(
+                            "auth",
+                            "keyboard_interactive_prompt",
+                            &format!("Server sent {} prompt(s); waiting for user", prompts.len()),
+                            Some(&name),
+                            false,
+                        );
+                        keyboard_interactive::ask(
+                            app,
+                            keyboard_interactive::PromptRequest {
+                                host: hop.host.clone(),
+                                port: hop.port,
+                                username: username.to_string(),
+                                correlation_id: Some(correlation_id.to_string()),
+                                name,
+                                instructions,
+                                prompts: prompts
+                                    .into_iter()
+                                    .map(|p| keyboard_interactive::PromptItem {
+                                        prompt: p.prompt,
+                                        echo: p.echo,
+                                    })
+                                    .collect(),
+                            },
+                        )
+                        .await
+                        .map_err(|e| {
+                            trace("auth", "keyboard_interactive_aborted", "Keyboard-interactive prompt not answered", Some(&e), true);
+                            SshError::AuthenticationFailed(e)
+                        })?
+                    };
+
+                    response = handle
+                        .authenticate_keyboard_interactive_respond(answers)
+                        .await
+                        .map_err(|e| {
+                            trace("auth", "failed", "Keyboard-interactive auth error", Some(&e.to_string()), true);
+                            SshError::AuthenticationFailed(e.to_string())
+                        })?;
+                }
+            }
+            AuthMethod::Chain(_) => {
+                return Err(SshError::AuthenticationFailed(
+                    "Nested authentication chains are not supported".to_string(),
+                ));
+            }
+        };
+
+        Ok(auth_result)
     }
 
+
     /// Initialize SFTP subsystem
     async fn ensure_sftp(&mut self) -> Result<Arc<Mutex<SftpSession>>, SshError> {
         if let Some(sftp) = &self.sftp {
@@ -938,6 +1689,24 @@ impl SshConnection {
     }
 
     async fn read_file_with_stat_once(&mut self, path: &str) -> Result<(String, SftpStat), SshError> {
+        let (content, stat) = self.read_file_bytes_once(path).await?;
+        let text = String::from_utf8(content).map_err(|e| SshError::SftpError(e.to_string()))?;
+        Ok((text, stat))
+    }
+
+    /// Read raw file contents plus stat, without assuming any text encoding.
+    pub async fn read_file_bytes(&mut self, path: &str) -> Result<(Vec<u8>, SftpStat), SshError> {
+        match self.read_file_bytes_once(path).await {
+            Ok(result) => Ok(result),
+            Err(SshError::SftpTimeout | SshError::SftpSessionClosed) => {
+                self.reset_sftp();
+                self.read_file_bytes_once(path).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn read_file_bytes_once(&mut self, path: &str) -> Result<(Vec<u8>, SftpStat), SshError> {
         let sftp = self.ensure_sftp().await?;
         let sftp = sftp.lock().await;
 
@@ -950,13 +1719,48 @@ impl SshConnection {
 
         let metadata = sftp.metadata(path).await.map_err(map_sftp_error)?;
 
-        let text = String::from_utf8(content).map_err(|e| SshError::SftpError(e.to_string()))?;
         let stat = SftpStat {
             size: metadata.size.unwrap_or(0),
             mtime: metadata.mtime.map(|t| t as i64).unwrap_or(0),
         };
 
-        Ok((text, stat))
+        Ok((content, stat))
+    }
+
+    /// Read part of a file (see [`sftp::read_range`]).
+    pub async fn read_range(&mut self, path: &str, offset: u64, len: u64) -> Result<RangeRead, SshError> {
+        match self.read_range_once(path, offset, len).await {
+            Ok(result) => Ok(result),
+            Err(SshError::SftpTimeout | SshError::SftpSessionClosed) => {
+                self.reset_sftp();
+                self.read_range_once(path, offset, len).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn read_range_once(&mut self, path: &str, offset: u64, len: u64) -> Result<RangeRead, SshError> {
+        let sftp = self.ensure_sftp().await?;
+        let sftp = sftp.lock().await;
+        sftp::read_range(&sftp, path, offset, len).await
+    }
+
+    /// Read the last `lines` lines of a file (see [`sftp::read_tail`]).
+    pub async fn read_tail(&mut self, path: &str, lines: usize) -> Result<RangeRead, SshError> {
+        match self.read_tail_once(path, lines).await {
+            Ok(result) => Ok(result),
+            Err(SshError::SftpTimeout | SshError::SftpSessionClosed) => {
+                self.reset_sftp();
+                self.read_tail_once(path, lines).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn read_tail_once(&mut self, path: &str, lines: usize) -> Result<RangeRead, SshError> {
+        let sftp = self.ensure_sftp().await?;
+        let sftp = sftp.lock().await;
+        sftp::read_tail(&sftp, path, lines).await
     }
 
     /// List directory contents
@@ -974,28 +1778,10 @@ impl SshConnection {
 
     async fn list_dir_once(&mut self, path: &str) -> Result<Vec<SftpEntry>, SshError> {
         let sftp = self.ensure_sftp().await?;
+        let extensions = self.ensure_sftp_extensions().await;
         let sftp = sftp.lock().await;
 
-        let entries = sftp
-            .read_dir(path)
-            .await
-            .map_err(map_sftp_error)?;
-
-        let mut result = Vec::new();
-        for entry in entries {
-            let file_type = entry.file_type();
-            let metadata = entry.metadata();
-
-            result.push(SftpEntry {
-                name: entry.file_name(),
-                is_directory: file_type.is_dir(),
-                size: metadata.size.unwrap_or(0),
-                mtime: metadata.mtime.map(|t| t as i64).unwrap_or(0),
-                permissions: metadata.permissions.map(|p| format!("{:o}", p)),
-            });
-        }
-
-        Ok(result)
+        sftp::list_dir(&sftp, extensions.as_deref(), path).await
     }
 
     /// Read file contents
@@ -1028,31 +1814,148 @@ impl SshConnection {
     }
 
     /// Write content to a file
-    pub async fn write_file(&mut self, path: &str, content: &str) -> Result<(), SshError> {
-        match self.write_file_once(path, content).await {
-            Ok(()) => Ok(()),
+    pub async fn write_file(
+        &mut self,
+        path: &str,
+        content: &str,
+        precondition: &WritePrecondition,
+    ) -> Result<SaveReport, SshError> {
+        self.write_file_bytes(path, content.as_bytes(), precondition).await
+    }
+
+    /// Write raw bytes to a file, atomically where the server allows (see [`sftp::write_atomic`]).
+    /// The write is refused if the remote file no longer matches `precondition`.
+    pub async fn write_file_bytes(
+        &mut self,
+        path: &str,
+        content: &[u8],
+        precondition: &WritePrecondition,
+    ) -> Result<SaveReport, SshError> {
+        match self.write_file_once(path, content, precondition).await {
+            Ok(report) => Ok(report),
             Err(SshError::SftpTimeout | SshError::SftpSessionClosed) => {
                 self.reset_sftp();
-                self.write_file_once(path, content).await
+                self.write_file_once(path, content, precondition).await
             }
             Err(e) => Err(e),
         }
     }
 
-    async fn write_file_once(&mut self, path: &str, content: &str) -> Result<(), SshError> {
+    async fn write_file_once(
+        &mut self,
+        path: &str,
+        content: &[u8],
+        precondition: &WritePrecondition,
+    ) -> Result<SaveReport, SshError> {
         let sftp = self.ensure_sftp().await?;
+        let extensions = self.ensure_sftp_extensions().await;
         let sftp = sftp.lock().await;
 
-        let mut file = sftp
-            .create(path)
+        sftp::check_precondition(&sftp, path, precondition).await?;
+        sftp::write_atomic(&sftp, extensions.as_deref(), path, content).await
+    }
+
+    /// Best effort: without the extension session, saves fall back to in-place writes and
+    /// listings lack owner names. One session is kept per connection (reopened after
+    /// [`reset_sftp`](Self::reset_sftp)); a server that refuses it is not asked again.
+    async fn ensure_sftp_extensions(&mut self) -> Option<Arc<SftpExtensions>> {
+        if let Some(extensions) = &self.sftp_extensions {
+            return Some(extensions.clone());
+        }
+        if self.sftp_extensions_refused {
+            return None;
+        }
+
+        let opened = async {
+            let channel = self.handle.channel_open_session().await.ok()?;
+            channel.request_subsystem(true, "sftp").await.ok()?;
+            SftpExtensions::open(channel.into_stream(), 180).await.ok()
+        }
+        .await;
+        let Some(extensions) = opened else {
+            self.sftp_extensions_refused = true;
+            return None;
+        };
+
+        let extensions = Arc::new(extensions);
+        self.sftp_extensions = Some(extensions.clone());
+        Some(extensions)
+    }
+
+    /// Open a dedicated, initialized SFTP session for a streaming transfer. It is independent of
+    /// the shared session, so a long transfer neither holds the SFTP lock nor blocks this actor,
+    /// and its requests can be pipelined. `timeout_secs` applies to each request, not the transfer.
+    pub async fn open_sftp_channel(&self, timeout_secs: u64) -> Result<RawSftpSession, SshError> {
+        let channel = self
+            .handle
+            .channel_open_session()
             .await
-            .map_err(map_sftp_error)?;
+            .map_err(|e| SshError::ChannelError(e.to_string()))?;
 
-        file.write_all(content.as_bytes())
+        channel
+            .request_subsystem(true, "sftp")
             .await
-            .map_err(|e| SshError::SftpError(e.to_string()))?;
+            .map_err(|e| SshError::SftpError(format!("Failed to start SFTP subsystem: {}", e)))?;
 
-        Ok(())
+        let raw = RawSftpSession::new(channel.into_stream());
+        raw.set_timeout(timeout_secs).await;
+        raw.init().await.map_err(map_sftp_error)?;
+        Ok(raw)
+    }
+
+    /// Open a dedicated `SftpSession` for a long tree operation, independent of the shared one,
+    /// with an extensions session next to it when `extensions` is set and the server allows one.
+    /// `timeout_secs` applies to each request.
+    pub async fn open_sftp_session(
+        &self,
+        timeout_secs: u64,
+        extensions: bool,
+    ) -> Result<(SftpSession, Option<SftpExtensions>), SshError> {
+        let channel = self
+            .handle
+            .channel_open_session()
+            .await
+            .map_err(|e| SshError::ChannelError(e.to_string()))?;
+
+        channel
+            .request_subsystem(true, "sftp")
+            .await
+            .map_err(|e| SshError::SftpError(format!("Failed to start SFTP subsystem: {}", e)))?;
+
+        let sftp = SftpSession::new_opts(channel.into_stream(), Some(timeout_secs))
+            .await
+            .map_err(map_sftp_error)?;
+
+        if !extensions {
+            return Ok((sftp, None));
+        }
+        let extensions = match self.handle.channel_open_session().await {
+            Ok(channel) if channel.request_subsystem(true, "sftp").await.is_ok() => {
+                SftpExtensions::open(channel.into_stream(), timeout_secs).await.ok()
+            }
+            _ => None,
+        };
+        Ok((sftp, extensions))
+    }
+
+    /// Open a non-PTY session channel running `command`. The caller owns the channel: it writes
+    /// stdin, reads output and the exit status with `wait`, and closes it.
+    pub async fn open_exec_channel(&self, command: &str) -> Result<russh::Channel<client::Msg>, SshError> {
+        let channel = self
+            .handle
+            .channel_open_session()
+            .await
+            .map_err(|e| match e {
+                russh::Error::ChannelOpenFailure(reason) => {
+                    SshError::ChannelOpenFailed(format!("session ({:?})", reason))
+                }
+                other => SshError::ChannelError(other.to_string()),
+            })?;
+        channel
+            .exec(true, command)
+            .await
+            .map_err(|e| SshError::ChannelError(e.to_string()))?;
+        Ok(channel)
     }
 
     /// Get file metadata
@@ -1204,6 +2107,269 @@ impl SshConnection {
         Ok(())
     }
 
+    /// Change the permission bits of a file or directory
+    pub async fn chmod(&mut self, path: &str, mode: u32) -> Result<(), SshError> {
+        match self.chmod_once(path, mode).await {
+            Ok(()) => Ok(()),
+            Err(SshError::SftpTimeout | SshError::SftpSessionClosed) => {
+                self.reset_sftp();
+                self.chmod_once(path, mode).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn chmod_once(&mut self, path: &str, mode: u32) -> Result<(), SshError> {
+        let sftp = self.ensure_sftp().await?;
+        let sftp = sftp.lock().await;
+
+        sftp::chmod(&sftp, path, mode).await
+    }
+
+    /// Change the owner and/or group of a file or directory
+    pub async fn chown(&mut self, path: &str, uid: Option<u32>, gid: Option<u32>) -> Result<(), SshError> {
+        match self.chown_once(path, uid, gid).await {
+            Ok(()) => Ok(()),
+            Err(SshError::SftpTimeout | SshError::SftpSessionClosed) => {
+                self.reset_sftp();
+                self.chown_once(path, uid, gid).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn chown_once(&mut self, path: &str, uid: Option<u32>, gid: Option<u32>) -> Result<(), SshError> {
+        let sftp = self.ensure_sftp().await?;
+        let sftp = sftp.lock().await;
+
+        sftp::chown(&sftp, path, uid, gid).await
+    }
+
+    /// Set access and modification times, creating the file if needed
+    pub async fn touch(&mut self, path: &str, mtime: Option<i64>) -> Result<(), SshError> {
+        match self.touch_once(path, mtime).await {
+            Ok(()) => Ok(()),
+            Err(SshError::SftpTimeout | SshError::SftpSessionClosed) => {
+                self.reset_sftp();
+                self.touch_once(path, mtime).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn touch_once(&mut self, path: &str, mtime: Option<i64>) -> Result<(), SshError> {
+        let sftp = self.ensure_sftp().await?;
+        let sftp = sftp.lock().await;
+
+        sftp::touch(&sftp, path, mtime).await
+    }
+
+    /// Create a symlink at `path` pointing to `target`
+    pub async fn create_symlink(&mut self, path: &str, target: &str) -> Result<(), SshError> {
+        match self.create_symlink_once(path, target).await {
+            Ok(()) => Ok(()),
+            Err(SshError::SftpTimeout | SshError::SftpSessionClosed) => {
+                self.reset_sftp();
+                self.create_symlink_once(path, target).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn create_symlink_once(&mut self, path: &str, target: &str) -> Result<(), SshError> {
+        let sftp = self.ensure_sftp().await?;
+        let sftp = sftp.lock().await;
+
+        sftp::create_symlink(&sftp, path, target).await
+    }
+
+    /// Wait for a hop's next `hostkeys-00@openssh.com` announcement.
+    pub async fn next_host_key_announcement(&mut self) -> Option<HostKeyAnnouncement> {
+        self.announcements.recv().await
+    }
+
+    /// Prove the keys in `announcement` that are not trusted yet, then trust the proven ones and
+    /// retire keys the host no longer announces (`known_hosts::apply_announcement`).
+    ///
+    /// Each proof is a separate handshake to the same hop, dialled directly for the first hop and
+    /// through a `direct-tcpip` channel on the previous hop otherwise. Only the channels are opened
+    /// here; the handshakes run in a background task.
+    pub async fn prove_announced_host_keys(&self, app: &AppHandle, announcement: HostKeyAnnouncement) {
+        let trace = |step: &str, msg: &str, detail: String, is_error: bool| {
+            let mut event = TraceEvent::new("hostkey", step, msg)
+                .with_correlation_id(announcement.correlation_id.clone())
+                .with_detail(detail);
+            if is_error {
+                event = event.error();
+            }
+            emit_trace(app, event);
+        };
+
+        let candidates = match known_hosts::unproven_announced(
+            app,
+            &announcement.host,
+            announcement.port,
+            &announcement.verified_fingerprint,
+            &announcement.keys,
+        )
+        .await
+        {
+            Ok(Some(candidates)) => candidates,
+            Ok(None) => return,
+            Err(e) => return trace("announce_failed", "Failed to read announced host keys", e, true),
+        };
+
+        let via = announcement
+            .hop
+            .checked_sub(1)
+            .map(|idx| self.jump_handles.get(idx).unwrap_or(&self.handle));
+        let mut proofs = Vec::with_capacity(candidates.len());
+        for key in candidates {
+            let tunnel = match via {
+                None => None,
+                Some(via) => match tokio::time::timeout(
+                    CONNECT_TIMEOUT,
+                    via.channel_open_direct_tcpip(announcement.host.clone(), announcement.port as u32, "127.0.0.1", 0),
+                )
+                .await
+                {
+                    Ok(Ok(channel)) => Some(channel),
+                    _ => continue,
+                },
+            };
+            proofs.push((key, tunnel));
+        }
+
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let HostKeyAnnouncement {
+                host,
+                port,
+                correlation_id,
+                verified_fingerprint,
+                keys,
+                ..
+            } = announcement;
+            let mut proven = Vec::new();
+            for (key, tunnel) in proofs {
+                let ok = match tunnel {
+                    Some(channel) => prove_host_key(channel.into_stream(), &key).await,
+                    None => match tokio::time::timeout(
+                        CONNECT_TIMEOUT,
+                        tokio::net::TcpStream::connect((host.as_str(), port)),
+                    )
+                    .await
+                    {
+                        Ok(Ok(stream)) => prove_host_key(stream, &key).await,
+                        _ => false,
+                    },
+                };
+                if ok {
+                    proven.push(key);
+                } else {
+                    emit_trace(
+                        &app,
+                        TraceEvent::new("hostkey", "unproven", "Server did not prove an announced host key")
+                            .with_correlation_id(correlation_id.clone())
+                            .with_detail(format!("{}:{} {}", host, port, key.fingerprint(HashAlg::Sha256)))
+                            .error(),
+                    );
+                }
+            }
+
+            match known_hosts::apply_announcement(&app, &host, port, &verified_fingerprint, &keys, &proven).await {
+                Ok(outcome) => {
+                    for (step, msg, entry) in outcome
+                        .added
+                        .iter()
+                        .map(|e| ("learned", "Learned a proven host key", e))
+                        .chain(outcome.retired.iter().map(|e| ("retired", "Retired a host key no longer announced", e)))
+                    {
+                        emit_trace(
+                            &app,
+                            TraceEvent::new("hostkey", step, msg)
+                                .with_correlation_id(correlation_id.clone())
+                                .with_detail(format!(
+                                    "{}:{} {} {}",
+                                    host, port, entry.key_type, entry.fingerprint_sha256
+                                )),
+                        );
+                    }
+                }
+                Err(e) => emit_trace(
+                    &app,
+                    TraceEvent::new("hostkey", "announce_failed", "Failed to update announced host keys")
+                        .with_correlation_id(correlation_id)
+                        .with_detail(e)
+                        .error(),
+                ),
+            }
+        });
+    }
+
+    /// Open a `direct-tcpip` channel to `host:port` as seen from the server (`ssh -L`).
+    /// A refusal by the server only fails this channel, not the session.
+    pub async fn open_direct_tcpip(
+        &self,
+        host: &str,
+        port: u16,
+        originator_address: &str,
+        originator_port: u16,
+    ) -> Result<russh::Channel<client::Msg>, SshError> {
+        self.handle
+            .channel_open_direct_tcpip(host, port as u32, originator_address, originator_port as u32)
+            .await
+            .map_err(|e| match e {
+                russh::Error::ChannelOpenFailure(reason) => {
+                    SshError::ChannelOpenFailed(format!("{}:{} ({:?})", host, port, reason))
+                }
+                other => SshError::ChannelError(other.to_string()),
+            })
+    }
+
+    /// Ask the server to listen on `bind_host:bind_port` (`ssh -R`) and route its connections to
+    /// `route`. Returns the port actually bound, which the server picks when `bind_port` is 0.
+    pub async fn request_remote_forward(
+        &mut self,
+        bind_host: &str,
+        bind_port: u16,
+        route: RemoteRoute,
+    ) -> Result<u16, SshError> {
+        let bound = self
+            .handle
+            .tcpip_forward(bind_host, bind_port as u32)
+            .await
+            .map_err(|e| match e {
+                russh::Error::RequestDenied => {
+                    SshError::ForwardRejected(format!("{}:{}", bind_host, bind_port))
+                }
+                other => SshError::ChannelError(other.to_string()),
+            })?;
+        let bound = if bind_port == 0 { bound as u16 } else { bind_port };
+        self.remote_routes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(bound as u32, route);
+        Ok(bound)
+    }
+
+    /// Stop a remote forward on the server.
+    pub async fn cancel_remote_forward(&mut self, bind_host: &str, bind_port: u16) -> Result<(), SshError> {
+        self.remote_routes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&(bind_port as u32));
+        self.handle
+            .cancel_tcpip_forward(bind_host, bind_port as u32)
+            .await
+            .map_err(|e| match e {
+                russh::Error::RequestDenied => {
+                    SshError::ForwardRejected(format!("cancel {}:{}", bind_host, bind_port))
+                }
+                other => SshError::ChannelError(other.to_string()),
+            })
+    }
+
     /// Create a new PTY session
     pub async fn create_pty_session(
         &mut self,
@@ -1245,16 +2411,24 @@ impl SshConnection {
     pub async fn disconnect(&mut self) -> Result<(), SshError> {
         self.reset_sftp();
 
-        self.handle
+        let result = self
+            .handle
             .disconnect(Disconnect::ByApplication, "User requested disconnect", "en")
             .await
-            .map_err(|e| SshError::ConnectionFailed(e.to_string()))?;
+            .map_err(|e| SshError::ConnectionFailed(e.to_string()));
 
-        Ok(())
+        // Tear down jump hosts innermost-first; best-effort since the tunnel may already be gone.
+        for jump in self.jump_handles.drain(..).rev() {
+            let _ = jump
+                .disconnect(Disconnect::ByApplication, "User requested disconnect", "en")
+                .await;
+        }
+
+        result
     }
 }
 
-fn map_sftp_error(error: SftpClientError) -> SshError {
+pub(crate) fn map_sftp_error(error: SftpClientError) -> SshError {
     match error {
         SftpClientError::Timeout => SshError::SftpTimeout,
         SftpClientError::UnexpectedBehavior(msg) if msg.to_lowercase().contains("session closed") => {
@@ -0,0 +1,747 @@
+//! Minimal OpenSSH client config (`~/.ssh/config`) reader.
+//!
+//! Supports the subset needed to turn config aliases into connection profiles:
+//! `Host`, `Match host|originalhost|user|all`, `HostName`, `Port`, `User`, `IdentityFile`,
+//! `CertificateFile`, `ProxyJump` and `Include`. Like OpenSSH, the first value obtained for a keyword
+//! wins (except `IdentityFile`/`CertificateFile`, which accumulate).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const MAX_INCLUDE_DEPTH: usize = 16;
+const MAX_JUMP_DEPTH: usize = 8;
+const DEFAULT_IDENTITY_FILES: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+#[derive(Debug, Error)]
+pub enum SshConfigError {
+    #[error("Failed to read {path}: {detail}")]
+    Io { path: String, detail: String },
+    #[error("{path}:{line}: {detail}")]
+    Parse {
+        path: String,
+        line: usize,
+        detail: String,
+    },
+    #[error("Include nesting too deep at {0}")]
+    IncludeDepth(String),
+    #[error("ProxyJump chain too deep for {0}")]
+    JumpDepth(String),
+    #[error("No Host entry matches {0}")]
+    UnknownHost(String),
+}
+
+#[derive(Debug, Clone)]
+enum Criterion {
+    All,
+    Host(Vec<String>),
+    OriginalHost(Vec<String>),
+    User(Vec<String>),
+    /// A criterion we do not evaluate (e.g. `exec`); the block never matches.
+    Unsupported,
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    /// Directives before the first `Host`/`Match` line apply to every host.
+    Always,
+    Host(Vec<String>),
+    Match(Vec<Criterion>),
+}
+
+#[derive(Debug, Clone)]
+struct Block {
+    condition: Condition,
+    /// Lowercased keyword + arguments, in file order.
+    directives: Vec<(String, Vec<String>)>,
+}
+
+/// One `ProxyJump` element: `[user@]host[:port]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpSpec {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+/// Effective settings for a single alias after applying every matching block.
+#[derive(Debug, Clone)]
+pub struct ResolvedHost {
+    pub alias: String,
+    pub host_name: String,
+    pub port: u16,
+    pub user: Option<String>,
+    pub identity_files: Vec<String>,
+    pub certificate_files: Vec<String>,
+    /// Fully expanded jump chain (outermost first), each hop resolved through the config as well.
+    pub jump_hosts: Vec<ResolvedHost>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SshConfig {
+    blocks: Vec<Block>,
+}
+
+impl SshConfig {
+    /// Load the user's `~/.ssh/config` followed by the system-wide `/etc/ssh/ssh_config`.
+    ///
+    /// Missing files are not an error.
+    pub fn load_default() -> Result<Self, SshConfigError> {
+        let mut config = SshConfig::default();
+        if let Some(path) = user_config_path() {
+            if path.exists() {
+                let base = path.parent().unwrap_or(Path::new("")).to_path_buf();
+                config.parse_file(&path, &base, Condition::Always, 0)?;
+            }
+        }
+        let system = Path::new("/etc/ssh/ssh_config");
+        if cfg!(unix) && system.exists() {
+            config.parse_file(system, Path::new("/etc/ssh"), Condition::Always, 0)?;
+        }
+        Ok(config)
+    }
+
+    /// Load a single config file (and its includes, relative ones resolved against its directory).
+    pub fn load_file(path: &Path) -> Result<Self, SshConfigError> {
+        let mut config = SshConfig::default();
+        let base = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        config.parse_file(path, &base, Condition::Always, 0)?;
+        Ok(config)
+    }
+
+    /// Concrete aliases declared in `Host` lines (patterns and negations are skipped).
+    pub fn aliases(&self) -> Vec<String> {
+        let mut seen = Vec::new();
+        for block in &self.blocks {
+            if let Condition::Host(patterns) = &block.condition {
+                for pattern in patterns {
+                    let concrete = !pattern.starts_with('!') && !pattern.contains(['*', '?']);
+                    if concrete && !seen.contains(pattern) {
+                        seen.push(pattern.clone());
+                    }
+                }
+            }
+        }
+        seen
+    }
+
+    /// Resolve `alias` the way `ssh alias` would, including its ProxyJump chain.
+    pub fn resolve(&self, alias: &str) -> Result<ResolvedHost, SshConfigError> {
+        self.resolve_with_depth(alias, 0)
+    }
+
+    fn resolve_with_depth(&self, alias: &str, depth: usize) -> Result<ResolvedHost, SshConfigError> {
+        if depth > MAX_JUMP_DEPTH {
+            return Err(SshConfigError::JumpDepth(alias.to_string()));
+        }
+
+        let mut options: HashMap<&str, Vec<String>> = HashMap::new();
+        let mut identity_files: Vec<String> = Vec::new();
+        let mut certificate_files: Vec<String> = Vec::new();
+        let mut matched_host_block = false;
+
+        for block in &self.blocks {
+            let host_name = options
+                .get("hostname")
+                .and_then(|v| v.first())
+                .map(|h| expand_tokens(h, alias, alias, None, None))
+                .unwrap_or_else(|| alias.to_string());
+            let user = options.get("user").and_then(|v| v.first()).cloned();
+
+            let matches = match &block.condition {
+                Condition::Always => true,
+                Condition::Host(patterns) => {
+                    let ok = match_pattern_list(alias, patterns);
+                    matched_host_block |= ok;
+                    ok
+                }
+                Condition::Match(criteria) => criteria.iter().all(|c| match c {
+                    Criterion::All => true,
+                    Criterion::Host(p) => match_pattern_list(&host_name, p),
+                    Criterion::OriginalHost(p) => match_pattern_list(alias, p),
+                    Criterion::User(p) => match_pattern_list(user.as_deref().unwrap_or(&local_user()), p),
+                    Criterion::Unsupported => false,
+                }),
+            };
+            if !matches {
+                continue;
+            }
+
+            for (keyword, args) in &block.directives {
+                match keyword.as_str() {
+                    "identityfile" => identity_files.extend(args.iter().cloned()),
+                    "certificatefile" => certificate_files.extend(args.iter().cloned()),
+                    "hostname" | "port" | "user" | "proxyjump" => {
+                        options.entry(keyword.as_str()).or_insert_with(|| args.clone());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if !matched_host_block && options.is_empty() {
+            return Err(SshConfigError::UnknownHost(alias.to_string()));
+        }
+
+        let host_name = options
+            .get("hostname")
+            .and_then(|v| v.first())
+            .map(|h| expand_tokens(h, alias, alias, None, None))
+            .unwrap_or_else(|| alias.to_string());
+        let port = match options.get("port").and_then(|v| v.first()) {
+            Some(p) => p.parse::<u16>().map_err(|_| SshConfigError::Parse {
+                path: "<resolved>".to_string(),
+                line: 0,
+                detail: format!("invalid Port {:?} for {}", p, alias),
+            })?,
+            None => 22,
+        };
+        let user = options.get("user").and_then(|v| v.first()).cloned();
+
+        let identity_files = identity_files
+            .iter()
+            .map(|f| expand_tokens(f, alias, &host_name, Some(port), user.as_deref()))
+            .map(|f| expand_home(&f))
+            .collect();
+        let certificate_files = certificate_files
+            .iter()
+            .map(|f| expand_tokens(f, alias, &host_name, Some(port), user.as_deref()))
+            .map(|f| expand_home(&f))
+            .collect();
+
+        let mut jump_hosts = Vec::new();
+        let proxy_jump = options.get("proxyjump").map(|v| v.join(","));
+        if let Some(spec) = proxy_jump.filter(|s| !s.eq_ignore_ascii_case("none")) {
+            for jump in parse_proxy_jump(&spec) {
+                // Jump hosts are themselves resolved through the config, like OpenSSH does.
+                let mut resolved = match self.resolve_with_depth(&jump.host, depth + 1) {
+                    Ok(r) => r,
+                    Err(SshConfigError::UnknownHost(_)) => ResolvedHost {
+                        alias: jump.host.clone(),
+                        host_name: jump.host.clone(),
+                        port: 22,
+                        user: None,
+                        identity_files: Vec::new(),
+                        certificate_files: Vec::new(),
+                        jump_hosts: Vec::new(),
+                    },
+                    Err(e) => return Err(e),
+                };
+                if let Some(u) = jump.user {
+                    resolved.user = Some(u);
+                }
+                if let Some(p) = jump.port {
+                    resolved.port = p;
+                }
+                jump_hosts.append(&mut resolved.jump_hosts);
+                jump_hosts.push(resolved);
+            }
+        }
+
+        Ok(ResolvedHost {
+            alias: alias.to_string(),
+            host_name,
+            port,
+            user,
+            identity_files,
+            certificate_files,
+            jump_hosts,
+        })
+    }
+
+    /// Parse `path` into blocks. Relative `Include`s are resolved against `include_base`, the
+    /// directory of the top-level file (`~/.ssh` for the user config, `/etc/ssh` for the system one).
+    fn parse_file(
+        &mut self,
+        path: &Path,
+        include_base: &Path,
+        condition: Condition,
+        depth: usize,
+    ) -> Result<(), SshConfigError> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(SshConfigError::IncludeDepth(path.display().to_string()));
+        }
+        let text = std::fs::read_to_string(path).map_err(|e| SshConfigError::Io {
+            path: path.display().to_string(),
+            detail: e.to_string(),
+        })?;
+
+        self.blocks.push(Block {
+            condition,
+            directives: Vec::new(),
+        });
+
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (keyword, rest) = split_keyword(line);
+            let args = tokenize(rest).map_err(|detail| SshConfigError::Parse {
+                path: path.display().to_string(),
+                line: line_no,
+                detail,
+            })?;
+            let keyword = keyword.to_ascii_lowercase();
+
+            match keyword.as_str() {
+                "host" => self.blocks.push(Block {
+                    condition: Condition::Host(args),
+                    directives: Vec::new(),
+                }),
+                "match" => {
+                    let location = format!("{}:{}", path.display(), line_no);
+                    let criteria = parse_match(&args, &location).map_err(|detail| SshConfigError::Parse {
+                        path: path.display().to_string(),
+                        line: line_no,
+                        detail,
+                    })?;
+                    self.blocks.push(Block {
+                        condition: Condition::Match(criteria),
+                        directives: Vec::new(),
+                    });
+                }
+                "include" => {
+                    let current = self
+                        .blocks
+                        .last()
+                        .map(|b| b.condition.clone())
+                        .unwrap_or(Condition::Always);
+                    for pattern in &args {
+                        for included in expand_include(pattern, include_base) {
+                            self.parse_file(&included, include_base, current.clone(), depth + 1)?;
+                        }
+                    }
+                    // Directives after the Include keep applying to the enclosing block.
+                    self.blocks.push(Block {
+                        condition: current,
+                        directives: Vec::new(),
+                    });
+                }
+                _ => {
+                    if let Some(block) = self.blocks.last_mut() {
+                        block.directives.push((keyword, args));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Default location of the user's config file.
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+/// First of OpenSSH's default identity files that exists, if any.
+pub fn default_identity_file() -> Option<String> {
+    let ssh_dir = dirs::home_dir()?.join(".ssh");
+    DEFAULT_IDENTITY_FILES
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .find(|p| p.exists())
+        .map(|p| p.display().to_string())
+}
+
+/// Parse a `ProxyJump` value: comma-separated `[user@]host[:port]` (IPv6 in brackets).
+pub fn parse_proxy_jump(value: &str) -> Vec<JumpSpec> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|item| {
+            let item = item.strip_prefix("ssh://").unwrap_or(item);
+            let (user, host_port) = match item.rsplit_once('@') {
+                Some((u, h)) => (Some(u.to_string()), h),
+                None => (None, item),
+            };
+            let (host, port) = if let Some(rest) = host_port.strip_prefix('[') {
+                match rest.split_once(']') {
+                    Some((h, tail)) => (h.to_string(), tail.strip_prefix(':').and_then(|p| p.parse().ok())),
+                    None => (rest.to_string(), None),
+                }
+            } else {
+                match host_port.rsplit_once(':') {
+                    Some((h, p)) if !h.contains(':') => (h.to_string(), p.parse().ok()),
+                    _ => (host_port.to_string(), None),
+                }
+            };
+            JumpSpec { user, host, port }
+        })
+        .collect()
+}
+
+pub fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+fn split_keyword(line: &str) -> (&str, &str) {
+    // `Keyword value`, `Keyword=value` and `Keyword = value` are all valid.
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let (keyword, rest) = line.split_at(end);
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim_start();
+    (keyword, rest)
+}
+
+fn tokenize(s: &str) -> Result<Vec<String>, String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+
+    for c in s.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            '#' if !in_quotes && !has_token => break,
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    out.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quote".to_string());
+    }
+    if has_token {
+        out.push(current);
+    }
+    Ok(out)
+}
+
+/// Parse `Match` arguments. Criteria we cannot evaluate (`exec`, `localnetwork`, or ones we do not
+/// know) make the block non-matching rather than failing the whole file.
+fn parse_match(args: &[String], location: &str) -> Result<Vec<Criterion>, String> {
+    let mut criteria = Vec::new();
+    let mut iter = args.iter();
+    while let Some(word) = iter.next() {
+        let lower = word.to_ascii_lowercase();
+        let criterion = match lower.as_str() {
+            "all" => Criterion::All,
+            "canonical" | "final" => Criterion::Unsupported,
+            "host" | "originalhost" | "user" | "localuser" | "exec" | "localnetwork" | "tagged" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("Match {} requires an argument", word))?;
+                let patterns: Vec<String> = value.split(',').map(|p| p.to_string()).collect();
+                match lower.as_str() {
+                    "host" => Criterion::Host(patterns),
+                    "originalhost" => Criterion::OriginalHost(patterns),
+                    "user" => Criterion::User(patterns),
+                    // Never run `exec` criteria.
+                    _ => {
+                        log::warn!("{}: Match {} is not supported; block treated as non-matching", location, word);
+                        Criterion::Unsupported
+                    }
+                }
+            }
+            _ => {
+                // We cannot tell which of the remaining words are its arguments; the block is
+                // non-matching either way.
+                log::warn!("{}: unknown Match criterion {:?}; block treated as non-matching", location, word);
+                criteria.push(Criterion::Unsupported);
+                break;
+            }
+        };
+        criteria.push(criterion);
+    }
+    Ok(criteria)
+}
+
+fn expand_include(pattern: &str, base: &Path) -> Vec<PathBuf> {
+    let expanded = expand_home(pattern);
+    let full = if Path::new(&expanded).is_absolute() {
+        PathBuf::from(expanded)
+    } else {
+        base.join(expanded)
+    };
+
+    let Some(pattern) = full.to_str() else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = match glob::glob(pattern) {
+        Ok(iter) => iter.filter_map(Result::ok).filter(|p| p.is_file()).collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths
+}
+
+fn expand_home(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest).display().to_string();
+        }
+    }
+    path.to_string()
+}
+
+/// Expand the `%` tokens OpenSSH supports in `HostName`/`IdentityFile`.
+fn expand_tokens(value: &str, alias: &str, host: &str, port: Option<u16>, user: Option<&str>) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('h') => out.push_str(host),
+            Some('n') => out.push_str(alias),
+            Some('p') => out.push_str(&port.unwrap_or(22).to_string()),
+            Some('r') => out.push_str(user.unwrap_or(&local_user())),
+            Some('u') => out.push_str(&local_user()),
+            Some('d') => {
+                if let Some(home) = dirs::home_dir() {
+                    out.push_str(&home.display().to_string());
+                }
+            }
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// OpenSSH pattern-list semantics: any negated match rejects, otherwise at least one positive match.
+pub(crate) fn match_pattern_list(value: &str, patterns: &[String]) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(&negated.to_ascii_lowercase(), &value.to_ascii_lowercase()) {
+                return false;
+            }
+        } else if wildcard_match(&pattern.to_ascii_lowercase(), &value.to_ascii_lowercase()) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// `*` / `?` glob match (no character classes), as used by `Host` patterns.
+pub(crate) fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let v: Vec<char> = value.chars().collect();
+    let (mut pi, mut vi) = (0usize, 0usize);
+    let mut star: Option<(usize, usize)> = None;
+
+    while vi < v.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == v[vi]) {
+            pi += 1;
+            vi += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, vi));
+            pi += 1;
+        } else if let Some((star_pi, star_vi)) = star {
+            pi = star_pi + 1;
+            vi = star_vi + 1;
+            star = Some((star_pi, star_vi + 1));
+        } else {
+            return false;
+        }
+    }
+    while pi < p.len() && p[pi] == '*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A scratch directory removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("driftcode-ssh-config-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn write(&self, rel: &str, text: &str) -> PathBuf {
+            let path = self.0.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, text).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn load(name: &str, text: &str) -> (TempDir, SshConfig) {
+        let dir = TempDir::new(name);
+        let path = dir.write("config", text);
+        let config = SshConfig::load_file(&path).unwrap();
+        (dir, config)
+    }
+
+    #[test]
+    fn resolves_alias_settings() {
+        let (_dir, config) = load(
+            "alias",
+            "Host web\n  HostName %n.example.com\n  Port 2222\n  User deploy\n  IdentityFile /keys/%r@%h\n",
+        );
+        let host = config.resolve("web").unwrap();
+        assert_eq!(host.host_name, "web.example.com");
+        assert_eq!(host.port, 2222);
+        assert_eq!(host.user.as_deref(), Some("deploy"));
+        assert_eq!(host.identity_files, vec!["/keys/deploy@web.example.com".to_string()]);
+        assert!(host.jump_hosts.is_empty());
+    }
+
+    #[test]
+    fn lists_concrete_aliases_only() {
+        let (_dir, config) = load("aliases", "Host web db\nHost *.internal !bastion\nHost web\n");
+        assert_eq!(config.aliases(), vec!["web".to_string(), "db".to_string()]);
+    }
+
+    #[test]
+    fn unknown_alias_is_an_error() {
+        let (_dir, config) = load("unknown", "Host web\n  Port 2222\n");
+        assert!(matches!(config.resolve("db"), Err(SshConfigError::UnknownHost(_))));
+    }
+
+    #[test]
+    fn first_obtained_value_wins() {
+        let (_dir, config) = load(
+            "first-match",
+            "Host web\n  Port 2222\n  Port 3333\n  IdentityFile /keys/web\nHost *\n  Port 22\n  User admin\n  IdentityFile /keys/default\n",
+        );
+        let host = config.resolve("web").unwrap();
+        assert_eq!(host.port, 2222);
+        assert_eq!(host.user.as_deref(), Some("admin"));
+        assert_eq!(
+            host.identity_files,
+            vec!["/keys/web".to_string(), "/keys/default".to_string()]
+        );
+    }
+
+    #[test]
+    fn match_host_sees_resolved_hostname() {
+        let (_dir, config) = load(
+            "match",
+            "Host web\n  HostName web.prod.example.com\nMatch host *.prod.example.com\n  User ops\n",
+        );
+        assert_eq!(config.resolve("web").unwrap().user.as_deref(), Some("ops"));
+    }
+
+    #[test]
+    fn unsupported_match_criteria_do_not_match() {
+        let (_dir, config) = load(
+            "match-unsupported",
+            "Match exec \"test -f /x\"\n  User exec\nMatch localnetwork 10.0.0.0/8\n  User net\n\
+             Match somethingnew foo host web\n  User new\nHost web\n  User deploy\n",
+        );
+        assert_eq!(config.resolve("web").unwrap().user.as_deref(), Some("deploy"));
+    }
+
+    #[test]
+    fn relative_include_resolves_against_config_dir() {
+        let dir = TempDir::new("include");
+        dir.write("conf.d/10-web", "Host web\n  HostName 10.0.0.5\n");
+        dir.write("conf.d/20-db", "Host db\n  Port 5432\n");
+        let path = dir.write("config", "Include conf.d/*\nHost *\n  User fallback\n");
+
+        let config = SshConfig::load_file(&path).unwrap();
+        assert_eq!(config.resolve("web").unwrap().host_name, "10.0.0.5");
+        assert_eq!(config.resolve("db").unwrap().port, 5432);
+        assert_eq!(config.resolve("db").unwrap().user.as_deref(), Some("fallback"));
+    }
+
+    #[test]
+    fn include_inside_host_block_keeps_its_condition() {
+        let dir = TempDir::new("include-scoped");
+        dir.write("web.conf", "Port 2200\n");
+        let path = dir.write("config", "Host web\n  Include web.conf\n  User deploy\nHost db\n");
+
+        let config = SshConfig::load_file(&path).unwrap();
+        let web = config.resolve("web").unwrap();
+        assert_eq!(web.port, 2200);
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        let db = config.resolve("db").unwrap();
+        assert_eq!(db.port, 22);
+        assert_eq!(db.user, None);
+    }
+
+    #[test]
+    fn parses_proxy_jump_specs() {
+        assert_eq!(
+            parse_proxy_jump("alice@bastion:2200, gw ,[fe80::1]:2022,ssh://bob@edge"),
+            vec![
+                JumpSpec {
+                    user: Some("alice".to_string()),
+                    host: "bastion".to_string(),
+                    port: Some(2200),
+                },
+                JumpSpec {
+                    user: None,
+                    host: "gw".to_string(),
+                    port: None,
+                },
+                JumpSpec {
+                    user: None,
+                    host: "fe80::1".to_string(),
+                    port: Some(2022),
+                },
+                JumpSpec {
+                    user: Some("bob".to_string()),
+                    host: "edge".to_string(),
+                    port: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_proxy_jump_chain_through_config() {
+        let (_dir, config) = load(
+            "jump",
+            "Host target\n  ProxyJump ops@bastion:2200\nHost bastion\n  HostName bastion.example.com\n  ProxyJump edge\nHost edge\n  User gate\n",
+        );
+        let target = config.resolve("target").unwrap();
+        let hops: Vec<(&str, u16, Option<&str>)> = target
+            .jump_hosts
+            .iter()
+            .map(|h| (h.host_name.as_str(), h.port, h.user.as_deref()))
+            .collect();
+        assert_eq!(
+            hops,
+            vec![("edge", 22, Some("gate")), ("bastion.example.com", 2200, Some("ops"))]
+        );
+    }
+
+    #[test]
+    fn proxy_jump_none_disables_jumping() {
+        let (_dir, config) = load("jump-none", "Host direct\n  ProxyJump none\nHost *\n  ProxyJump bastion\n");
+        assert!(config.resolve("direct").unwrap().jump_hosts.is_empty());
+    }
+}
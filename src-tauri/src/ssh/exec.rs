@@ -0,0 +1,845 @@
+//! Non-interactive command execution over exec channels (no PTY).
+//!
+//! The command runs under `sh -c` (a login shell by default, so `PATH` matches the terminal) with
+//! the requested environment and working directory set by the wrapper script: servers accept
+//! `env` requests only for names listed in `AcceptEnv`. stdout and stderr stay separate. A started
+//! command streams them as `exec_output` events and ends with `exec_done`; [`run`] collects them
+//! instead.
+//!
+//! Without a PTY, closing the channel does not hang the command up, and servers before OpenSSH 7.9
+//! ignore `signal` requests. So an outer `sh -c` first prints its PID behind a per-command marker,
+//! before any profile script can write, then `exec`s the wrapper; cancelling or timing out sends
+//! `TERM` to the command's whole process group over a second exec channel.
+
+use crate::problems::{Diagnostic, MatcherKind, ProblemMatcher};
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::SshError;
+use crate::ssh::pty::shell_escape;
+use crate::state::AppState;
+use crate::trace::{emit_trace, TraceEvent};
+use russh::{ChannelMsg, Sig};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{oneshot, watch, Mutex};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+/// Output kept per stream by [`run`]
+const OUTPUT_LIMIT: usize = 1024 * 1024;
+/// Longest text after the marker still read as the wrapper's PID
+const PID_LIMIT: usize = 20;
+const KILL_TIMEOUT: Duration = Duration::from_secs(5);
+/// Timeout of [`run`] when the options set none
+const RUN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Error)]
+pub enum ExecError {
+    #[error("Connection not found")]
+    ConnectionNotFound,
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error("Invalid environment variable name: {0}")]
+    InvalidEnv(String),
+    #[error("A command with ID {0} is already running")]
+    DuplicateId(String),
+    #[error(transparent)]
+    Ssh(#[from] SshError),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExecOptions {
+    /// ID to track the command under instead of a generated one, so it can be cancelled while
+    /// [`run`] is still waiting for it.
+    pub id: Option<String>,
+    /// Working directory; `~` and `~/…` are relative to the remote home.
+    pub cwd: Option<String>,
+    pub env: BTreeMap<String, String>,
+    /// Written to the command's stdin, which is then closed. Without it stdin is closed at once.
+    pub stdin: Option<String>,
+    /// The command's process group is sent `TERM` and its channel closed after this long. [`run`]
+    /// defaults to ten minutes.
+    pub timeout_ms: Option<u64>,
+    /// Run under `sh -lc` so profile scripts set up `PATH`.
+    pub login_shell: bool,
+    /// Match the output for diagnostics (an empty list enables every built-in matcher); relative
+    /// paths resolve against `cwd`. Only used by [`start`].
+    pub problem_matchers: Option<Vec<MatcherKind>>,
+}
+
+impl Default for ExecOptions {
+    fn default() -> Self {
+        Self {
+            id: None,
+            cwd: None,
+            env: BTreeMap::new(),
+            stdin: None,
+            timeout_ms: None,
+            login_shell: true,
+            problem_matchers: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecStatus {
+    Running,
+    /// Ran to the end; see `exitCode` or `signal`
+    Completed,
+    TimedOut,
+    Cancelled,
+    /// Could not be started, was refused by the server, or the connection dropped
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecInfo {
+    pub id: String,
+    pub connection_id: String,
+    pub command: String,
+    pub cwd: Option<String>,
+    pub status: ExecStatus,
+    pub exit_code: Option<u32>,
+    /// Name of the signal that killed the command, without the `SIG` prefix
+    pub signal: Option<String>,
+    pub core_dumped: bool,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+/// Payload of `exec_output`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecOutputEvent {
+    pub exec_id: String,
+    pub stream: ExecStream,
+    pub data: String,
+}
+
+/// Payload of `exec_problems`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecProblemsEvent {
+    pub exec_id: String,
+    /// Absolute directory relative paths were resolved against
+    pub root: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Result of [`run`]: the finished command and its collected output.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecOutput {
+    #[serde(flatten)]
+    pub info: ExecInfo,
+    pub stdout: String,
+    pub stderr: String,
+    /// Output beyond 1 MiB per stream was dropped
+    pub truncated: bool,
+}
+
+struct ExecEntry {
+    info: ExecInfo,
+    cancel: watch::Sender<bool>,
+}
+
+static EXECS: OnceLock<StdMutex<HashMap<String, ExecEntry>>> = OnceLock::new();
+
+fn registry() -> &'static StdMutex<HashMap<String, ExecEntry>> {
+    EXECS.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Send a request to the connection's current actor and wait for its reply.
+async fn actor_request<T>(
+    app: &AppHandle,
+    connection_id: &str,
+    request: impl FnOnce(oneshot::Sender<Result<T, SshError>>) -> ConnectionRequest,
+) -> Result<T, ExecError> {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    }
+    .ok_or(ExecError::ConnectionNotFound)?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| ExecError::ConnectionClosed)?;
+    Ok(rx.await.map_err(|_| ExecError::ConnectionClosed)??)
+}
+
+/// Resolves once cancellation is requested. Wraps `wait_for`, whose guard is not `Send`.
+async fn cancel_requested(cancel: &mut watch::Receiver<bool>) {
+    let _ = cancel.wait_for(|c| *c).await;
+}
+
+/// The exec request string: `command` wrapped with exports and a `cd`. Fails with the offending
+/// name if an environment variable name is not a shell identifier.
+pub(crate) fn build_command(command: &str, options: &ExecOptions) -> Result<String, String> {
+    let mut script = String::new();
+    for (name, value) in &options.env {
+        let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(name.clone());
+        }
+        script.push_str(&format!("export {}={}; ", name, shell_escape(value)));
+    }
+    if let Some(cwd) = options.cwd.as_deref().filter(|cwd| !cwd.is_empty()) {
+        let dir = match cwd.strip_prefix('~') {
+            Some("") => "\"$HOME\"".to_string(),
+            Some(rest) if rest.starts_with('/') => format!("\"$HOME\"{}", shell_escape(rest)),
+            _ => shell_escape(cwd),
+        };
+        script.push_str(&format!("cd -- {} || exit 1; ", dir));
+    }
+    script.push_str(command);
+    let shell = if options.login_shell { "sh -lc" } else { "sh -c" };
+    Ok(format!("{} {}", shell, shell_escape(&script)))
+}
+
+/// An exec request string from [`build_tracked_command`] and the marker of its PID line.
+pub(crate) struct TrackedCommand {
+    pub command: String,
+    pub marker: String,
+}
+
+/// As [`build_command`], but an outer shell first prints a marker and its PID on a line of its
+/// own, then `exec`s the wrapper under the same PID. [`PidScanner`] finds that line in stdout.
+/// Output of profile scripts follows it.
+pub(crate) fn build_tracked_command(command: &str, options: &ExecOptions) -> Result<TrackedCommand, String> {
+    let marker = pid_marker();
+    let script = format!("echo {}$$; exec {}", marker, build_command(command, options)?);
+    Ok(TrackedCommand {
+        command: format!("sh -c {}", shell_escape(&script)),
+        marker,
+    })
+}
+
+/// As [`build_tracked_command`], but the wrapper prints the PID line itself, after any profile
+/// scripts and just before `command`, so everything after the line is the command's own output.
+/// For commands whose stdout is a protocol.
+pub(crate) fn build_protocol_command(command: &str, options: &ExecOptions) -> Result<TrackedCommand, String> {
+    let marker = pid_marker();
+    let command = build_command(&format!("echo {}$$; {}", marker, command), options)?;
+    Ok(TrackedCommand { command, marker })
+}
+
+/// Only `[a-z0-9_]`, so it needs no quoting, and no output will contain it by chance.
+fn pid_marker() -> String {
+    format!("__driftcode_pid_{}_", Uuid::new_v4().simple())
+}
+
+/// Finds the line [`build_tracked_command`] prints in stdout and passes everything else through,
+/// whatever was written before it.
+pub(crate) struct PidScanner {
+    marker: String,
+    /// Start of a line that may still turn out to be the PID line
+    held: Vec<u8>,
+    /// Inside a line already known to be output
+    mid_line: bool,
+    found: bool,
+    pid: Option<u32>,
+}
+
+impl PidScanner {
+    pub(crate) fn new(marker: String) -> Self {
+        Self {
+            marker,
+            held: Vec::new(),
+            mid_line: false,
+            found: false,
+            pid: None,
+        }
+    }
+
+    pub(crate) fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Take a chunk of stdout and return the part that is the command's output.
+    pub(crate) fn scan(&mut self, data: &[u8]) -> Vec<u8> {
+        let (mut output, after) = self.scan_parts(data);
+        output.extend_from_slice(&after);
+        output
+    }
+
+    /// As [`scan`](Self::scan), but split into the output before the PID line and after it.
+    pub(crate) fn scan_parts(&mut self, mut data: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut output = Vec::new();
+        while !self.found && !data.is_empty() {
+            let line_end = data.iter().position(|&b| b == b'\n').map(|newline| newline + 1);
+            let (part, rest) = data.split_at(line_end.unwrap_or(data.len()));
+            data = rest;
+            if self.mid_line {
+                output.extend_from_slice(part);
+                self.mid_line = line_end.is_none();
+                continue;
+            }
+            self.held.extend_from_slice(part);
+            if line_end.is_some() {
+                let line = std::mem::take(&mut self.held);
+                match line.strip_prefix(self.marker.as_bytes()) {
+                    Some(pid) => {
+                        self.found = true;
+                        self.pid = std::str::from_utf8(pid).ok().and_then(|pid| pid.trim().parse().ok());
+                    }
+                    None => output.extend_from_slice(&line),
+                }
+            } else if !self.may_be_pid_line() {
+                output.append(&mut self.held);
+                self.mid_line = true;
+            }
+        }
+        (output, data.to_vec())
+    }
+
+    /// Whether the unfinished line held so far can still become the PID line.
+    fn may_be_pid_line(&self) -> bool {
+        let marker = self.marker.as_bytes();
+        marker.starts_with(&self.held)
+            || (self.held.starts_with(marker) && self.held.len() <= marker.len() + PID_LIMIT)
+    }
+
+    /// Output held back when the stream ended inside a possible PID line.
+    pub(crate) fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.held)
+    }
+}
+
+/// Decodes a byte stream as UTF-8, holding back a character split across chunks.
+#[derive(Default)]
+struct Utf8Chunks {
+    pending: Vec<u8>,
+}
+
+impl Utf8Chunks {
+    fn decode(&mut self, data: &[u8]) -> String {
+        self.pending.extend_from_slice(data);
+        let complete = self.pending.len() - incomplete_tail(&self.pending);
+        let text = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+        text
+    }
+
+    fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        text
+    }
+}
+
+/// Length of a multi-byte character cut off at the end of `bytes`.
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 == 0x80 {
+            continue;
+        }
+        let width = match byte {
+            0xF0..=0xFF => 4,
+            0xE0..=0xEF => 3,
+            0xC0..=0xDF => 2,
+            _ => 1,
+        };
+        return if width > back { back } else { 0 };
+    }
+    0
+}
+
+/// How a command ended; merged into its [`ExecInfo`].
+struct Finished {
+    status: ExecStatus,
+    exit_code: Option<u32>,
+    signal: Option<String>,
+    core_dumped: bool,
+    error: Option<String>,
+}
+
+impl Finished {
+    fn failed(error: impl Into<String>) -> Self {
+        Self {
+            status: ExecStatus::Failed,
+            exit_code: None,
+            signal: None,
+            core_dumped: false,
+            error: Some(error.into()),
+        }
+    }
+
+    fn apply(self, info: &mut ExecInfo) {
+        info.status = self.status;
+        info.exit_code = self.exit_code;
+        info.signal = self.signal;
+        info.core_dumped = self.core_dumped;
+        info.error = self.error;
+        info.finished_at = Some(now_ms());
+    }
+}
+
+/// Send `TERM` to the process group of the wrapper with PID `pid`, over a second exec channel.
+/// sshd starts each non-PTY session in a session of its own, so the group holds the command and
+/// everything it started, and nothing else.
+pub(crate) async fn kill_process_group(app: &AppHandle, connection_id: &str, pid: u32) {
+    let script = format!(
+        "pgid=$(ps -o pgid= -p {0} 2>/dev/null | tr -d ' '); kill -s TERM -- -\"${{pgid:-{0}}}\" 2>/dev/null || kill -s TERM {0}",
+        pid
+    );
+    let command = format!("sh -c {}", shell_escape(&script));
+    let opened = actor_request(app, connection_id, |respond_to| ConnectionRequest::OpenExecChannel {
+        command,
+        respond_to,
+    })
+    .await;
+    let Ok(mut channel) = opened else {
+        return;
+    };
+    let _ = channel.eof().await;
+    let _ = tokio::time::timeout(KILL_TIMEOUT, async {
+        while !matches!(channel.wait().await, None | Some(ChannelMsg::Close)) {}
+    })
+    .await;
+}
+
+fn signal_name(signal: &Sig) -> String {
+    match signal {
+        Sig::Custom(name) => name.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Run a command wrapped by [`build_tracked_command`] to the end, handing decoded output to
+/// `on_output`.
+async fn execute(
+    app: &AppHandle,
+    connection_id: &str,
+    command: TrackedCommand,
+    stdin: Option<String>,
+    timeout: Option<Duration>,
+    cancel: &mut watch::Receiver<bool>,
+    mut on_output: impl FnMut(ExecStream, String),
+) -> Finished {
+    let TrackedCommand { command, marker } = command;
+    let opened = actor_request(app, connection_id, |respond_to| ConnectionRequest::OpenExecChannel {
+        command,
+        respond_to,
+    })
+    .await;
+    let mut channel = match opened {
+        Ok(channel) => channel,
+        Err(e) => return Finished::failed(e.to_string()),
+    };
+
+    // A separate writer, so a command that does not read its input cannot stall the output.
+    let writer = stdin.map(|stdin| {
+        let mut tx = channel.make_writer();
+        tauri::async_runtime::spawn(async move {
+            let _ = tx.write_all(stdin.as_bytes()).await;
+            let _ = tx.shutdown().await;
+        })
+    });
+    if writer.is_none() {
+        let _ = channel.eof().await;
+    }
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let expired = async {
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(expired);
+
+    let mut stdout = Utf8Chunks::default();
+    let mut stderr = Utf8Chunks::default();
+    let mut pid = PidScanner::new(marker);
+    let mut finished = Finished {
+        status: ExecStatus::Completed,
+        exit_code: None,
+        signal: None,
+        core_dumped: false,
+        error: None,
+    };
+
+    loop {
+        let stop_with = tokio::select! {
+            _ = cancel_requested(cancel) => ExecStatus::Cancelled,
+            _ = &mut expired => ExecStatus::TimedOut,
+            msg = channel.wait() => {
+                match msg {
+                    Some(ChannelMsg::Data { data }) => {
+                        let text = stdout.decode(&pid.scan(&data));
+                        if !text.is_empty() {
+                            on_output(ExecStream::Stdout, text);
+                        }
+                    }
+                    Some(ChannelMsg::ExtendedData { data, ext: 1 }) => {
+                        let text = stderr.decode(&data);
+                        if !text.is_empty() {
+                            on_output(ExecStream::Stderr, text);
+                        }
+                    }
+                    Some(ChannelMsg::ExitStatus { exit_status }) => finished.exit_code = Some(exit_status),
+                    Some(ChannelMsg::ExitSignal {
+                        signal_name: signal,
+                        core_dumped,
+                        error_message,
+                        ..
+                    }) => {
+                        finished.signal = Some(signal_name(&signal));
+                        finished.core_dumped = core_dumped;
+                        if !error_message.is_empty() {
+                            finished.error = Some(error_message);
+                        }
+                    }
+                    Some(ChannelMsg::Failure) => {
+                        finished = Finished::failed("The server refused to run the command");
+                        break;
+                    }
+                    Some(ChannelMsg::Close) => break,
+                    None => {
+                        if finished.exit_code.is_none() && finished.signal.is_none() {
+                            finished = Finished::failed("Connection closed before the command finished");
+                        }
+                        break;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+        };
+        // Closing the channel does not stop a command without a PTY, so signal its process group.
+        // The `signal` request covers a wrapper whose PID line has not arrived yet, on servers
+        // that honour it (OpenSSH 7.9 and later).
+        if let Some(pid) = pid.pid() {
+            kill_process_group(app, connection_id, pid).await;
+        }
+        let _ = channel.signal(Sig::TERM).await;
+        let _ = channel.close().await;
+        finished.status = stop_with;
+        break;
+    }
+
+    if let Some(writer) = writer {
+        writer.abort();
+    }
+    let held = stdout.decode(&pid.finish());
+    if !held.is_empty() {
+        on_output(ExecStream::Stdout, held);
+    }
+    for (stream, chunks) in [(ExecStream::Stdout, &mut stdout), (ExecStream::Stderr, &mut stderr)] {
+        let text = chunks.finish();
+        if !text.is_empty() {
+            on_output(stream, text);
+        }
+    }
+    finished
+}
+
+fn new_info(connection_id: &str, command: &str, options: &ExecOptions) -> ExecInfo {
+    ExecInfo {
+        id: options.id.clone().unwrap_or_else(|| Uuid::new_v4().to_string()),
+        connection_id: connection_id.to_string(),
+        command: command.to_string(),
+        cwd: options.cwd.clone(),
+        status: ExecStatus::Running,
+        exit_code: None,
+        signal: None,
+        core_dumped: false,
+        error: None,
+        created_at: now_ms(),
+        finished_at: None,
+    }
+}
+
+/// Track a command so [`cancel`] and [`list`] find it. Returns `false` if its ID is taken.
+fn register(info: &ExecInfo, cancel: watch::Sender<bool>) -> bool {
+    let mut execs = registry().lock().unwrap_or_else(|e| e.into_inner());
+    if execs.contains_key(&info.id) {
+        return false;
+    }
+    execs.insert(info.id.clone(), ExecEntry { info: info.clone(), cancel });
+    true
+}
+
+async fn ensure_connection(app: &AppHandle, connection_id: &str) -> Result<(), ExecError> {
+    let state = app.state::<Arc<Mutex<AppState>>>();
+    let app_state = state.lock().await;
+    app_state
+        .get_connection_sender(connection_id)
+        .map(|_| ())
+        .ok_or(ExecError::ConnectionNotFound)
+}
+
+/// The absolute directory a command with `cwd` runs in, for resolving paths in its diagnostics.
+/// `~`, relative paths and no `cwd` at all start from the login directory, read from the server;
+/// if that fails (or for `~user`) the path is used as given.
+async fn absolute_cwd(app: &AppHandle, connection_id: &str, cwd: Option<&str>) -> String {
+    let cwd = cwd.unwrap_or_default();
+    if cwd.starts_with('/') {
+        return cwd.to_string();
+    }
+    let rest = match cwd.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest.trim_start_matches('/'),
+        Some(_) => return cwd.to_string(),
+        None => cwd,
+    };
+    match actor_request(app, connection_id, |respond_to| ConnectionRequest::GetHomeDir { respond_to }).await {
+        Ok(home) if rest.is_empty() => home,
+        Ok(home) => format!("{}/{}", home.trim_end_matches('/'), rest),
+        Err(_) => cwd.to_string(),
+    }
+}
+
+/// Start `command` in the background. Output arrives as `exec_output` events and the final
+/// [`ExecInfo`] as `exec_done`. With `problem_matchers` set, diagnostics found in the output
+/// arrive as `exec_problems` events before `exec_done`.
+pub async fn start(
+    app: AppHandle,
+    connection_id: String,
+    command: String,
+    options: ExecOptions,
+) -> Result<ExecInfo, ExecError> {
+    start_with_done(app, connection_id, command, options, |_| {}).await
+}
+
+/// As [`start`], calling `on_done` with the final [`ExecInfo`] just before `exec_done` is sent.
+pub async fn start_with_done(
+    app: AppHandle,
+    connection_id: String,
+    command: String,
+    options: ExecOptions,
+    on_done: impl FnOnce(&ExecInfo) + Send + 'static,
+) -> Result<ExecInfo, ExecError> {
+    let wrapped = build_tracked_command(&command, &options).map_err(ExecError::InvalidEnv)?;
+    ensure_connection(&app, &connection_id).await?;
+
+    let info = new_info(&connection_id, &command, &options);
+    let (cancel, mut cancelled) = watch::channel(false);
+    if !register(&info, cancel) {
+        return Err(ExecError::DuplicateId(info.id));
+    }
+
+    emit_trace(
+        &app,
+        TraceEvent::new("exec", "start", "Running command")
+            .with_correlation_id(&info.id)
+            .with_detail(format!("{} via {}", command, connection_id)),
+    );
+
+    let id = info.id.clone();
+    let timeout = options.timeout_ms.map(Duration::from_millis);
+    let root = match options.problem_matchers {
+        Some(_) => absolute_cwd(&app, &connection_id, options.cwd.as_deref()).await,
+        None => String::new(),
+    };
+    let mut matcher = options
+        .problem_matchers
+        .as_deref()
+        .map(|kinds| ProblemMatcher::new(&root, kinds));
+    tauri::async_runtime::spawn(async move {
+        let output_app = app.clone();
+        let output_id = id.clone();
+        let emit_problems = |diagnostics: Vec<Diagnostic>| {
+            if diagnostics.is_empty() {
+                return;
+            }
+            let event = ExecProblemsEvent {
+                exec_id: id.clone(),
+                root: root.clone(),
+                diagnostics,
+            };
+            if let Err(e) = app.emit("exec_problems", event) {
+                log::error!("Failed to emit exec problems: {}", e);
+            }
+        };
+        let on_output = |stream, data: String| {
+            if let Some(matcher) = matcher.as_mut() {
+                emit_problems(matcher.push(stream == ExecStream::Stderr, &data));
+            }
+            let event = ExecOutputEvent {
+                exec_id: output_id.clone(),
+                stream,
+                data,
+            };
+            if let Err(e) = output_app.emit("exec_output", event) {
+                log::error!("Failed to emit exec output: {}", e);
+            }
+        };
+        let finished = execute(&app, &connection_id, wrapped, options.stdin, timeout, &mut cancelled, on_output).await;
+        if let Some(matcher) = matcher.as_mut() {
+            emit_problems(matcher.finish());
+        }
+
+        let Some(mut entry) = registry().lock().unwrap_or_else(|e| e.into_inner()).remove(&id) else {
+            return;
+        };
+        finished.apply(&mut entry.info);
+        on_done(&entry.info);
+        emit_trace(
+            &app,
+            TraceEvent::new("exec", "done", &format!("Command {:?}", entry.info.status))
+                .with_correlation_id(&id)
+                .with_detail(format!("exit {:?}, signal {:?}", entry.info.exit_code, entry.info.signal)),
+        );
+        if let Err(e) = app.emit("exec_done", entry.info) {
+            log::error!("Failed to emit exec done: {}", e);
+        }
+    });
+    Ok(info)
+}
+
+/// Run `command` to the end and return its output, without events. It is listed and can be
+/// cancelled like a started command while it runs, and times out after ten minutes unless the
+/// options say otherwise.
+pub async fn run(
+    app: &AppHandle,
+    connection_id: &str,
+    command: &str,
+    options: ExecOptions,
+) -> Result<ExecOutput, ExecError> {
+    let wrapped = build_tracked_command(command, &options).map_err(ExecError::InvalidEnv)?;
+    ensure_connection(app, connection_id).await?;
+
+    let mut info = new_info(connection_id, command, &options);
+    let (cancel, mut cancelled) = watch::channel(false);
+    if !register(&info, cancel) {
+        return Err(ExecError::DuplicateId(info.id));
+    }
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    let mut truncated = false;
+    let timeout = options.timeout_ms.map_or(RUN_TIMEOUT, Duration::from_millis);
+    let on_output = |stream, data: String| {
+        let buffer = match stream {
+            ExecStream::Stdout => &mut stdout,
+            ExecStream::Stderr => &mut stderr,
+        };
+        let room = OUTPUT_LIMIT.saturating_sub(buffer.len());
+        if data.len() <= room {
+            buffer.push_str(&data);
+        } else {
+            let mut cut = room;
+            while !data.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            buffer.push_str(&data[..cut]);
+            truncated = true;
+        }
+    };
+    let finished = execute(app, connection_id, wrapped, options.stdin, Some(timeout), &mut cancelled, on_output).await;
+    registry().lock().unwrap_or_else(|e| e.into_inner()).remove(&info.id);
+    finished.apply(&mut info);
+
+    Ok(ExecOutput {
+        info,
+        stdout,
+        stderr,
+        truncated,
+    })
+}
+
+/// Ask a running command to stop; its `exec_done` event (or [`run`]'s result) reports
+/// `cancelled`. Returns `false` if it does not exist (or already finished).
+pub fn cancel(id: &str) -> bool {
+    let execs = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let Some(entry) = execs.get(id) else {
+        return false;
+    };
+    entry.cancel.send_replace(true);
+    true
+}
+
+/// Running commands, optionally only those of one connection.
+pub fn list(connection_id: Option<&str>) -> Vec<ExecInfo> {
+    let execs = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let mut infos: Vec<ExecInfo> = execs
+        .values()
+        .filter(|entry| connection_id.map_or(true, |id| entry.info.connection_id == id))
+        .map(|entry| entry.info.clone())
+        .collect();
+    infos.sort_by_key(|info| info.created_at);
+    infos
+}
+
+/// Cancel every command of a connection (on explicit disconnect).
+pub fn cancel_for_connection(connection_id: &str) {
+    let execs = registry().lock().unwrap_or_else(|e| e.into_inner());
+    for entry in execs.values().filter(|entry| entry.info.connection_id == connection_id) {
+        entry.cancel.send_replace(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_all(marker: &str, chunks: &[&str]) -> (String, Option<u32>) {
+        let mut scanner = PidScanner::new(marker.to_string());
+        let mut output = Vec::new();
+        for chunk in chunks {
+            output.extend(scanner.scan(chunk.as_bytes()));
+        }
+        output.extend(scanner.finish());
+        (String::from_utf8(output).unwrap(), scanner.pid())
+    }
+
+    #[test]
+    fn pid_line_comes_first() {
+        assert_eq!(scan_all("_m_", &["_m_4242\nhello\n"]), ("hello\n".to_string(), Some(4242)));
+    }
+
+    #[test]
+    fn profile_output_before_pid_line_passes_through() {
+        let (output, pid) = scan_all("_m_", &["Welcome!\n12\n_m_77\nout"]);
+        assert_eq!(output, "Welcome!\n12\nout");
+        assert_eq!(pid, Some(77));
+    }
+
+    #[test]
+    fn pid_line_split_across_chunks() {
+        assert_eq!(scan_all("_m_", &["_", "m_1", "23", "\nrest"]), ("rest".to_string(), Some(123)));
+    }
+
+    #[test]
+    fn output_split_across_chunks_is_not_held() {
+        let mut scanner = PidScanner::new("_m_".to_string());
+        assert_eq!(scanner.scan(b"no newline yet"), b"no newline yet");
+        assert_eq!(scanner.scan(b" _m_1\n"), b" _m_1\n");
+        assert_eq!(scanner.pid(), None);
+    }
+
+    #[test]
+    fn missing_pid_line_keeps_all_output() {
+        assert_eq!(scan_all("_m_", &["a\n", "_m"]), ("a\n_m".to_string(), None));
+    }
+
+    #[test]
+    fn only_the_first_marker_line_is_taken() {
+        assert_eq!(scan_all("_m_", &["_m_1\n_m_2\n"]), ("_m_2\n".to_string(), Some(1)));
+    }
+}
@@ -0,0 +1,294 @@
+//! Follow mode (`tail -f`) for remote files.
+//!
+//! A follower polls the file's size through the connection's actor and pushes appended bytes as
+//! `file_follow_data` events. It asks `AppState` for the current actor on every poll, so it keeps
+//! going across `ssh_reconnect`; polls that find no connection are skipped.
+
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::SshError;
+use crate::ssh::sftp::{RangeRead, SftpStat, MAX_RANGE_LEN};
+use crate::state::AppState;
+use crate::trace::{emit_trace, TraceEvent};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tokio::sync::{oneshot, watch, Mutex};
+use uuid::Uuid;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
+pub enum FollowError {
+    #[error("Connection not found")]
+    ConnectionNotFound,
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error(transparent)]
+    Ssh(#[from] SshError),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FollowInfo {
+    pub id: String,
+    pub connection_id: String,
+    pub path: String,
+    /// Next byte to be pushed
+    pub offset: u64,
+    pub created_at: u64,
+}
+
+/// Payload of `file_follow_data`. Either carries appended bytes, or reports a poll error (the
+/// follower keeps polling, e.g. while a rotated log is recreated).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FollowEvent {
+    pub follow_id: String,
+    pub connection_id: String,
+    pub path: String,
+    /// Offset of the first byte of `content_base64`
+    pub offset: u64,
+    pub content_base64: String,
+    /// File size as of this poll
+    pub size: u64,
+    /// The file shrank (truncated or replaced); following restarted from offset 0.
+    pub truncated: bool,
+    pub error: Option<String>,
+}
+
+struct FollowEntry {
+    info: FollowInfo,
+    stop: watch::Sender<bool>,
+}
+
+static FOLLOWS: OnceLock<StdMutex<HashMap<String, FollowEntry>>> = OnceLock::new();
+
+fn registry() -> &'static StdMutex<HashMap<String, FollowEntry>> {
+    FOLLOWS.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Send a request to the connection's current actor and wait for its reply.
+async fn actor_request<T>(
+    app: &AppHandle,
+    connection_id: &str,
+    request: impl FnOnce(oneshot::Sender<Result<T, SshError>>) -> ConnectionRequest,
+) -> Result<T, FollowError> {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    }
+    .ok_or(FollowError::ConnectionNotFound)?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| FollowError::ConnectionClosed)?;
+    Ok(rx.await.map_err(|_| FollowError::ConnectionClosed)??)
+}
+
+async fn stat(app: &AppHandle, connection_id: &str, path: &str) -> Result<SftpStat, FollowError> {
+    actor_request(app, connection_id, |respond_to| ConnectionRequest::Stat {
+        path: path.to_string(),
+        respond_to,
+    })
+    .await
+}
+
+async fn read_range(
+    app: &AppHandle,
+    connection_id: &str,
+    path: &str,
+    offset: u64,
+    len: u64,
+) -> Result<RangeRead, FollowError> {
+    actor_request(app, connection_id, |respond_to| ConnectionRequest::ReadRange {
+        path: path.to_string(),
+        offset,
+        len,
+        respond_to,
+    })
+    .await
+}
+
+/// Start pushing bytes appended to `path` from `offset` on; `None` means from the current end,
+/// and passing the `size` of a preceding tail read continues it without a gap.
+pub async fn start(
+    app: AppHandle,
+    connection_id: String,
+    path: String,
+    offset: Option<u64>,
+) -> Result<FollowInfo, FollowError> {
+    let size = stat(&app, &connection_id, &path).await?.size;
+    let info = FollowInfo {
+        id: Uuid::new_v4().to_string(),
+        connection_id,
+        path,
+        offset: offset.unwrap_or(size),
+        created_at: now_ms(),
+    };
+
+    let (stop, stopped) = watch::channel(false);
+    registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(info.id.clone(), FollowEntry { info: info.clone(), stop });
+
+    emit_trace(
+        &app,
+        TraceEvent::new("follow", "start", "Following file")
+            .with_correlation_id(&info.id)
+            .with_detail(format!("{} from offset {} via {}", info.path, info.offset, info.connection_id)),
+    );
+
+    let task_info = info.clone();
+    tauri::async_runtime::spawn(follow_loop(app, task_info, stopped));
+    Ok(info)
+}
+
+async fn follow_loop(app: AppHandle, info: FollowInfo, mut stopped: watch::Receiver<bool>) {
+    let FollowInfo {
+        id,
+        connection_id,
+        path,
+        mut offset,
+        ..
+    } = info;
+    let mut last_error: Option<String> = None;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = stopped.wait_for(|s| *s) => break,
+        }
+
+        let polled = poll_once(&app, &id, &connection_id, &path, &mut offset, &stopped).await;
+        match polled {
+            Ok(()) => last_error = None,
+            // Between disconnect and reconnect; try again next tick.
+            Err(FollowError::ConnectionNotFound | FollowError::ConnectionClosed) => {}
+            Err(e) => {
+                let message = e.to_string();
+                // Report each distinct error once instead of every second.
+                if last_error.as_deref() != Some(message.as_str()) {
+                    emit_data(&app, FollowEvent {
+                        follow_id: id.clone(),
+                        connection_id: connection_id.clone(),
+                        path: path.clone(),
+                        offset,
+                        content_base64: String::new(),
+                        size: 0,
+                        truncated: false,
+                        error: Some(message.clone()),
+                    });
+                    last_error = Some(message);
+                }
+            }
+        }
+    }
+}
+
+/// Push everything appended since `offset`, in chunks of at most [`MAX_RANGE_LEN`].
+async fn poll_once(
+    app: &AppHandle,
+    id: &str,
+    connection_id: &str,
+    path: &str,
+    offset: &mut u64,
+    stopped: &watch::Receiver<bool>,
+) -> Result<(), FollowError> {
+    let size = stat(app, connection_id, path).await?.size;
+    let mut truncated = false;
+    if size < *offset {
+        truncated = true;
+        *offset = 0;
+    }
+
+    while *offset < size && !*stopped.borrow() {
+        let read = read_range(app, connection_id, path, *offset, (size - *offset).min(MAX_RANGE_LEN)).await?;
+        if read.data.is_empty() {
+            break;
+        }
+        let len = read.data.len() as u64;
+        emit_data(app, FollowEvent {
+            follow_id: id.to_string(),
+            connection_id: connection_id.to_string(),
+            path: path.to_string(),
+            offset: read.offset,
+            content_base64: BASE64.encode(&read.data),
+            size: read.size,
+            truncated,
+            error: None,
+        });
+        truncated = false;
+        *offset += len;
+        if let Some(entry) = registry().lock().unwrap_or_else(|e| e.into_inner()).get_mut(id) {
+            entry.info.offset = *offset;
+        }
+    }
+
+    // Truncated with nothing new to send (e.g. emptied by logrotate): still tell the UI.
+    if truncated {
+        emit_data(app, FollowEvent {
+            follow_id: id.to_string(),
+            connection_id: connection_id.to_string(),
+            path: path.to_string(),
+            offset: 0,
+            content_base64: String::new(),
+            size,
+            truncated,
+            error: None,
+        });
+    }
+    Ok(())
+}
+
+fn emit_data(app: &AppHandle, event: FollowEvent) {
+    if let Err(e) = app.emit("file_follow_data", event) {
+        log::error!("Failed to emit file follow data: {}", e);
+    }
+}
+
+/// Followers, optionally only those of one connection.
+pub fn list(connection_id: Option<&str>) -> Vec<FollowInfo> {
+    let follows = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let mut infos: Vec<FollowInfo> = follows
+        .values()
+        .filter(|entry| connection_id.map_or(true, |id| entry.info.connection_id == id))
+        .map(|entry| entry.info.clone())
+        .collect();
+    infos.sort_by_key(|info| info.created_at);
+    infos
+}
+
+/// Stop a follower; returns its last state, or `None` if it does not exist.
+pub fn stop(id: &str) -> Option<FollowInfo> {
+    let entry = registry().lock().unwrap_or_else(|e| e.into_inner()).remove(id)?;
+    entry.stop.send_replace(true);
+    Some(entry.info)
+}
+
+/// Stop every follower of a connection (on explicit disconnect).
+pub fn stop_for_connection(connection_id: &str) {
+    let mut follows = registry().lock().unwrap_or_else(|e| e.into_inner());
+    follows.retain(|_, entry| {
+        if entry.info.connection_id == connection_id {
+            entry.stop.send_replace(true);
+            false
+        } else {
+            true
+        }
+    });
+}
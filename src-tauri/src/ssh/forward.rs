@@ -0,0 +1,755 @@
+//! Port forwards bound to a connection ID: local (`ssh -L`), remote (`ssh -R`) and dynamic SOCKS5
+//! (`ssh -D`).
+//!
+//! Forwards live in a global registry rather than inside the connection actor so they survive
+//! `ssh_reconnect`: local and dynamic listeners ask `AppState` for the connection's *current* actor
+//! on every accepted socket, and remote forwards are re-requested on the new session by
+//! [`reattach`].
+
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::SshError;
+use crate::state::AppState;
+use crate::trace::{emit_trace, TraceEvent};
+use russh::client::Msg;
+use russh::Channel;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{oneshot, watch, Mutex};
+use uuid::Uuid;
+
+const COPY_BUFFER_SIZE: usize = 32 * 1024;
+const SOCKS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCAL_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum ForwardError {
+    #[error("Connection not found")]
+    ConnectionNotFound,
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error("Could not listen: {0}")]
+    Bind(#[from] std::io::Error),
+    #[error(transparent)]
+    Ssh(#[from] SshError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ForwardKind {
+    /// `ssh -L`: local listener, connections tunnelled to the target via `direct-tcpip`.
+    Local,
+    /// `ssh -R`: the server listens (`tcpip-forward`) and hands connections back as
+    /// `forwarded-tcpip` channels, which we connect to a local target.
+    Remote,
+    /// `ssh -D`: local SOCKS5 listener, each CONNECT tunnelled via `direct-tcpip`.
+    Dynamic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ForwardStatus {
+    Active,
+    /// The forward is registered but not working (e.g. the server refused it after a reconnect).
+    Failed,
+}
+
+#[derive(Default)]
+pub struct ForwardStats {
+    /// Bytes from the accepting side into the tunnel
+    bytes_sent: AtomicU64,
+    /// Bytes from the tunnel back to the accepting side
+    bytes_received: AtomicU64,
+    active_connections: AtomicU64,
+    total_connections: AtomicU64,
+    failed: AtomicBool,
+    last_error: StdMutex<Option<String>>,
+}
+
+impl ForwardStats {
+    fn set_error(&self, error: impl Into<String>) {
+        *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(error.into());
+    }
+}
+
+/// Snapshot of a forward for the UI and diagnostics export.
+///
+/// `bind_*` is where connections are accepted (locally for `local`/`dynamic`, on the server for
+/// `remote`); `target_*` is where they are sent (absent for `dynamic`, chosen per SOCKS request).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardInfo {
+    pub id: String,
+    pub connection_id: String,
+    pub kind: ForwardKind,
+    pub status: ForwardStatus,
+    pub bind_host: String,
+    pub bind_port: u16,
+    pub target_host: Option<String>,
+    pub target_port: Option<u16>,
+    pub created_at: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub active_connections: u64,
+    pub total_connections: u64,
+    pub last_error: Option<String>,
+}
+
+/// Where a remote forward's incoming channels go; held by the session's `ClientHandler`.
+#[derive(Clone)]
+pub struct RemoteRoute {
+    forward_id: String,
+    target_host: String,
+    target_port: u16,
+    stats: Arc<ForwardStats>,
+    shutdown: watch::Receiver<bool>,
+}
+
+/// Remote forwards active on one SSH session, keyed by the port bound on the server.
+pub type RemoteRoutes = Arc<StdMutex<HashMap<u32, RemoteRoute>>>;
+
+struct ForwardEntry {
+    id: String,
+    connection_id: String,
+    kind: ForwardKind,
+    bind_host: String,
+    bind_port: u16,
+    target_host: Option<String>,
+    target_port: Option<u16>,
+    created_at: u64,
+    stats: Arc<ForwardStats>,
+    shutdown: watch::Sender<bool>,
+}
+
+impl ForwardEntry {
+    fn new(
+        connection_id: &str,
+        kind: ForwardKind,
+        bind_host: String,
+        bind_port: u16,
+        target: Option<(String, u16)>,
+    ) -> Self {
+        let (target_host, target_port) = target.unzip();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            connection_id: connection_id.to_string(),
+            kind,
+            bind_host,
+            bind_port,
+            target_host,
+            target_port,
+            created_at: now_ms(),
+            stats: Arc::new(ForwardStats::default()),
+            shutdown: watch::channel(false).0,
+        }
+    }
+
+    fn snapshot(&self) -> ForwardInfo {
+        ForwardInfo {
+            id: self.id.clone(),
+            connection_id: self.connection_id.clone(),
+            kind: self.kind,
+            status: if self.stats.failed.load(Ordering::Relaxed) {
+                ForwardStatus::Failed
+            } else {
+                ForwardStatus::Active
+            },
+            bind_host: self.bind_host.clone(),
+            bind_port: self.bind_port,
+            target_host: self.target_host.clone(),
+            target_port: self.target_port,
+            created_at: self.created_at,
+            bytes_sent: self.stats.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.stats.bytes_received.load(Ordering::Relaxed),
+            active_connections: self.stats.active_connections.load(Ordering::Relaxed),
+            total_connections: self.stats.total_connections.load(Ordering::Relaxed),
+            last_error: self
+                .stats
+                .last_error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        }
+    }
+
+    fn remote_route(&self) -> Option<RemoteRoute> {
+        Some(RemoteRoute {
+            forward_id: self.id.clone(),
+            target_host: self.target_host.clone()?,
+            target_port: self.target_port?,
+            stats: self.stats.clone(),
+            shutdown: self.shutdown.subscribe(),
+        })
+    }
+}
+
+static FORWARDS: OnceLock<StdMutex<HashMap<String, ForwardEntry>>> = OnceLock::new();
+
+fn registry() -> &'static StdMutex<HashMap<String, ForwardEntry>> {
+    FORWARDS.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Send a request to the connection's current actor and wait for its reply.
+async fn actor_request<T>(
+    app: &AppHandle,
+    connection_id: &str,
+    request: impl FnOnce(oneshot::Sender<Result<T, SshError>>) -> ConnectionRequest,
+) -> Result<T, ForwardError> {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    }
+    .ok_or(ForwardError::ConnectionNotFound)?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| ForwardError::ConnectionClosed)?;
+    Ok(rx.await.map_err(|_| ForwardError::ConnectionClosed)??)
+}
+
+/// Ask the connection's current actor for a `direct-tcpip` channel.
+async fn open_direct_tcpip(
+    app: &AppHandle,
+    connection_id: &str,
+    host: &str,
+    port: u16,
+    originator: SocketAddr,
+) -> Result<Channel<Msg>, ForwardError> {
+    actor_request(app, connection_id, |respond_to| ConnectionRequest::OpenDirectTcpip {
+        host: host.to_string(),
+        port,
+        originator_address: originator.ip().to_string(),
+        originator_port: originator.port(),
+        respond_to,
+    })
+    .await
+}
+
+fn insert(entry: ForwardEntry) -> ForwardInfo {
+    let info = entry.snapshot();
+    registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(entry.id.clone(), entry);
+    info
+}
+
+/// Bind `bind_host:bind_port` (port 0 picks a free port) and tunnel each accepted socket to
+/// `target_host:target_port` as seen from the server.
+pub async fn open_local(
+    app: AppHandle,
+    connection_id: String,
+    bind_host: String,
+    bind_port: u16,
+    target_host: String,
+    target_port: u16,
+) -> Result<ForwardInfo, ForwardError> {
+    let listener = TcpListener::bind((bind_host.as_str(), bind_port)).await?;
+    let bound_port = listener.local_addr()?.port();
+
+    let entry = ForwardEntry::new(
+        &connection_id,
+        ForwardKind::Local,
+        bind_host,
+        bound_port,
+        Some((target_host.clone(), target_port)),
+    );
+    let target = Some((target_host, target_port));
+    spawn_accept_loop(&app, &entry, listener, target);
+    let info = insert(entry);
+
+    emit_trace(
+        &app,
+        TraceEvent::new("forward", "local_open", "Local forward listening")
+            .with_correlation_id(&info.id)
+            .with_detail(format!(
+                "{}:{} -> {}:{} via {}",
+                info.bind_host,
+                info.bind_port,
+                info.target_host.as_deref().unwrap_or_default(),
+                target_port,
+                connection_id
+            )),
+    );
+    Ok(info)
+}
+
+/// Run a local SOCKS5 proxy on `bind_host:bind_port`; every CONNECT goes out through the server.
+pub async fn open_dynamic(
+    app: AppHandle,
+    connection_id: String,
+    bind_host: String,
+    bind_port: u16,
+) -> Result<ForwardInfo, ForwardError> {
+    let listener = TcpListener::bind((bind_host.as_str(), bind_port)).await?;
+    let bound_port = listener.local_addr()?.port();
+
+    let entry = ForwardEntry::new(&connection_id, ForwardKind::Dynamic, bind_host, bound_port, None);
+    spawn_accept_loop(&app, &entry, listener, None);
+    let info = insert(entry);
+
+    emit_trace(
+        &app,
+        TraceEvent::new("forward", "dynamic_open", "SOCKS proxy listening")
+            .with_correlation_id(&info.id)
+            .with_detail(format!("{}:{} via {}", info.bind_host, info.bind_port, connection_id)),
+    );
+    Ok(info)
+}
+
+/// Ask the server to listen on `bind_host:bind_port` (port 0 lets the server pick) and connect
+/// incoming connections to `target_host:target_port` on this machine.
+pub async fn open_remote(
+    app: AppHandle,
+    connection_id: String,
+    bind_host: String,
+    bind_port: u16,
+    target_host: String,
+    target_port: u16,
+) -> Result<ForwardInfo, ForwardError> {
+    let mut entry = ForwardEntry::new(
+        &connection_id,
+        ForwardKind::Remote,
+        bind_host.clone(),
+        bind_port,
+        Some((target_host, target_port)),
+    );
+    let route = entry.remote_route().expect("remote forwards have a target");
+    entry.bind_port = actor_request(&app, &connection_id, |respond_to| {
+        ConnectionRequest::RequestRemoteForward {
+            bind_host,
+            bind_port,
+            route,
+            respond_to,
+        }
+    })
+    .await?;
+    let info = insert(entry);
+
+    emit_trace(
+        &app,
+        TraceEvent::new("forward", "remote_open", "Remote forward listening on server")
+            .with_correlation_id(&info.id)
+            .with_detail(format!(
+                "{}:{} -> {}:{} via {}",
+                info.bind_host,
+                info.bind_port,
+                info.target_host.as_deref().unwrap_or_default(),
+                target_port,
+                connection_id
+            )),
+    );
+    Ok(info)
+}
+
+fn spawn_accept_loop(
+    app: &AppHandle,
+    entry: &ForwardEntry,
+    listener: TcpListener,
+    target: Option<(String, u16)>,
+) {
+    let app = app.clone();
+    let forward_id = entry.id.clone();
+    let connection_id = entry.connection_id.clone();
+    let stats = entry.stats.clone();
+    let mut shutdown_rx = entry.shutdown.subscribe();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let accepted = tokio::select! {
+                _ = shutdown_rx.changed() => break,
+                accepted = listener.accept() => accepted,
+            };
+            match accepted {
+                Ok((socket, peer)) => {
+                    let _ = socket.set_nodelay(true);
+                    stats.total_connections.fetch_add(1, Ordering::Relaxed);
+                    let tunnel = LocalTunnel {
+                        app: app.clone(),
+                        forward_id: forward_id.clone(),
+                        connection_id: connection_id.clone(),
+                        stats: stats.clone(),
+                        shutdown: shutdown_rx.clone(),
+                    };
+                    let target = target.clone();
+                    tauri::async_runtime::spawn(async move {
+                        match target {
+                            Some((host, port)) => tunnel.forward(socket, peer, host, port).await,
+                            None => tunnel.socks(socket, peer).await,
+                        }
+                    });
+                }
+                Err(e) => {
+                    stats.set_error(format!("accept failed: {}", e));
+                    // Avoid spinning on persistent errors such as fd exhaustion.
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                }
+            }
+        }
+        emit_trace(
+            &app,
+            TraceEvent::new("forward", "listener_closed", "Forward listener closed").with_correlation_id(&forward_id),
+        );
+    });
+}
+
+/// One accepted local socket of a local or dynamic forward.
+struct LocalTunnel {
+    app: AppHandle,
+    forward_id: String,
+    connection_id: String,
+    stats: Arc<ForwardStats>,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl LocalTunnel {
+    async fn open(&self, peer: SocketAddr, host: &str, port: u16) -> Result<Channel<Msg>, ForwardError> {
+        open_direct_tcpip(&self.app, &self.connection_id, host, port, peer)
+            .await
+            .inspect_err(|e| {
+                emit_trace(
+                    &self.app,
+                    TraceEvent::new("forward", "channel_failed", "Could not open forwarded channel")
+                        .with_correlation_id(&self.forward_id)
+                        .with_detail(format!("{} -> {}:{}: {}", peer, host, port, e))
+                        .error(),
+                );
+                self.stats.set_error(e.to_string());
+            })
+    }
+
+    async fn forward(self, socket: TcpStream, peer: SocketAddr, host: String, port: u16) {
+        if let Ok(channel) = self.open(peer, &host, port).await {
+            self.pipe(socket, channel).await;
+        }
+    }
+
+    async fn socks(self, mut socket: TcpStream, peer: SocketAddr) {
+        let (host, port) = match tokio::time::timeout(SOCKS_HANDSHAKE_TIMEOUT, socks5_handshake(&mut socket)).await {
+            Ok(Ok(dest)) => dest,
+            Ok(Err(e)) => {
+                self.stats.set_error(format!("SOCKS handshake from {}: {}", peer, e));
+                return;
+            }
+            Err(_) => {
+                self.stats.set_error(format!("SOCKS handshake from {} timed out", peer));
+                return;
+            }
+        };
+
+        match self.open(peer, &host, port).await {
+            Ok(channel) => {
+                if socks5_reply(&mut socket, SOCKS5_SUCCEEDED).await.is_ok() {
+                    self.pipe(socket, channel).await;
+                }
+            }
+            Err(e) => {
+                let code = match e {
+                    ForwardError::Ssh(SshError::ChannelOpenFailed(_)) => SOCKS5_CONNECTION_REFUSED,
+                    _ => SOCKS5_GENERAL_FAILURE,
+                };
+                let _ = socks5_reply(&mut socket, code).await;
+            }
+        }
+    }
+
+    async fn pipe(self, socket: TcpStream, channel: Channel<Msg>) {
+        self.stats.active_connections.fetch_add(1, Ordering::Relaxed);
+        pipe(socket, channel.into_stream(), &self.stats, self.shutdown).await;
+        self.stats.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Handle a `forwarded-tcpip` channel opened by the server for one of our remote forwards.
+/// Called from the session's `ClientHandler`; unknown ports are refused by dropping the channel.
+pub(crate) fn serve_remote_channel(
+    app: &AppHandle,
+    routes: &RemoteRoutes,
+    channel: Channel<Msg>,
+    connected_port: u32,
+    originator: String,
+) {
+    let route = routes
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&connected_port)
+        .cloned();
+    let Some(route) = route.filter(|r| !*r.shutdown.borrow()) else {
+        emit_trace(
+            app,
+            TraceEvent::new("forward", "remote_unrouted", "Server opened a channel for an unknown forward")
+                .with_detail(format!("port {} from {}", connected_port, originator))
+                .error(),
+        );
+        return;
+    };
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        route.stats.total_connections.fetch_add(1, Ordering::Relaxed);
+        let target = (route.target_host.as_str(), route.target_port);
+        let socket = match tokio::time::timeout(LOCAL_CONNECT_TIMEOUT, TcpStream::connect(target)).await {
+            Ok(Ok(socket)) => socket,
+            Ok(Err(e)) => {
+                route.stats.set_error(format!("connect {}:{}: {}", target.0, target.1, e));
+                let _ = channel.close().await;
+                return;
+            }
+            Err(_) => {
+                route.stats.set_error(format!("connect {}:{} timed out", target.0, target.1));
+                let _ = channel.close().await;
+                return;
+            }
+        };
+        let _ = socket.set_nodelay(true);
+        emit_trace(
+            &app,
+            TraceEvent::new("forward", "remote_accept", "Remote forward connection")
+                .with_correlation_id(&route.forward_id)
+                .with_detail(format!("{} -> {}:{}", originator, target.0, target.1)),
+        );
+
+        route.stats.active_connections.fetch_add(1, Ordering::Relaxed);
+        pipe(socket, channel.into_stream(), &route.stats, route.shutdown.clone()).await;
+        route.stats.active_connections.fetch_sub(1, Ordering::Relaxed);
+    });
+}
+
+/// Copy both directions until the tunnel side closes (or the forward is shut down).
+/// A local EOF is propagated to the tunnel while the response keeps flowing back.
+async fn pipe<L, R>(local: L, remote: R, stats: &ForwardStats, mut shutdown: watch::Receiver<bool>)
+where
+    L: AsyncRead + AsyncWrite + Unpin,
+    R: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut local_read, mut local_write) = tokio::io::split(local);
+    let (mut remote_read, mut remote_write) = tokio::io::split(remote);
+    let up = copy_counted(&mut local_read, &mut remote_write, &stats.bytes_sent);
+    let down = copy_counted(&mut remote_read, &mut local_write, &stats.bytes_received);
+    tokio::pin!(up, down);
+
+    let mut up_done = false;
+    loop {
+        tokio::select! {
+            result = &mut up, if !up_done => {
+                if let Err(e) = result {
+                    stats.set_error(e.to_string());
+                    break;
+                }
+                up_done = true;
+            }
+            result = &mut down => {
+                if let Err(e) = result {
+                    stats.set_error(e.to_string());
+                }
+                break;
+            }
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+async fn copy_counted<R, W>(reader: &mut R, writer: &mut W, counter: &AtomicU64) -> std::io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            let _ = writer.shutdown().await;
+            return Ok(());
+        }
+        writer.write_all(&buf[..n]).await?;
+        counter.fetch_add(n as u64, Ordering::Relaxed);
+    }
+}
+
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_NO_AUTH: u8 = 0x00;
+const SOCKS5_NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const SOCKS5_CMD_CONNECT: u8 = 0x01;
+const SOCKS5_SUCCEEDED: u8 = 0x00;
+const SOCKS5_GENERAL_FAILURE: u8 = 0x01;
+const SOCKS5_CONNECTION_REFUSED: u8 = 0x05;
+const SOCKS5_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const SOCKS5_ADDRESS_NOT_SUPPORTED: u8 = 0x08;
+
+fn socks_error(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Read a SOCKS5 greeting and CONNECT request (RFC 1928, no authentication) and return the
+/// requested destination. Replies with an error code itself when the request is unsupported.
+async fn socks5_handshake(socket: &mut TcpStream) -> std::io::Result<(String, u16)> {
+    let mut greeting = [0u8; 2];
+    socket.read_exact(&mut greeting).await?;
+    if greeting[0] != SOCKS5_VERSION {
+        return Err(socks_error("not a SOCKS5 client"));
+    }
+    let mut methods = vec![0u8; greeting[1] as usize];
+    socket.read_exact(&mut methods).await?;
+    if !methods.contains(&SOCKS5_NO_AUTH) {
+        socket.write_all(&[SOCKS5_VERSION, SOCKS5_NO_ACCEPTABLE_METHOD]).await?;
+        return Err(socks_error("client requires authentication"));
+    }
+    socket.write_all(&[SOCKS5_VERSION, SOCKS5_NO_AUTH]).await?;
+
+    let mut request = [0u8; 4];
+    socket.read_exact(&mut request).await?;
+    if request[0] != SOCKS5_VERSION {
+        return Err(socks_error("bad request version"));
+    }
+    if request[1] != SOCKS5_CMD_CONNECT {
+        socks5_reply(socket, SOCKS5_COMMAND_NOT_SUPPORTED).await?;
+        return Err(socks_error("only CONNECT is supported"));
+    }
+
+    let host = match request[3] {
+        0x01 => {
+            let mut addr = [0u8; 4];
+            socket.read_exact(&mut addr).await?;
+            Ipv4Addr::from(addr).to_string()
+        }
+        0x03 => {
+            let len = socket.read_u8().await?;
+            let mut name = vec![0u8; len as usize];
+            socket.read_exact(&mut name).await?;
+            String::from_utf8(name).map_err(|_| socks_error("hostname is not UTF-8"))?
+        }
+        0x04 => {
+            let mut addr = [0u8; 16];
+            socket.read_exact(&mut addr).await?;
+            Ipv6Addr::from(addr).to_string()
+        }
+        _ => {
+            socks5_reply(socket, SOCKS5_ADDRESS_NOT_SUPPORTED).await?;
+            return Err(socks_error("unsupported address type"));
+        }
+    };
+    let port = socket.read_u16().await?;
+    Ok((host, port))
+}
+
+async fn socks5_reply(socket: &mut TcpStream, code: u8) -> std::io::Result<()> {
+    // The bound address is meaningless for a tunnelled connection; report 0.0.0.0:0.
+    socket
+        .write_all(&[SOCKS5_VERSION, code, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+        .await
+}
+
+/// Re-request this connection's remote forwards on its new session after `ssh_reconnect`.
+/// Local and dynamic listeners need nothing: they resolve the actor per accepted socket.
+pub async fn reattach(app: &AppHandle, connection_id: &str) -> (usize, usize) {
+    let remotes: Vec<(String, String, u16, RemoteRoute)> = registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .filter(|f| f.connection_id == connection_id && f.kind == ForwardKind::Remote)
+        .filter_map(|f| Some((f.id.clone(), f.bind_host.clone(), f.bind_port, f.remote_route()?)))
+        .collect();
+
+    let (mut ok, mut failed) = (0, 0);
+    for (id, bind_host, bind_port, route) in remotes {
+        let stats = route.stats.clone();
+        let result = actor_request(app, connection_id, |respond_to| {
+            ConnectionRequest::RequestRemoteForward {
+                bind_host,
+                bind_port,
+                route,
+                respond_to,
+            }
+        })
+        .await;
+        match result {
+            Ok(_) => {
+                stats.failed.store(false, Ordering::Relaxed);
+                ok += 1;
+            }
+            Err(e) => {
+                emit_trace(
+                    app,
+                    TraceEvent::new("forward", "remote_reattach_failed", "Server refused remote forward after reconnect")
+                        .with_correlation_id(&id)
+                        .with_detail(e.to_string())
+                        .error(),
+                );
+                stats.failed.store(true, Ordering::Relaxed);
+                stats.set_error(e.to_string());
+                failed += 1;
+            }
+        }
+    }
+    (ok, failed)
+}
+
+/// Forwards for one connection, or all of them.
+pub fn list(connection_id: Option<&str>) -> Vec<ForwardInfo> {
+    let guard = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let mut forwards: Vec<ForwardInfo> = guard
+        .values()
+        .filter(|f| connection_id.map_or(true, |id| f.connection_id == id))
+        .map(ForwardEntry::snapshot)
+        .collect();
+    forwards.sort_by_key(|f| f.created_at);
+    forwards
+}
+
+fn remove(forward_id: &str) -> Option<ForwardEntry> {
+    let entry = registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(forward_id)?;
+    entry.shutdown.send_replace(true);
+    Some(entry)
+}
+
+/// Stop a forward: closes its listener and any tunnelled sockets, and cancels remote forwards on
+/// the server. Returns the final snapshot.
+pub async fn close(app: &AppHandle, forward_id: &str) -> Option<ForwardInfo> {
+    let entry = remove(forward_id)?;
+    if entry.kind == ForwardKind::Remote {
+        let result = actor_request(app, &entry.connection_id, |respond_to| {
+            ConnectionRequest::CancelRemoteForward {
+                bind_host: entry.bind_host.clone(),
+                bind_port: entry.bind_port,
+                respond_to,
+            }
+        })
+        .await;
+        if let Err(e) = result {
+            // The session may already be gone, which drops the server-side listener anyway.
+            log::warn!("cancel remote forward {}: {}", forward_id, e);
+        }
+    }
+    Some(entry.snapshot())
+}
+
+/// Stop every forward of a connection (on explicit disconnect, which also ends remote forwards).
+pub fn close_for_connection(connection_id: &str) -> Vec<ForwardInfo> {
+    let ids: Vec<String> = registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .filter(|f| f.connection_id == connection_id)
+        .map(|f| f.id.clone())
+        .collect();
+    ids.iter().filter_map(|id| remove(id)).map(|e| e.snapshot()).collect()
+}
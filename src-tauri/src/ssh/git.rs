@@ -0,0 +1,842 @@
+//! Git in the remote project, run with [`exec::run`] and parsed into typed results.
+//!
+//! Status comes from `git status --porcelain=v2 -z`, whose paths are relative to the repository's
+//! top level; they are reported as absolute remote paths so the file tree can decorate entries
+//! directly. Every status refresh (including the one after `add`, `commit`, `checkout` and
+//! `stash`) is also emitted as a `git_status` event.
+
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::SshError;
+use crate::ssh::exec::{self, ExecError, ExecOptions, ExecStatus};
+use crate::ssh::pty::shell_escape;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tokio::sync::{oneshot, Mutex};
+
+const GIT_TIMEOUT_MS: u64 = 60_000;
+/// Field and record separators in `--format` output
+const FIELD: char = '\x1f';
+const RECORD: char = '\x1e';
+const LOG_FORMAT: &str = "--format=%H%x1f%h%x1f%P%x1f%an%x1f%ae%x1f%at%x1f%s%x1f%b%x1e";
+
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("Connection not found")]
+    ConnectionNotFound,
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error("Not a git repository: {0}")]
+    NotARepository(String),
+    #[error("git failed: {0}")]
+    Failed(String),
+    #[error(transparent)]
+    Exec(#[from] ExecError),
+    #[error(transparent)]
+    Ssh(#[from] SshError),
+}
+
+/// State of a file on one side (index or worktree) of a status entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileState {
+    Unmodified,
+    Modified,
+    TypeChanged,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    Unmerged,
+    Untracked,
+    Ignored,
+}
+
+impl FileState {
+    fn from_code(code: char) -> Self {
+        match code {
+            'M' => FileState::Modified,
+            'T' => FileState::TypeChanged,
+            'A' => FileState::Added,
+            'D' => FileState::Deleted,
+            'R' => FileState::Renamed,
+            'C' => FileState::Copied,
+            'U' => FileState::Unmerged,
+            '?' => FileState::Untracked,
+            '!' => FileState::Ignored,
+            _ => FileState::Unmodified,
+        }
+    }
+}
+
+/// One-word summary of a file's status for tree decorations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decoration {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Ignored,
+    Conflicted,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitFileStatus {
+    /// Absolute remote path
+    pub path: String,
+    /// Path relative to the repository top level, as git reports it
+    pub repo_path: String,
+    /// Source of a rename or copy, relative to the top level
+    pub orig_repo_path: Option<String>,
+    pub index: FileState,
+    pub worktree: FileState,
+    pub decoration: Decoration,
+    /// Has changes in the index
+    pub staged: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    /// Repository top level
+    pub toplevel: String,
+    /// Current branch; `None` when detached
+    pub branch: Option<String>,
+    /// `None` before the first commit
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub files: Vec<GitFileStatus>,
+}
+
+/// Payload of `git_status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatusEvent {
+    pub connection_id: String,
+    pub root: String,
+    pub status: GitStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub content: String,
+    pub old_line: Option<u64>,
+    pub new_line: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    pub old_start: u64,
+    pub old_lines: u64,
+    pub new_start: u64,
+    pub new_lines: u64,
+    /// Text after the closing `@@`, usually the enclosing function
+    pub section: String,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    /// `None` for an added file
+    pub old_path: Option<String>,
+    /// `None` for a deleted file
+    pub new_path: Option<String>,
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub parents: Vec<String>,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date, Unix seconds
+    pub timestamp: i64,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStash {
+    /// `stash@{n}`
+    pub name: String,
+    pub index: u32,
+    pub hash: String,
+    pub timestamp: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum StashAction {
+    List,
+    Push {
+        message: Option<String>,
+        #[serde(default)]
+        include_untracked: bool,
+    },
+    Pop {
+        #[serde(default)]
+        index: u32,
+    },
+    Apply {
+        #[serde(default)]
+        index: u32,
+    },
+    Drop {
+        #[serde(default)]
+        index: u32,
+    },
+}
+
+/// Send a request to the connection's current actor and wait for its reply.
+async fn actor_request<T>(
+    app: &AppHandle,
+    connection_id: &str,
+    request: impl FnOnce(oneshot::Sender<Result<T, SshError>>) -> ConnectionRequest,
+) -> Result<T, GitError> {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    }
+    .ok_or(GitError::ConnectionNotFound)?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| GitError::ConnectionClosed)?;
+    Ok(rx.await.map_err(|_| GitError::ConnectionClosed)??)
+}
+
+/// `git` with fixed config and the given arguments, shell-escaped.
+fn git_command(args: &[&str]) -> String {
+    let mut command = "git -c core.quotePath=false -c color.ui=never".to_string();
+    for arg in args {
+        command.push(' ');
+        command.push_str(&shell_escape(arg));
+    }
+    command
+}
+
+/// Run a shell `command` (built from [`git_command`]) in `root` and return its stdout.
+async fn run_git(
+    app: &AppHandle,
+    connection_id: &str,
+    root: &str,
+    command: &str,
+    stdin: Option<String>,
+) -> Result<String, GitError> {
+    let env = BTreeMap::from([
+        ("GIT_TERMINAL_PROMPT".to_string(), "0".to_string()),
+        ("GIT_OPTIONAL_LOCKS".to_string(), "0".to_string()),
+        ("LC_ALL".to_string(), "C".to_string()),
+    ]);
+    let options = ExecOptions {
+        cwd: Some(root.to_string()),
+        env,
+        stdin,
+        timeout_ms: Some(GIT_TIMEOUT_MS),
+        ..ExecOptions::default()
+    };
+    let output = exec::run(app, connection_id, command, options).await?;
+    match (output.info.status, output.info.exit_code) {
+        (ExecStatus::Completed, Some(0)) => Ok(output.stdout),
+        (ExecStatus::Completed, _) => {
+            let stderr = output.stderr.trim();
+            if stderr.contains("not a git repository") {
+                Err(GitError::NotARepository(root.to_string()))
+            } else if stderr.is_empty() {
+                Err(GitError::Failed(output.stdout.trim().to_string()))
+            } else {
+                Err(GitError::Failed(stderr.to_string()))
+            }
+        }
+        (ExecStatus::TimedOut, _) => Err(GitError::Failed("timed out".to_string())),
+        _ => Err(GitError::Failed(output.info.error.unwrap_or_else(|| "command did not finish".to_string()))),
+    }
+}
+
+fn decoration(kind: char, index: FileState, worktree: FileState) -> Decoration {
+    if kind == 'u' {
+        return Decoration::Conflicted;
+    }
+    let states = [index, worktree];
+    if states.contains(&FileState::Untracked) {
+        Decoration::Untracked
+    } else if states.contains(&FileState::Ignored) {
+        Decoration::Ignored
+    } else if states.contains(&FileState::Deleted) {
+        Decoration::Deleted
+    } else if states.contains(&FileState::Renamed) || states.contains(&FileState::Copied) {
+        Decoration::Renamed
+    } else if index == FileState::Added {
+        Decoration::Added
+    } else {
+        Decoration::Modified
+    }
+}
+
+/// Parse `git rev-parse --show-toplevel` followed by `git status --porcelain=v2 --branch -z`.
+/// Directories (only listed whole without `--untracked-files=all`) lose their trailing `/`.
+fn parse_status(output: &str) -> GitStatus {
+    let (toplevel, records) = output.split_once('\n').unwrap_or((output, ""));
+    let mut status = GitStatus {
+        toplevel: toplevel.trim().to_string(),
+        ..GitStatus::default()
+    };
+    let mut records = records.split('\0').filter(|record| !record.is_empty());
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => status.head = Some(value.to_string()),
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for count in value.split_whitespace() {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or(0);
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let kind = record.chars().next().unwrap_or(' ');
+        // Space-separated fields before the path: `1` has 8, `2` has 9 (plus the original path
+        // as the next record), `u` has 10, `?` and `!` have 1.
+        let fields = match kind {
+            '1' => 8,
+            '2' => 9,
+            'u' => 10,
+            '?' | '!' => 1,
+            _ => continue,
+        };
+        let mut parts = record.splitn(fields + 1, ' ');
+        let xy: Vec<char> = match kind {
+            '?' | '!' => vec![kind, kind],
+            _ => parts.nth(1).unwrap_or("..").chars().collect(),
+        };
+        let Some(repo_path) = record.splitn(fields + 1, ' ').nth(fields) else {
+            continue;
+        };
+        let repo_path = repo_path.trim_end_matches('/');
+        let orig_repo_path = if kind == '2' { records.next().map(str::to_string) } else { None };
+        let index = FileState::from_code(xy.first().copied().unwrap_or('.'));
+        let worktree = FileState::from_code(xy.get(1).copied().unwrap_or('.'));
+        status.files.push(GitFileStatus {
+            path: format!("{}/{}", status.toplevel.trim_end_matches('/'), repo_path),
+            repo_path: repo_path.to_string(),
+            orig_repo_path,
+            index,
+            worktree,
+            decoration: decoration(kind, index, worktree),
+            staged: !matches!(index, FileState::Unmodified | FileState::Untracked | FileState::Ignored),
+        });
+    }
+    status
+}
+
+/// Strip the `a/` or `b/` prefix from a `---`/`+++` path; `/dev/null` means no file.
+fn diff_path(path: &str) -> Option<String> {
+    let path = path.trim_end_matches('\t');
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path).to_string())
+}
+
+/// `-12,3` or `+4` from a hunk header: start and line count (1 when omitted).
+fn hunk_range(range: &str) -> (u64, u64) {
+    let range = range.trim_start_matches(['-', '+']);
+    match range.split_once(',') {
+        Some((start, count)) => (start.parse().unwrap_or(0), count.parse().unwrap_or(0)),
+        None => (range.parse().unwrap_or(0), 1),
+    }
+}
+
+fn parse_diff(output: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let (mut old_line, mut new_line) = (0u64, 0u64);
+
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            // Paths from this header are only a fallback for diffs without `---`/`+++` lines
+            // (binary or mode-only changes); they are ambiguous when a path contains " b/".
+            let (old, new) = header.split_once(" b/").unwrap_or((header, header));
+            let old = old.strip_prefix("a/").unwrap_or(old).to_string();
+            files.push(FileDiff {
+                old_path: Some(old),
+                new_path: Some(new.to_string()),
+                binary: false,
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else { continue };
+
+        if let Some(hunk) = line.strip_prefix("@@ ") {
+            let (ranges, section) = hunk.split_once(" @@").unwrap_or((hunk, ""));
+            let mut ranges = ranges.split_whitespace();
+            let (old_start, old_lines) = hunk_range(ranges.next().unwrap_or("-0"));
+            let (new_start, new_lines) = hunk_range(ranges.next().unwrap_or("+0"));
+            old_line = old_start;
+            new_line = new_start;
+            file.hunks.push(DiffHunk {
+                old_start,
+                old_lines,
+                new_start,
+                new_lines,
+                section: section.trim().to_string(),
+                lines: Vec::new(),
+            });
+            continue;
+        }
+        if let Some(hunk) = file.hunks.last_mut() {
+            let (kind, content) = match line.chars().next() {
+                Some(' ') => (DiffLineKind::Context, &line[1..]),
+                Some('+') => (DiffLineKind::Added, &line[1..]),
+                Some('-') => (DiffLineKind::Removed, &line[1..]),
+                Some('\\') => continue,
+                // A line of an empty context line can lose its leading space.
+                None => (DiffLineKind::Context, ""),
+                _ => continue,
+            };
+            let (old, new) = match kind {
+                DiffLineKind::Context => (Some(old_line), Some(new_line)),
+                DiffLineKind::Added => (None, Some(new_line)),
+                DiffLineKind::Removed => (Some(old_line), None),
+            };
+            if old.is_some() {
+                old_line += 1;
+            }
+            if new.is_some() {
+                new_line += 1;
+            }
+            hunk.lines.push(DiffLine {
+                kind,
+                content: content.to_string(),
+                old_line: old,
+                new_line: new,
+            });
+            continue;
+        }
+
+        if let Some(path) = line.strip_prefix("--- ") {
+            file.old_path = diff_path(path);
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            file.new_path = diff_path(path);
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            file.old_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            file.new_path = Some(path.to_string());
+        } else if line.starts_with("new file mode") {
+            file.old_path = None;
+        } else if line.starts_with("deleted file mode") {
+            file.new_path = None;
+        } else if line.starts_with("Binary files ") {
+            file.binary = true;
+        }
+    }
+    files
+}
+
+fn parse_log(output: &str) -> Vec<GitCommit> {
+    output
+        .split(RECORD)
+        .map(|record| record.trim_start_matches('\n'))
+        .filter(|record| !record.is_empty())
+        .filter_map(|record| {
+            let fields: Vec<&str> = record.splitn(8, FIELD).collect();
+            if fields.len() < 8 {
+                return None;
+            }
+            Some(GitCommit {
+                hash: fields[0].to_string(),
+                short_hash: fields[1].to_string(),
+                parents: fields[2].split_whitespace().map(str::to_string).collect(),
+                author_name: fields[3].to_string(),
+                author_email: fields[4].to_string(),
+                timestamp: fields[5].parse().unwrap_or(0),
+                subject: fields[6].to_string(),
+                body: fields[7].trim_end().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_stashes(output: &str) -> Vec<GitStash> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(4, FIELD).collect();
+            if fields.len() < 4 {
+                return None;
+            }
+            let index = fields[0]
+                .strip_prefix("stash@{")
+                .and_then(|rest| rest.strip_suffix('}'))
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(0);
+            Some(GitStash {
+                name: fields[0].to_string(),
+                index,
+                hash: fields[1].to_string(),
+                timestamp: fields[2].parse().unwrap_or(0),
+                message: fields[3].to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Working tree status of the repository containing `root`; also emitted as `git_status`.
+pub async fn status(app: &AppHandle, connection_id: &str, root: &str) -> Result<GitStatus, GitError> {
+    let command = format!(
+        "{} && {}",
+        git_command(&["rev-parse", "--show-toplevel"]),
+        git_command(&["status", "--porcelain=v2", "--branch", "--untracked-files=all", "-z"]),
+    );
+    let status = parse_status(&run_git(app, connection_id, root, &command, None).await?);
+    let event = GitStatusEvent {
+        connection_id: connection_id.to_string(),
+        root: root.to_string(),
+        status: status.clone(),
+    };
+    if let Err(e) = app.emit("git_status", event) {
+        log::error!("Failed to emit git status: {}", e);
+    }
+    Ok(status)
+}
+
+/// Unstaged changes, or staged ones with `staged`, optionally limited to `paths`.
+pub async fn diff(
+    app: &AppHandle,
+    connection_id: &str,
+    root: &str,
+    paths: &[String],
+    staged: bool,
+) -> Result<Vec<FileDiff>, GitError> {
+    let mut args = vec!["diff", "--no-ext-diff", "--no-color", "-M"];
+    if staged {
+        args.push("--cached");
+    }
+    args.push("--");
+    args.extend(paths.iter().map(String::as_str));
+    Ok(parse_diff(&run_git(app, connection_id, root, &git_command(&args), None).await?))
+}
+
+/// Commits reachable from HEAD, newest first, optionally only those touching `path`.
+pub async fn log(
+    app: &AppHandle,
+    connection_id: &str,
+    root: &str,
+    limit: u32,
+    skip: u32,
+    path: Option<&str>,
+) -> Result<Vec<GitCommit>, GitError> {
+    let limit = format!("--max-count={}", limit);
+    let skip = format!("--skip={}", skip);
+    let mut args = vec!["log", LOG_FORMAT, limit.as_str(), skip.as_str()];
+    if let Some(path) = path {
+        args.extend(["--", path]);
+    }
+    Ok(parse_log(&run_git(app, connection_id, root, &git_command(&args), None).await?))
+}
+
+/// Stage `paths`, or every change (including deletions and untracked files) with `all`.
+pub async fn add(
+    app: &AppHandle,
+    connection_id: &str,
+    root: &str,
+    paths: &[String],
+    all: bool,
+) -> Result<GitStatus, GitError> {
+    let mut args = vec!["add"];
+    if all {
+        args.push("--all");
+    }
+    args.push("--");
+    args.extend(paths.iter().map(String::as_str));
+    run_git(app, connection_id, root, &git_command(&args), None).await?;
+    status(app, connection_id, root).await
+}
+
+/// Commit the index with `message` (passed on stdin), staging tracked changes first with `all`.
+pub async fn commit(
+    app: &AppHandle,
+    connection_id: &str,
+    root: &str,
+    message: String,
+    amend: bool,
+    all: bool,
+) -> Result<GitCommit, GitError> {
+    let mut args = vec!["commit", "--quiet", "--file=-"];
+    if amend {
+        args.push("--amend");
+    }
+    if all {
+        args.push("--all");
+    }
+    let command = format!(
+        "{} && {}",
+        git_command(&args),
+        git_command(&["log", LOG_FORMAT, "--max-count=1"]),
+    );
+    let output = run_git(app, connection_id, root, &command, Some(message)).await?;
+    let committed = parse_log(&output)
+        .into_iter()
+        .next()
+        .ok_or_else(|| GitError::Failed("could not read the new commit".to_string()))?;
+    let _ = status(app, connection_id, root).await;
+    Ok(committed)
+}
+
+/// Switch to `target` (creating it as a new branch with `create`), or with `paths`, restore
+/// those paths from `target` (the index when `None`), discarding their changes.
+pub async fn checkout(
+    app: &AppHandle,
+    connection_id: &str,
+    root: &str,
+    target: Option<&str>,
+    create: bool,
+    paths: &[String],
+) -> Result<GitStatus, GitError> {
+    let mut args = vec!["checkout", "--quiet"];
+    if paths.is_empty() {
+        let target = target.ok_or_else(|| GitError::Failed("nothing to check out".to_string()))?;
+        if create {
+            args.push("-b");
+        }
+        args.push(target);
+    } else {
+        args.extend(target);
+        args.push("--");
+        args.extend(paths.iter().map(String::as_str));
+    }
+    run_git(app, connection_id, root, &git_command(&args), None).await?;
+    invalidate(app, connection_id, root).await;
+    status(app, connection_id, root).await
+}
+
+/// Run a stash action and return the stash list afterwards.
+pub async fn stash(
+    app: &AppHandle,
+    connection_id: &str,
+    root: &str,
+    action: StashAction,
+) -> Result<Vec<GitStash>, GitError> {
+    let name = |index: u32| format!("stash@{{{}}}", index);
+    let mut args: Vec<String> = vec!["stash".to_string()];
+    match &action {
+        StashAction::List => {}
+        StashAction::Push {
+            message,
+            include_untracked,
+        } => {
+            args.extend(["push".to_string(), "--quiet".to_string()]);
+            if *include_untracked {
+                args.push("--include-untracked".to_string());
+            }
+            if let Some(message) = message {
+                args.push(format!("--message={}", message));
+            }
+        }
+        StashAction::Pop { index } => args.extend(["pop".to_string(), "--quiet".to_string(), name(*index)]),
+        StashAction::Apply { index } => args.extend(["apply".to_string(), "--quiet".to_string(), name(*index)]),
+        StashAction::Drop { index } => args.extend(["drop".to_string(), "--quiet".to_string(), name(*index)]),
+    }
+    if !matches!(action, StashAction::List) {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run_git(app, connection_id, root, &git_command(&args), None).await?;
+        if !matches!(action, StashAction::Drop { .. }) {
+            invalidate(app, connection_id, root).await;
+        }
+        let _ = status(app, connection_id, root).await;
+    }
+    let list = git_command(&["stash", "list", "--format=%gd%x1f%H%x1f%at%x1f%gs"]);
+    Ok(parse_stashes(&run_git(app, connection_id, root, &list, None).await?))
+}
+
+/// Drop cached listings under `root` after git rewrote the working tree.
+async fn invalidate(app: &AppHandle, connection_id: &str, root: &str) {
+    let _ = actor_request(app, connection_id, |respond_to| ConnectionRequest::InvalidateDirCache {
+        paths: vec![root.to_string()],
+        respond_to,
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_entries() {
+        let output = concat!(
+            "/home/me/project\n",
+            "# branch.oid 1f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c\0",
+            "# branch.head main\0",
+            "# branch.upstream origin/main\0",
+            "# branch.ab +2 -1\0",
+            "1 .M N... 100644 100644 100644 3b18e512 3b18e512 src/main.rs\0",
+            "1 A. N... 000000 100644 100644 00000000 e69de29b docs/new file.md\0",
+            "1 D. N... 100644 000000 000000 e69de29b 00000000 old.txt\0",
+            "2 R. N... 100644 100644 100644 e69de29b e69de29b R100 lib/with space.rs\0lib/old name.rs\0",
+            "u UU N... 100644 100644 100644 100644 a1a1a1a1 b2b2b2b2 c3c3c3c3 conflict.rs\0",
+            "? notes/todo list.txt\0",
+            "? build/\0",
+        );
+        let status = parse_status(output);
+        assert_eq!(status.toplevel, "/home/me/project");
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.head.as_deref(), Some("1f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+
+        use Decoration as D;
+        use FileState as F;
+        // Path, rename source, index, worktree, decoration, staged
+        let expected = [
+            ("src/main.rs", None, F::Unmodified, F::Modified, D::Modified, false),
+            ("docs/new file.md", None, F::Added, F::Unmodified, D::Added, true),
+            ("old.txt", None, F::Deleted, F::Unmodified, D::Deleted, true),
+            ("lib/with space.rs", Some("lib/old name.rs"), F::Renamed, F::Unmodified, D::Renamed, true),
+            ("conflict.rs", None, F::Unmerged, F::Unmerged, D::Conflicted, true),
+            ("notes/todo list.txt", None, F::Untracked, F::Untracked, D::Untracked, false),
+            ("build", None, F::Untracked, F::Untracked, D::Untracked, false),
+        ];
+        let actual: Vec<_> = status
+            .files
+            .iter()
+            .map(|f| (f.repo_path.as_str(), f.orig_repo_path.as_deref(), f.index, f.worktree, f.decoration, f.staged))
+            .collect();
+        assert_eq!(actual, expected);
+        assert_eq!(status.files[0].path, "/home/me/project/src/main.rs");
+    }
+
+    #[test]
+    fn status_before_first_commit_and_detached() {
+        let initial = parse_status("/repo\n# branch.oid (initial)\0# branch.head main\0");
+        assert_eq!(initial.head, None);
+        assert_eq!(initial.branch.as_deref(), Some("main"));
+        assert!(initial.files.is_empty());
+
+        let detached = parse_status("/repo/\n# branch.oid abc123\0# branch.head (detached)\0? a.txt\0");
+        assert_eq!(detached.branch, None);
+        assert_eq!(detached.files[0].path, "/repo/a.txt");
+    }
+
+    #[test]
+    fn diff_modified_file() {
+        let output = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 3b18e51..a8c2f0d 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,5 @@ mod tests
+ use std::io;
+-fn old() {}
++fn new() {}
++fn added() {}
+
+ fn end() {}
+\\ No newline at end of file
+";
+        let files = parse_diff(output);
+        assert_eq!(files.len(), 1);
+        let file = &files[0];
+        assert_eq!(file.old_path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(file.new_path.as_deref(), Some("src/lib.rs"));
+        let hunk = &file.hunks[0];
+        assert_eq!((hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines), (1, 4, 1, 5));
+        assert_eq!(hunk.section, "mod tests");
+        let lines: Vec<(DiffLineKind, &str, Option<u64>, Option<u64>)> = hunk
+            .lines
+            .iter()
+            .map(|line| (line.kind, line.content.as_str(), line.old_line, line.new_line))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (DiffLineKind::Context, "use std::io;", Some(1), Some(1)),
+                (DiffLineKind::Removed, "fn old() {}", Some(2), None),
+                (DiffLineKind::Added, "fn new() {}", None, Some(2)),
+                (DiffLineKind::Added, "fn added() {}", None, Some(3)),
+                (DiffLineKind::Context, "", Some(3), Some(4)),
+                (DiffLineKind::Context, "fn end() {}", Some(4), Some(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_added_deleted_renamed_and_binary() {
+        let output = "\
+diff --git a/docs/new file.md b/docs/new file.md
+new file mode 100644
+index 0000000..e69de29
+--- /dev/null
++++ b/docs/new file.md\t
+@@ -0,0 +1 @@
++hello
+diff --git a/old.txt b/old.txt
+deleted file mode 100644
+index e69de29..0000000
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+diff --git a/lib/old name.rs b/lib/with space.rs
+similarity index 100%
+rename from lib/old name.rs
+rename to lib/with space.rs
+diff --git a/logo.png b/logo.png
+index 1111111..2222222 100644
+Binary files a/logo.png and b/logo.png differ
+";
+        let files = parse_diff(output);
+        let summary: Vec<(Option<&str>, Option<&str>, bool, usize)> = files
+            .iter()
+            .map(|file| (file.old_path.as_deref(), file.new_path.as_deref(), file.binary, file.hunks.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (None, Some("docs/new file.md"), false, 1),
+                (Some("old.txt"), None, false, 1),
+                (Some("lib/old name.rs"), Some("lib/with space.rs"), false, 0),
+                (Some("logo.png"), Some("logo.png"), true, 0),
+            ]
+        );
+        assert_eq!(files[0].hunks[0].lines[0].new_line, Some(1));
+        assert_eq!(files[1].hunks[0].lines[0].old_line, Some(1));
+    }
+}
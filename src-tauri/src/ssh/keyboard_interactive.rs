@@ -0,0 +1,101 @@
+//! Bridges SSH keyboard-interactive prompts (PAM, TOTP) to the frontend.
+//!
+//! The connect path calls [`ask`], which emits `ssh_keyboard_interactive_prompt` and parks until the
+//! UI answers through `ssh_keyboard_interactive_respond` (or the prompt times out).
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{oneshot, Mutex};
+use uuid::Uuid;
+
+/// How long we wait for the user to type an answer before failing authentication.
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptItem {
+    pub prompt: String,
+    pub echo: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PromptRequest {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub correlation_id: Option<String>,
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<PromptItem>,
+}
+
+/// Event payload sent to the frontend for each server info request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptEvent {
+    request_id: String,
+    host: String,
+    port: u16,
+    username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<String>,
+    name: String,
+    instructions: String,
+    prompts: Vec<PromptItem>,
+}
+
+type PendingMap = HashMap<String, oneshot::Sender<Option<Vec<String>>>>;
+
+static PENDING: OnceLock<Mutex<PendingMap>> = OnceLock::new();
+
+fn pending() -> &'static Mutex<PendingMap> {
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Forward prompts to the UI and wait for one answer per prompt.
+pub async fn ask(app: &AppHandle, request: PromptRequest) -> Result<Vec<String>, String> {
+    let request_id = Uuid::new_v4().to_string();
+    let expected = request.prompts.len();
+    let (tx, rx) = oneshot::channel();
+    pending().lock().await.insert(request_id.clone(), tx);
+
+    let event = PromptEvent {
+        request_id: request_id.clone(),
+        host: request.host,
+        port: request.port,
+        username: request.username,
+        correlation_id: request.correlation_id,
+        name: request.name,
+        instructions: request.instructions,
+        prompts: request.prompts,
+    };
+    if let Err(e) = app.emit("ssh_keyboard_interactive_prompt", event) {
+        pending().lock().await.remove(&request_id);
+        return Err(format!("Failed to deliver keyboard-interactive prompt: {}", e));
+    }
+
+    let answer = tokio::time::timeout(PROMPT_TIMEOUT, rx).await;
+    pending().lock().await.remove(&request_id);
+
+    match answer {
+        Ok(Ok(Some(responses))) if responses.len() == expected => Ok(responses),
+        Ok(Ok(Some(responses))) => Err(format!(
+            "Expected {} keyboard-interactive responses, got {}",
+            expected,
+            responses.len()
+        )),
+        Ok(Ok(None)) | Ok(Err(_)) => Err("Keyboard-interactive prompt cancelled".to_string()),
+        Err(_) => Err("Keyboard-interactive prompt timed out".to_string()),
+    }
+}
+
+/// Deliver the user's answers (`None` cancels). Returns `false` if no prompt is waiting on `request_id`.
+pub async fn respond(request_id: &str, responses: Option<Vec<String>>) -> bool {
+    match pending().lock().await.remove(request_id) {
+        Some(tx) => tx.send(responses).is_ok(),
+        None => false,
+    }
+}
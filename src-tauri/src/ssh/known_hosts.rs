@@ -1,6 +1,10 @@
+use crate::ssh::openssh_known_hosts::{self as openssh, KnownHostsLine, Marker};
 use serde::{Deserialize, Serialize};
+use ssh_key::{HashAlg, PublicKey};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::SystemTime;
 use tauri::AppHandle;
 use tauri::Manager;
 use tokio::fs;
@@ -17,10 +21,82 @@ pub struct KnownHostEntry {
     pub trusted_at: u64,
 }
 
+/// One change to the set of trusted keys for a host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyAuditEvent {
+    pub timestamp: u64,
+    pub host: String,
+    pub port: u16,
+    /// `added` or `retired`
+    pub action: String,
+    pub key_type: String,
+    pub fingerprint_sha256: String,
+    /// What caused the change: `user`, `import`, `hostkeys-00@openssh.com`
+    pub source: String,
+}
+
+/// On-disk value for a host: a key set, or a single entry from before hosts could hold several keys.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredHostKeys {
+    Many(Vec<KnownHostEntry>),
+    One(KnownHostEntry),
+}
+
+/// Host key that must never be accepted (`@revoked` in OpenSSH `known_hosts`), for any host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokedKeyEntry {
+    pub key_type: String,
+    pub fingerprint_sha256: String,
+    pub public_key_openssh: String,
+    pub revoked_at: u64,
+}
+
+/// Outcome of checking a server host key against our store (and the system file in read-through mode).
+#[derive(Debug, Clone)]
+pub enum HostKeyCheck {
+    /// `source` is `"app"` or the system known_hosts path.
+    Trusted { source: String },
+    Revoked,
+    /// A different key of the same type is trusted for this host.
+    Mismatch(KnownHostEntry),
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSkip {
+    pub line: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub host_keys: usize,
+    pub revoked: usize,
+    pub unchanged: usize,
+    pub skipped: Vec<ImportSkip>,
+}
+
 #[derive(Default)]
 struct KnownHostsState {
     loaded: bool,
-    entries: HashMap<String, KnownHostEntry>,
+    /// Trusted keys per `host:port`, oldest first.
+    entries: HashMap<String, Vec<KnownHostEntry>>,
+    audit: Vec<HostKeyAuditEvent>,
+    revoked: Vec<RevokedKeyEntry>,
+    settings: KnownHostsSettings,
+}
+
+/// Persisted known-hosts preferences (`known_host_settings.json`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct KnownHostsSettings {
+    /// System `known_hosts` consulted by `check` when set.
+    read_through: Option<PathBuf>,
 }
 
 static KNOWN_HOSTS: std::sync::OnceLock<Mutex<KnownHostsState>> = std::sync::OnceLock::new();
@@ -40,47 +116,183 @@ fn file_path(app: &AppHandle) -> Result<PathBuf, tauri::Error> {
     Ok(app.path().app_config_dir()?.join("known_hosts.json"))
 }
 
-async fn ensure_loaded_locked(app: &AppHandle, state: &mut KnownHostsState) -> Result<(), String> {
-    if state.loaded {
-        return Ok(());
-    }
+fn audit_file_path(app: &AppHandle) -> Result<PathBuf, tauri::Error> {
+    Ok(app.path().app_config_dir()?.join("known_host_audit.json"))
+}
+
+fn revoked_file_path(app: &AppHandle) -> Result<PathBuf, tauri::Error> {
+    Ok(app.path().app_config_dir()?.join("known_host_revoked.json"))
+}
+
+fn settings_file_path(app: &AppHandle) -> Result<PathBuf, tauri::Error> {
+    Ok(app.path().app_config_dir()?.join("known_host_settings.json"))
+}
+
+/// The user's OpenSSH `~/.ssh/known_hosts`.
+pub fn default_system_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts"))
+}
+
+/// OpenSSH's `GlobalKnownHostsFile`, consulted alongside the read-through file.
+const GLOBAL_SYSTEM_PATH: &str = "/etc/ssh/ssh_known_hosts";
+
+/// A parsed system known_hosts file, reused until its mtime or size changes.
+struct CachedSystemFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    lines: Arc<Vec<KnownHostsLine>>,
+}
+
+static SYSTEM_FILES: OnceLock<StdMutex<HashMap<PathBuf, CachedSystemFile>>> = OnceLock::new();
+
+fn system_files() -> &'static StdMutex<HashMap<PathBuf, CachedSystemFile>> {
+    SYSTEM_FILES.get_or_init(|| StdMutex::new(HashMap::new()))
+}
 
-    let path = file_path(app).map_err(|e| e.to_string())?;
-    let bytes = match fs::read(&path).await {
-        Ok(b) => b,
+/// Parsed lines of an OpenSSH known_hosts file; a missing file reads as empty.
+async fn read_system_file(path: &PathBuf) -> Result<Arc<Vec<KnownHostsLine>>, String> {
+    let meta = match fs::metadata(path).await {
+        Ok(meta) => meta,
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            state.loaded = true;
-            return Ok(());
+            system_files().lock().unwrap_or_else(|e| e.into_inner()).remove(path);
+            return Ok(Arc::default());
         }
-        Err(e) => return Err(e.to_string()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
     };
+    let modified = meta.modified().ok();
+    if let Some(cached) = system_files().lock().unwrap_or_else(|e| e.into_inner()).get(path) {
+        if cached.modified.is_some() && cached.modified == modified && cached.len == meta.len() {
+            return Ok(cached.lines.clone());
+        }
+    }
 
-    let decoded: HashMap<String, KnownHostEntry> =
-        serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
-    state.entries = decoded;
-    state.loaded = true;
-    Ok(())
+    let text = match fs::read_to_string(path).await {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Arc::default()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let lines = Arc::new(openssh::parse(&text).0);
+    system_files().lock().unwrap_or_else(|e| e.into_inner()).insert(
+        path.clone(),
+        CachedSystemFile {
+            modified,
+            len: meta.len(),
+            lines: lines.clone(),
+        },
+    );
+    Ok(lines)
 }
 
-async fn save_locked(app: &AppHandle, state: &KnownHostsState) -> Result<(), String> {
-    let path = file_path(app).map_err(|e| e.to_string())?;
+async fn read_json<T: serde::de::DeserializeOwned + Default>(path: &PathBuf) -> Result<T, String> {
+    match fs::read(path).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+async fn write_json<T: Serialize>(path: &PathBuf, value: &T) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
     }
-    let json = serde_json::to_vec_pretty(&state.entries).map_err(|e| e.to_string())?;
+    let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
     fs::write(path, json).await.map_err(|e| e.to_string())
 }
 
+async fn ensure_loaded_locked(app: &AppHandle, state: &mut KnownHostsState) -> Result<(), String> {
+    if state.loaded {
+        return Ok(());
+    }
+
+    let stored: HashMap<String, StoredHostKeys> =
+        read_json(&file_path(app).map_err(|e| e.to_string())?).await?;
+    state.entries = stored
+        .into_iter()
+        .map(|(k, v)| match v {
+            StoredHostKeys::Many(keys) => (k, keys),
+            StoredHostKeys::One(entry) => (k, vec![entry]),
+        })
+        .collect();
+    state.audit = read_json(&audit_file_path(app).map_err(|e| e.to_string())?).await?;
+    state.revoked = read_json(&revoked_file_path(app).map_err(|e| e.to_string())?).await?;
+    state.settings = read_json(&settings_file_path(app).map_err(|e| e.to_string())?).await?;
+    state.loaded = true;
+    Ok(())
+}
+
+async fn save_locked(app: &AppHandle, state: &KnownHostsState) -> Result<(), String> {
+    write_json(&file_path(app).map_err(|e| e.to_string())?, &state.entries).await?;
+    write_json(&audit_file_path(app).map_err(|e| e.to_string())?, &state.audit).await
+}
+
+async fn save_revoked_locked(app: &AppHandle, state: &KnownHostsState) -> Result<(), String> {
+    write_json(&revoked_file_path(app).map_err(|e| e.to_string())?, &state.revoked).await
+}
+
+async fn save_settings_locked(app: &AppHandle, state: &KnownHostsState) -> Result<(), String> {
+    write_json(&settings_file_path(app).map_err(|e| e.to_string())?, &state.settings).await
+}
+
 fn store() -> &'static Mutex<KnownHostsState> {
     KNOWN_HOSTS.get_or_init(|| Mutex::new(KnownHostsState::default()))
 }
 
+fn record_locked(state: &mut KnownHostsState, entry: &KnownHostEntry, action: &str, source: &str) {
+    state.audit.push(HostKeyAuditEvent {
+        timestamp: now_ms(),
+        host: entry.host.clone(),
+        port: entry.port,
+        action: action.to_string(),
+        key_type: entry.key_type.clone(),
+        fingerprint_sha256: entry.fingerprint_sha256.clone(),
+        source: source.to_string(),
+    });
+}
+
+/// Add `entry` to its host's key set, retiring other keys of the same type when `replace_same_type`.
+/// Returns `false` if the key was already trusted.
+fn add_key_locked(
+    state: &mut KnownHostsState,
+    entry: KnownHostEntry,
+    replace_same_type: bool,
+    source: &str,
+) -> bool {
+    let keys = state.entries.entry(key(&entry.host, entry.port)).or_default();
+    if keys.iter().any(|k| k.fingerprint_sha256 == entry.fingerprint_sha256) {
+        return false;
+    }
+    let (retired, kept): (Vec<_>, Vec<_>) = std::mem::take(keys)
+        .into_iter()
+        .partition(|k| replace_same_type && k.key_type == entry.key_type);
+    *keys = kept;
+    keys.push(entry.clone());
+
+    for old in &retired {
+        record_locked(state, old, "retired", source);
+    }
+    record_locked(state, &entry, "added", source);
+    true
+}
+
+/// The first key trusted for `host:port` (stable across key additions).
 pub async fn get(app: &AppHandle, host: &str, port: u16) -> Result<Option<KnownHostEntry>, String> {
     let mut guard = store().lock().await;
     ensure_loaded_locked(app, &mut guard).await?;
-    Ok(guard.entries.get(&key(host, port)).cloned())
+    Ok(guard
+        .entries
+        .get(&key(host, port))
+        .and_then(|keys| keys.first())
+        .cloned())
+}
+
+/// Every key trusted for `host:port`.
+pub async fn get_all(app: &AppHandle, host: &str, port: u16) -> Result<Vec<KnownHostEntry>, String> {
+    let mut guard = store().lock().await;
+    ensure_loaded_locked(app, &mut guard).await?;
+    Ok(guard.entries.get(&key(host, port)).cloned().unwrap_or_default())
 }
 
+/// Trust a key for `host:port`, replacing a previously trusted key of the same type.
 pub async fn upsert(
     app: &AppHandle,
     host: &str,
@@ -91,8 +303,8 @@ pub async fn upsert(
 ) -> Result<(), String> {
     let mut guard = store().lock().await;
     ensure_loaded_locked(app, &mut guard).await?;
-    guard.entries.insert(
-        key(host, port),
+    add_key_locked(
+        &mut guard,
         KnownHostEntry {
             host: host.trim().to_string(),
             port,
@@ -101,19 +313,375 @@ pub async fn upsert(
             public_key_openssh: public_key_openssh.to_string(),
             trusted_at: now_ms(),
         },
+        true,
+        "user",
     );
     save_locked(app, &guard).await
 }
 
-pub async fn remove(app: &AppHandle, host: &str, port: u16) -> Result<(), String> {
+/// Stop trusting one key (`fingerprint_sha256`) or, with `None`, every key for `host:port`.
+pub async fn remove(
+    app: &AppHandle,
+    host: &str,
+    port: u16,
+    fingerprint_sha256: Option<&str>,
+) -> Result<(), String> {
     let mut guard = store().lock().await;
     ensure_loaded_locked(app, &mut guard).await?;
-    guard.entries.remove(&key(host, port));
+    let host_key = key(host, port);
+    let keys = guard.entries.remove(&host_key).unwrap_or_default();
+    let (retired, kept): (Vec<_>, Vec<_>) = keys
+        .into_iter()
+        .partition(|k| fingerprint_sha256.map_or(true, |fp| k.fingerprint_sha256 == fp));
+    if !kept.is_empty() {
+        guard.entries.insert(host_key, kept);
+    }
+    for old in &retired {
+        record_locked(&mut guard, old, "retired", "user");
+    }
     save_locked(app, &guard).await
 }
 
 pub async fn list(app: &AppHandle) -> Result<Vec<KnownHostEntry>, String> {
     let mut guard = store().lock().await;
     ensure_loaded_locked(app, &mut guard).await?;
-    Ok(guard.entries.values().cloned().collect())
+    Ok(guard.entries.values().flatten().cloned().collect())
+}
+
+/// Audit trail of key additions/retirements, optionally for a single `host:port`.
+pub async fn audit_log(
+    app: &AppHandle,
+    host: Option<&str>,
+    port: Option<u16>,
+) -> Result<Vec<HostKeyAuditEvent>, String> {
+    let mut guard = store().lock().await;
+    ensure_loaded_locked(app, &mut guard).await?;
+    Ok(guard
+        .audit
+        .iter()
+        .filter(|e| host.map_or(true, |h| e.host == h.trim()) && port.map_or(true, |p| e.port == p))
+        .cloned()
+        .collect())
+}
+
+/// Whether an announcement from a server verified with `verified_fingerprint` may change the
+/// host's keys: that key must be pinned for `host:port` and be part of the announcement itself.
+fn announcement_accepted(
+    state: &KnownHostsState,
+    host: &str,
+    port: u16,
+    verified_fingerprint: &str,
+    announced: &[PublicKey],
+) -> bool {
+    let pinned = state.entries.get(&key(host, port)).map(Vec::as_slice).unwrap_or_default();
+    pinned.iter().any(|k| k.fingerprint_sha256 == verified_fingerprint)
+        && announced
+            .iter()
+            .any(|k| k.fingerprint(HashAlg::Sha256).to_string() == verified_fingerprint)
+}
+
+/// Keys from a `hostkeys-00@openssh.com` announcement that are neither trusted nor revoked, and
+/// so need proving before [`apply_announcement`]. `None` when the announcement is not accepted.
+pub async fn unproven_announced(
+    app: &AppHandle,
+    host: &str,
+    port: u16,
+    verified_fingerprint: &str,
+    announced: &[PublicKey],
+) -> Result<Option<Vec<PublicKey>>, String> {
+    let mut guard = store().lock().await;
+    ensure_loaded_locked(app, &mut guard).await?;
+    if !announcement_accepted(&guard, host, port, verified_fingerprint, announced) {
+        return Ok(None);
+    }
+    let pinned = guard.entries.get(&key(host, port)).cloned().unwrap_or_default();
+    Ok(Some(
+        announced
+            .iter()
+            .filter(|k| {
+                let fingerprint = k.fingerprint(HashAlg::Sha256).to_string();
+                !pinned.iter().any(|p| p.fingerprint_sha256 == fingerprint)
+                    && !guard.revoked.iter().any(|r| r.fingerprint_sha256 == fingerprint)
+            })
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Keys added and retired by [`apply_announcement`].
+#[derive(Debug, Clone, Default)]
+pub struct AnnouncementOutcome {
+    pub added: Vec<KnownHostEntry>,
+    pub retired: Vec<KnownHostEntry>,
+}
+
+/// Apply a `hostkeys-00@openssh.com` announcement, as OpenSSH's `UpdateHostKeys` does: trust the
+/// `proven` keys (those the server showed it holds the private key for) and retire pinned keys
+/// the server no longer announces. Nothing changes unless the announcement is accepted.
+pub async fn apply_announcement(
+    app: &AppHandle,
+    host: &str,
+    port: u16,
+    verified_fingerprint: &str,
+    announced: &[PublicKey],
+    proven: &[PublicKey],
+) -> Result<AnnouncementOutcome, String> {
+    const SOURCE: &str = "hostkeys-00@openssh.com";
+    let mut guard = store().lock().await;
+    ensure_loaded_locked(app, &mut guard).await?;
+    let mut outcome = AnnouncementOutcome::default();
+    if !announcement_accepted(&guard, host, port, verified_fingerprint, announced) {
+        return Ok(outcome);
+    }
+
+    let announced_fingerprints: Vec<String> = announced
+        .iter()
+        .map(|k| k.fingerprint(HashAlg::Sha256).to_string())
+        .collect();
+    for public_key in proven {
+        let fingerprint_sha256 = public_key.fingerprint(HashAlg::Sha256).to_string();
+        if !announced_fingerprints.contains(&fingerprint_sha256)
+            || guard.revoked.iter().any(|r| r.fingerprint_sha256 == fingerprint_sha256)
+        {
+            continue;
+        }
+        let entry = KnownHostEntry {
+            host: host.trim().to_string(),
+            port,
+            key_type: public_key.algorithm().as_str().to_string(),
+            fingerprint_sha256,
+            public_key_openssh: public_key.to_openssh().map_err(|e| e.to_string())?,
+            trusted_at: now_ms(),
+        };
+        if add_key_locked(&mut guard, entry.clone(), false, SOURCE) {
+            outcome.added.push(entry);
+        }
+    }
+
+    let host_key = key(host, port);
+    let keys = guard.entries.remove(&host_key).unwrap_or_default();
+    let (kept, retired): (Vec<_>, Vec<_>) = keys
+        .into_iter()
+        .partition(|k| announced_fingerprints.contains(&k.fingerprint_sha256));
+    guard.entries.insert(host_key, kept);
+    for old in &retired {
+        record_locked(&mut guard, old, "retired", SOURCE);
+    }
+    outcome.retired = retired;
+
+    if !outcome.added.is_empty() || !outcome.retired.is_empty() {
+        save_locked(app, &guard).await?;
+    }
+    Ok(outcome)
+}
+
+/// Consult the system known_hosts (`path`, default `~/.ssh/known_hosts`) in `check`, or stop doing so.
+/// The choice is saved with the other known-hosts files and survives restarts.
+pub async fn set_read_through(app: &AppHandle, path: Option<PathBuf>) -> Result<(), String> {
+    let mut guard = store().lock().await;
+    ensure_loaded_locked(app, &mut guard).await?;
+    guard.settings.read_through = path;
+    save_settings_locked(app, &guard).await
+}
+
+pub async fn read_through(app: &AppHandle) -> Result<Option<PathBuf>, String> {
+    let mut guard = store().lock().await;
+    ensure_loaded_locked(app, &mut guard).await?;
+    Ok(guard.settings.read_through.clone())
+}
+
+/// Decide whether `key` is acceptable for `host:port`.
+///
+/// Revocations win, then the app's own pin; in read-through mode the system file is used for hosts
+/// we have not pinned ourselves, together with `/etc/ssh/ssh_known_hosts`.
+pub async fn check(app: &AppHandle, host: &str, port: u16, server_key: &PublicKey) -> Result<HostKeyCheck, String> {
+    let fingerprint = server_key.fingerprint(HashAlg::Sha256).to_string();
+    let mut guard = store().lock().await;
+    ensure_loaded_locked(app, &mut guard).await?;
+    let revoked = guard.revoked.iter().any(|r| r.fingerprint_sha256 == fingerprint);
+    let pinned = guard.entries.get(&key(host, port)).cloned().unwrap_or_default();
+    let read_through = guard.settings.read_through.clone();
+    drop(guard);
+
+    if revoked {
+        return Ok(HostKeyCheck::Revoked);
+    }
+
+    // The read-through file, then the system-wide one; an unreadable global file is not fatal.
+    let mut files = Vec::new();
+    if let Some(path) = &read_through {
+        files.push((path.clone(), read_system_file(path).await?));
+        let global = PathBuf::from(GLOBAL_SYSTEM_PATH);
+        match read_system_file(&global).await {
+            Ok(lines) => files.push((global, lines)),
+            Err(e) => log::warn!("Skipping global known_hosts: {}", e),
+        }
+    }
+    let system_lines = || files.iter().flat_map(|(path, lines)| lines.iter().map(move |l| (path, l)));
+    if system_lines().any(|(_, l)| l.marker == Some(Marker::Revoked) && l.key.key_data() == server_key.key_data()) {
+        return Ok(HostKeyCheck::Revoked);
+    }
+
+    if pinned.iter().any(|k| k.fingerprint_sha256 == fingerprint) {
+        return Ok(HostKeyCheck::Trusted {
+            source: "app".to_string(),
+        });
+    }
+    // Only a different key of the same type is a changed host key; a new type is merely unknown.
+    let server_key_type = server_key.algorithm().as_str().to_string();
+    if let Some(entry) = pinned.into_iter().find(|k| k.key_type == server_key_type) {
+        return Ok(HostKeyCheck::Mismatch(entry));
+    }
+
+    let matching: Vec<(&PathBuf, &KnownHostsLine)> = system_lines()
+        .filter(|(_, l)| l.marker.is_none() && l.hosts.matches(host, port))
+        .collect();
+    if let Some((path, _)) = matching.iter().find(|(_, l)| l.key.key_data() == server_key.key_data()) {
+        return Ok(HostKeyCheck::Trusted {
+            source: path.display().to_string(),
+        });
+    }
+    // Like OpenSSH, only a different key of the same type counts as a changed host key.
+    if let Some((_, line)) = matching.iter().find(|(_, l)| l.key.algorithm() == server_key.algorithm()) {
+        return Ok(HostKeyCheck::Mismatch(KnownHostEntry {
+            host: host.trim().to_string(),
+            port,
+            key_type: line.key.algorithm().as_str().to_string(),
+            fingerprint_sha256: line.key.fingerprint(HashAlg::Sha256).to_string(),
+            public_key_openssh: line.key.to_openssh().unwrap_or_default(),
+            trusted_at: 0,
+        }));
+    }
+    Ok(HostKeyCheck::Unknown)
+}
+
+/// Merge an OpenSSH known_hosts file into the store.
+///
+/// Hashed hostnames cannot be reversed; they are imported only when they match one of `candidates`
+/// (e.g. the hosts of saved profiles). Hosts already pinned to a different key are left alone.
+pub async fn import_openssh(
+    app: &AppHandle,
+    text: &str,
+    candidates: &[(String, u16)],
+) -> Result<ImportReport, String> {
+    let (lines, skipped) = openssh::parse(text);
+    let mut report = ImportReport {
+        skipped: skipped
+            .into_iter()
+            .map(|s| ImportSkip {
+                line: s.line,
+                reason: s.reason,
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    let mut guard = store().lock().await;
+    ensure_loaded_locked(app, &mut guard).await?;
+    let now = now_ms();
+
+    for line in lines {
+        let key_type = line.key.algorithm().as_str().to_string();
+        let fingerprint_sha256 = line.key.fingerprint(HashAlg::Sha256).to_string();
+        let public_key_openssh = line.key.to_openssh().map_err(|e| e.to_string())?;
+        let skip = |reason: &str| ImportSkip {
+            line: line.line,
+            reason: reason.to_string(),
+        };
+
+        match line.marker {
+            Some(Marker::Revoked) => {
+                if !guard.revoked.iter().any(|r| r.fingerprint_sha256 == fingerprint_sha256) {
+                    guard.revoked.push(RevokedKeyEntry {
+                        key_type,
+                        fingerprint_sha256,
+                        public_key_openssh,
+                        revoked_at: now,
+                    });
+                    report.revoked += 1;
+                } else {
+                    report.unchanged += 1;
+                }
+            }
+            // russh does not negotiate certificate host keys, so a CA could never vouch for a host.
+            Some(Marker::CertAuthority) => {
+                report.skipped.push(skip("@cert-authority is not supported (host certificates are not negotiated)"));
+            }
+            None => {
+                let hosts = match &line.hosts {
+                    openssh::HostField::Hashed { .. } => candidates
+                        .iter()
+                        .filter(|(h, p)| line.hosts.matches(h, *p))
+                        .cloned()
+                        .collect(),
+                    patterns => patterns.literal_hosts(),
+                };
+                if hosts.is_empty() {
+                    report.skipped.push(skip(match line.hosts {
+                        openssh::HostField::Hashed { .. } => "hashed hostname matches no known host",
+                        _ => "only wildcard patterns (use read-through mode)",
+                    }));
+                    continue;
+                }
+
+                for (host, port) in hosts {
+                    let existing = guard.entries.get(&key(&host, port)).cloned().unwrap_or_default();
+                    if existing.iter().any(|k| k.fingerprint_sha256 == fingerprint_sha256) {
+                        report.unchanged += 1;
+                    } else if existing.iter().any(|k| k.key_type == key_type) {
+                        report.skipped.push(skip(&format!(
+                            "{} already trusts a different {} key",
+                            openssh::host_name(&host, port),
+                            key_type
+                        )));
+                    } else {
+                        add_key_locked(
+                            &mut guard,
+                            KnownHostEntry {
+                                host: host.clone(),
+                                port,
+                                key_type: key_type.clone(),
+                                fingerprint_sha256: fingerprint_sha256.clone(),
+                                public_key_openssh: public_key_openssh.clone(),
+                                trusted_at: now,
+                            },
+                            false,
+                            "import",
+                        );
+                        report.host_keys += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    save_locked(app, &guard).await?;
+    save_revoked_locked(app, &guard).await?;
+    Ok(report)
+}
+
+/// Render the store as an OpenSSH known_hosts file (pins and `@revoked`).
+pub async fn export_openssh(app: &AppHandle, hash_hostnames: bool) -> Result<String, String> {
+    let mut guard = store().lock().await;
+    ensure_loaded_locked(app, &mut guard).await?;
+
+    let mut out = String::from("# Exported by DriftCode\n");
+    let mut entries: Vec<&KnownHostEntry> = guard.entries.values().flatten().collect();
+    entries.sort_by(|a, b| (a.host.as_str(), a.port).cmp(&(b.host.as_str(), b.port)));
+
+    let lines = entries
+        .into_iter()
+        .filter_map(|e| {
+            let key = PublicKey::from_openssh(&e.public_key_openssh).ok()?;
+            openssh::format_line(None, &[openssh::host_name(&e.host, e.port)], &key, hash_hostnames)
+        })
+        .chain(guard.revoked.iter().filter_map(|r| {
+            let key = PublicKey::from_openssh(&r.public_key_openssh).ok()?;
+            openssh::format_line(Some(Marker::Revoked), &["*".to_string()], &key, false)
+        }));
+    for line in lines {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
 }
@@ -0,0 +1,610 @@
+//! Language servers running on the remote host.
+//!
+//! A server is launched in the project root over an exec channel and spoken to with LSP's
+//! `Content-Length` framing on its stdio. The login shell that starts it prints its PID behind a
+//! marker first: stdout before that line (profile script output) goes to `lsp_log`, and stopping
+//! the server sends `TERM` to that process group. Messages are relayed as JSON values: the
+//! frontend sends them with `lsp_send` and receives the server's as `lsp_message` events. Document
+//! URIs in the fields listed in [`URI_FIELDS`] are rewritten on the way through, between the
+//! server's `file:///path` and the editor's `driftcode://<connectionId>/path`. Percent-encoding is
+//! kept as it is.
+//!
+//! When the channel ends (the server exits or the connection drops) in-flight requests are
+//! answered with an error. After `ssh_reconnect` the connection's servers are started again; the
+//! frontend sees `lsp_status` with a higher `restarts` and must initialize the new server.
+
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::SshError;
+use crate::ssh::exec::{self, ExecOptions, PidScanner, TrackedCommand};
+use crate::state::AppState;
+use crate::trace::{emit_trace, TraceEvent};
+use russh::{ChannelMsg, Sig};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
+use uuid::Uuid;
+
+pub const EDITOR_SCHEME: &str = "driftcode";
+/// Largest message accepted from a server
+const MAX_MESSAGE: usize = 64 * 1024 * 1024;
+/// JSON-RPC error code for requests cut off by the server exiting (LSP `RequestFailed`)
+const REQUEST_FAILED: i64 = -32803;
+/// Fields whose string value is a document or folder URI. `WorkspaceEdit.changes` is keyed by
+/// URI instead; anything else is left alone, even if it looks like a URI.
+const URI_FIELDS: [&str; 8] = ["uri", "targetUri", "rootUri", "oldUri", "newUri", "scopeUri", "baseUri", "target"];
+
+/// Known servers by name, with the command that starts them in stdio mode.
+const PRESETS: [(&str, &str); 3] = [
+    ("rust-analyzer", "rust-analyzer"),
+    ("pyright", "pyright-langserver --stdio"),
+    ("gopls", "gopls"),
+];
+
+#[derive(Debug, Error)]
+pub enum LspError {
+    #[error("Connection not found")]
+    ConnectionNotFound,
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error("Language server not found")]
+    NotFound,
+    #[error("Language server is not running")]
+    NotRunning,
+    #[error("Invalid command: {0}")]
+    InvalidCommand(String),
+    #[error(transparent)]
+    Ssh(#[from] SshError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LspStatus {
+    Starting,
+    Running,
+    /// The server exited or its connection dropped
+    Exited,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspInfo {
+    pub id: String,
+    pub connection_id: String,
+    pub root: String,
+    /// Preset name or command line as given
+    pub server: String,
+    pub command: String,
+    pub status: LspStatus,
+    /// Times the server was started again after it exited
+    pub restarts: u32,
+    pub exit_code: Option<u32>,
+    pub error: Option<String>,
+    pub created_at: u64,
+}
+
+/// Payload of `lsp_message`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspMessageEvent {
+    pub server_id: String,
+    pub message: Value,
+}
+
+/// Payload of `lsp_log`: text the server wrote to stderr, or its login shell to stdout before
+/// starting it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LspLogEvent {
+    pub server_id: String,
+    pub text: String,
+}
+
+struct LspEntry {
+    info: LspInfo,
+    /// Bumped on every start, so a finished run cannot touch its successor's state.
+    generation: u64,
+    outgoing: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    stop: watch::Sender<bool>,
+    /// Requests sent to the server and not answered yet, by serialized ID
+    pending: HashMap<String, Value>,
+}
+
+static SERVERS: OnceLock<StdMutex<HashMap<String, LspEntry>>> = OnceLock::new();
+
+fn registry() -> &'static StdMutex<HashMap<String, LspEntry>> {
+    SERVERS.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Send a request to the connection's current actor and wait for its reply.
+async fn actor_request<T>(
+    app: &AppHandle,
+    connection_id: &str,
+    request: impl FnOnce(oneshot::Sender<Result<T, SshError>>) -> ConnectionRequest,
+) -> Result<T, LspError> {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    }
+    .ok_or(LspError::ConnectionNotFound)?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| LspError::ConnectionClosed)?;
+    Ok(rx.await.map_err(|_| LspError::ConnectionClosed)??)
+}
+
+/// Resolves once a stop is requested. Wraps `wait_for`, whose guard is not `Send`.
+async fn stop_requested(stop: &mut watch::Receiver<bool>) {
+    let _ = stop.wait_for(|s| *s).await;
+}
+
+/// Rewrite the URIs in `value` (see [`URI_FIELDS`]) that start with `from` to start with `to`.
+fn rewrite_uris(value: &mut Value, from: &str, to: &str) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| rewrite_uris(item, from, to)),
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                match item {
+                    Value::String(s) if URI_FIELDS.contains(&key.as_str()) => rewrite_prefix(s, from, to),
+                    Value::Object(changes) if key == "changes" => {
+                        *changes = std::mem::take(changes)
+                            .into_iter()
+                            .map(|(mut uri, mut edits)| {
+                                rewrite_prefix(&mut uri, from, to);
+                                rewrite_uris(&mut edits, from, to);
+                                (uri, edits)
+                            })
+                            .collect();
+                    }
+                    _ => rewrite_uris(item, from, to),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn rewrite_prefix(s: &mut String, from: &str, to: &str) {
+    if let Some(rest) = s.strip_prefix(from) {
+        *s = format!("{}{}", to, rest);
+    }
+}
+
+fn editor_prefix(connection_id: &str) -> String {
+    format!("{}://{}", EDITOR_SCHEME, connection_id)
+}
+
+/// Key for matching a response to its request (IDs may be numbers or strings).
+fn id_key(id: &Value) -> String {
+    id.to_string()
+}
+
+fn frame(message: &Value) -> Vec<u8> {
+    let body = message.to_string();
+    let mut framed = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    framed.extend_from_slice(body.as_bytes());
+    framed
+}
+
+/// Split complete messages off the front of `buffer`. Returns `Err` if the stream cannot be
+/// framed (a header without `Content-Length`, or an oversized message).
+fn take_messages(buffer: &mut Vec<u8>) -> Result<Vec<Vec<u8>>, String> {
+    let mut messages = Vec::new();
+    loop {
+        let Some(header_end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") else {
+            return Ok(messages);
+        };
+        let headers = String::from_utf8_lossy(&buffer[..header_end]);
+        let length = headers
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.trim().eq_ignore_ascii_case("content-length").then(|| value.trim().parse::<usize>().ok())?
+            })
+            .ok_or_else(|| format!("missing Content-Length in {:?}", headers))?;
+        if length > MAX_MESSAGE {
+            return Err(format!("message of {} bytes is too large", length));
+        }
+        let body_start = header_end + 4;
+        if buffer.len() < body_start + length {
+            return Ok(messages);
+        }
+        messages.push(buffer[body_start..body_start + length].to_vec());
+        buffer.drain(..body_start + length);
+    }
+}
+
+fn emit_status(app: &AppHandle, info: LspInfo) {
+    if let Err(e) = app.emit("lsp_status", info) {
+        log::error!("Failed to emit LSP status: {}", e);
+    }
+}
+
+/// Start a language server for `root`. `server` is a preset name (`rust-analyzer`, `pyright`,
+/// `gopls`) or a command line that speaks LSP on stdio.
+pub async fn start(app: AppHandle, connection_id: String, root: String, server: String) -> Result<LspInfo, LspError> {
+    let command = PRESETS
+        .iter()
+        .find(|(name, _)| *name == server)
+        .map(|(_, command)| command.to_string())
+        .unwrap_or_else(|| server.clone());
+    if command.trim().is_empty() {
+        return Err(LspError::InvalidCommand(server));
+    }
+    {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(&connection_id)
+            .ok_or(LspError::ConnectionNotFound)?;
+    }
+
+    let info = LspInfo {
+        id: Uuid::new_v4().to_string(),
+        connection_id,
+        root,
+        server,
+        command,
+        status: LspStatus::Starting,
+        restarts: 0,
+        exit_code: None,
+        error: None,
+        created_at: now_ms(),
+    };
+    let (stop, _) = watch::channel(false);
+    registry().lock().unwrap_or_else(|e| e.into_inner()).insert(
+        info.id.clone(),
+        LspEntry {
+            info: info.clone(),
+            generation: 0,
+            outgoing: None,
+            stop,
+            pending: HashMap::new(),
+        },
+    );
+    emit_trace(
+        &app,
+        TraceEvent::new("lsp", "start", "Starting language server")
+            .with_correlation_id(&info.id)
+            .with_detail(format!("{} in {} via {}", info.command, info.root, info.connection_id)),
+    );
+    launch(app, &info.id);
+    Ok(info)
+}
+
+/// Start a new run of a registered server, replacing any previous one.
+fn launch(app: AppHandle, id: &str) {
+    let (outgoing, incoming) = mpsc::unbounded_channel();
+    let (stop, stopped) = watch::channel(false);
+    let (info, generation) = {
+        let mut servers = registry().lock().unwrap_or_else(|e| e.into_inner());
+        let Some(entry) = servers.get_mut(id) else { return };
+        entry.stop.send_replace(true);
+        entry.stop = stop;
+        entry.generation += 1;
+        entry.outgoing = Some(outgoing);
+        entry.info.status = LspStatus::Starting;
+        entry.info.exit_code = None;
+        entry.info.error = None;
+        (entry.info.clone(), entry.generation)
+    };
+    emit_status(&app, info.clone());
+    tauri::async_runtime::spawn(run(app, info, generation, incoming, stopped));
+}
+
+/// One run of a server: relay messages until the channel ends or a stop is requested.
+async fn run(
+    app: AppHandle,
+    info: LspInfo,
+    generation: u64,
+    mut incoming: mpsc::UnboundedReceiver<Vec<u8>>,
+    mut stopped: watch::Receiver<bool>,
+) {
+    let options = ExecOptions {
+        cwd: Some(info.root.clone()),
+        ..ExecOptions::default()
+    };
+    let TrackedCommand { command, marker } = match exec::build_protocol_command(&info.command, &options) {
+        Ok(tracked) => tracked,
+        Err(name) => {
+            finish(&app, &info.id, generation, None, Some(LspError::InvalidCommand(name).to_string()));
+            return;
+        }
+    };
+    let mut pid = PidScanner::new(marker);
+    let opened = actor_request(&app, &info.connection_id, |respond_to| ConnectionRequest::OpenExecChannel {
+        command,
+        respond_to,
+    })
+    .await;
+    let mut channel = match opened {
+        Ok(channel) => channel,
+        Err(e) => {
+            finish(&app, &info.id, generation, None, Some(e.to_string()));
+            return;
+        }
+    };
+
+    if let Some(info) = update(&info.id, generation, |info| info.status = LspStatus::Running) {
+        emit_status(&app, info);
+    }
+
+    // Writes go through their own task so a large message cannot hold up reading.
+    let mut writer = channel.make_writer();
+    let writer_task = tauri::async_runtime::spawn(async move {
+        while let Some(framed) = incoming.recv().await {
+            if writer.write_all(&framed).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let editor = editor_prefix(&info.connection_id);
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut exit_code = None;
+    let mut error = None;
+    loop {
+        tokio::select! {
+            _ = stop_requested(&mut stopped) => {
+                // Closing the channel does not stop a command without a PTY. The `signal` request
+                // covers a server whose PID line has not arrived yet, where it is honoured.
+                match pid.pid() {
+                    Some(pid) => exec::kill_process_group(&app, &info.connection_id, pid).await,
+                    None => {
+                        let _ = channel.signal(Sig::TERM).await;
+                    }
+                }
+                let _ = channel.close().await;
+                break;
+            }
+            msg = channel.wait() => match msg {
+                Some(ChannelMsg::Data { data }) => {
+                    let (preamble, data) = pid.scan_parts(&data);
+                    log_output(&app, &info.id, &preamble);
+                    buffer.extend_from_slice(&data);
+                    let messages = match take_messages(&mut buffer) {
+                        Ok(messages) => messages,
+                        Err(e) => {
+                            error = Some(format!("Invalid output from server: {}", e));
+                            let _ = channel.close().await;
+                            break;
+                        }
+                    };
+                    for body in messages {
+                        let Ok(mut message) = serde_json::from_slice::<Value>(&body) else {
+                            continue;
+                        };
+                        rewrite_uris(&mut message, "file://", &editor);
+                        relay(&app, &info.id, message);
+                    }
+                }
+                Some(ChannelMsg::ExtendedData { data, .. }) => log_output(&app, &info.id, &data),
+                Some(ChannelMsg::ExitStatus { exit_status }) => exit_code = Some(exit_status),
+                Some(ChannelMsg::Failure) => {
+                    error = Some("The server refused to run the command".to_string());
+                    break;
+                }
+                Some(ChannelMsg::Close) => break,
+                None => {
+                    error = Some("Connection closed".to_string());
+                    break;
+                }
+                _ => {}
+            },
+        }
+    }
+    writer_task.abort();
+    log_output(&app, &info.id, &pid.finish());
+    finish(&app, &info.id, generation, exit_code, error);
+}
+
+/// Emit text the server (or its login shell) wrote outside the LSP stream.
+fn log_output(app: &AppHandle, id: &str, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+    let event = LspLogEvent {
+        server_id: id.to_string(),
+        text: String::from_utf8_lossy(data).into_owned(),
+    };
+    if let Err(e) = app.emit("lsp_log", event) {
+        log::error!("Failed to emit LSP log: {}", e);
+    }
+}
+
+/// Emit a server message, first settling the pending request it answers.
+fn relay(app: &AppHandle, id: &str, message: Value) {
+    let is_response = message.get("method").is_none() && message.get("id").is_some();
+    if is_response {
+        let key = id_key(&message["id"]);
+        if let Some(entry) = registry().lock().unwrap_or_else(|e| e.into_inner()).get_mut(id) {
+            entry.pending.remove(&key);
+        }
+    }
+    let event = LspMessageEvent {
+        server_id: id.to_string(),
+        message,
+    };
+    if let Err(e) = app.emit("lsp_message", event) {
+        log::error!("Failed to emit LSP message: {}", e);
+    }
+}
+
+/// Apply `change` to the server's info if `generation` is still its current run.
+fn update(id: &str, generation: u64, change: impl FnOnce(&mut LspInfo)) -> Option<LspInfo> {
+    let mut servers = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let entry = servers.get_mut(id).filter(|entry| entry.generation == generation)?;
+    change(&mut entry.info);
+    Some(entry.info.clone())
+}
+
+/// Record the end of a run and fail the requests it left unanswered.
+fn finish(app: &AppHandle, id: &str, generation: u64, exit_code: Option<u32>, error: Option<String>) {
+    let (info, pending) = {
+        let mut servers = registry().lock().unwrap_or_else(|e| e.into_inner());
+        let Some(entry) = servers.get_mut(id).filter(|entry| entry.generation == generation) else {
+            return;
+        };
+        entry.outgoing = None;
+        entry.info.status = LspStatus::Exited;
+        entry.info.exit_code = exit_code;
+        entry.info.error = error;
+        (entry.info.clone(), std::mem::take(&mut entry.pending))
+    };
+
+    for request_id in pending.into_values() {
+        relay(
+            app,
+            id,
+            json!({
+                "jsonrpc": "2.0",
+                "id": request_id,
+                "error": { "code": REQUEST_FAILED, "message": "Language server exited" },
+            }),
+        );
+    }
+    emit_trace(
+        app,
+        TraceEvent::new("lsp", "exited", "Language server exited")
+            .with_correlation_id(id)
+            .with_detail(format!("exit {:?}, {}", info.exit_code, info.error.as_deref().unwrap_or("no error"))),
+    );
+    emit_status(app, info);
+}
+
+/// Send a JSON-RPC message to the server, rewriting editor URIs to `file://` ones.
+pub async fn send(id: &str, mut message: Value) -> Result<(), LspError> {
+    let mut servers = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let entry = servers.get_mut(id).ok_or(LspError::NotFound)?;
+    let outgoing = entry.outgoing.as_ref().ok_or(LspError::NotRunning)?;
+    rewrite_uris(&mut message, &editor_prefix(&entry.info.connection_id), "file://");
+    if let (Some(request_id), Some(_)) = (message.get("id"), message.get("method")) {
+        entry.pending.insert(id_key(request_id), request_id.clone());
+    }
+    outgoing.send(frame(&message)).map_err(|_| LspError::NotRunning)
+}
+
+/// Start an exited server again (or restart a running one).
+pub async fn restart(app: AppHandle, id: &str) -> Result<LspInfo, LspError> {
+    {
+        let mut servers = registry().lock().unwrap_or_else(|e| e.into_inner());
+        let entry = servers.get_mut(id).ok_or(LspError::NotFound)?;
+        entry.info.restarts += 1;
+    }
+    launch(app, id);
+    list(None)
+        .into_iter()
+        .find(|info| info.id == id)
+        .ok_or(LspError::NotFound)
+}
+
+/// Restart every server of a connection on its new session after `ssh_reconnect`.
+pub async fn restart_for_connection(app: &AppHandle, connection_id: &str) -> usize {
+    let ids: Vec<String> = list(Some(connection_id)).into_iter().map(|info| info.id).collect();
+    for id in &ids {
+        let _ = restart(app.clone(), id).await;
+    }
+    ids.len()
+}
+
+/// Servers, optionally only those of one connection.
+pub fn list(connection_id: Option<&str>) -> Vec<LspInfo> {
+    let servers = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let mut infos: Vec<LspInfo> = servers
+        .values()
+        .filter(|entry| connection_id.map_or(true, |id| entry.info.connection_id == id))
+        .map(|entry| entry.info.clone())
+        .collect();
+    infos.sort_by_key(|info| info.created_at);
+    infos
+}
+
+/// Stop a server and forget it; returns its last state, or `None` if it does not exist.
+pub fn stop(id: &str) -> Option<LspInfo> {
+    let entry = registry().lock().unwrap_or_else(|e| e.into_inner()).remove(id)?;
+    entry.stop.send_replace(true);
+    Some(entry.info)
+}
+
+/// Stop every server of a connection (on explicit disconnect).
+pub fn stop_for_connection(connection_id: &str) {
+    let mut servers = registry().lock().unwrap_or_else(|e| e.into_inner());
+    servers.retain(|_, entry| {
+        if entry.info.connection_id == connection_id {
+            entry.stop.send_replace(true);
+            false
+        } else {
+            true
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_uri_fields_only() {
+        let mut message = json!({
+            "textDocument": { "uri": "file:///src/main.rs" },
+            "contentChanges": [{ "text": "let url = \"file:///etc/hosts\";" }],
+            "data": { "path": "file:///kept" },
+        });
+        rewrite_uris(&mut message, "file://", "driftcode://c1");
+        assert_eq!(message["textDocument"]["uri"], "driftcode://c1/src/main.rs");
+        assert_eq!(message["contentChanges"][0]["text"], "let url = \"file:///etc/hosts\";");
+        assert_eq!(message["data"]["path"], "file:///kept");
+    }
+
+    #[test]
+    fn rewrites_locations_and_workspace_folders() {
+        let mut message = json!({
+            "rootUri": "file:///project",
+            "workspaceFolders": [{ "uri": "file:///project", "name": "project" }],
+            "result": [{ "targetUri": "file:///project/lib.rs", "originSelectionRange": null }],
+        });
+        rewrite_uris(&mut message, "file://", "driftcode://c1");
+        assert_eq!(message["rootUri"], "driftcode://c1/project");
+        assert_eq!(message["workspaceFolders"][0]["uri"], "driftcode://c1/project");
+        assert_eq!(message["workspaceFolders"][0]["name"], "project");
+        assert_eq!(message["result"][0]["targetUri"], "driftcode://c1/project/lib.rs");
+    }
+
+    #[test]
+    fn rewrites_workspace_edit_keys() {
+        let mut message = json!({
+            "edit": {
+                "changes": { "driftcode://c1/a.rs": [{ "newText": "driftcode://c1/b.rs" }] },
+                "documentChanges": [{ "kind": "rename", "oldUri": "driftcode://c1/a.rs", "newUri": "driftcode://c1/b.rs" }],
+            }
+        });
+        rewrite_uris(&mut message, "driftcode://c1", "file://");
+        let edit = &message["edit"];
+        assert_eq!(edit["changes"]["file:///a.rs"][0]["newText"], "driftcode://c1/b.rs");
+        assert_eq!(edit["documentChanges"][0]["oldUri"], "file:///a.rs");
+        assert_eq!(edit["documentChanges"][0]["newUri"], "file:///b.rs");
+    }
+
+    #[test]
+    fn takes_framed_messages() {
+        let mut buffer = b"Content-Length: 2\r\n\r\n{}Content-Length: 7\r\n\r\n{\"a\"".to_vec();
+        assert_eq!(take_messages(&mut buffer).unwrap(), vec![b"{}".to_vec()]);
+        buffer.extend_from_slice(b":1}");
+        assert_eq!(take_messages(&mut buffer).unwrap(), vec![b"{\"a\":1}".to_vec()]);
+        assert!(buffer.is_empty());
+    }
+}
@@ -1,6 +1,19 @@
 pub mod auth;
 pub mod actor;
 pub mod client;
+pub mod config;
+pub mod exec;
+pub mod follow;
+pub mod forward;
+pub mod git;
+pub mod keyboard_interactive;
 pub mod known_hosts;
+pub mod lsp;
+pub mod openssh_known_hosts;
 pub mod pty;
+pub mod search;
 pub mod sftp;
+pub mod tasks;
+pub mod transfer;
+pub mod tree;
+pub mod watch;
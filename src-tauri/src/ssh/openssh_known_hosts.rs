@@ -0,0 +1,293 @@
+//! Reader/writer for OpenSSH `known_hosts` files.
+//!
+//! Handles plain and hashed (`|1|salt|hmac`) host fields, `[host]:port` names, comma-separated
+//! aliases and negated patterns, and the `@cert-authority` / `@revoked` markers.
+
+use crate::ssh::config::match_pattern_list;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use ssh_key::PublicKey;
+
+type HmacSha1 = Hmac<Sha1>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Marker {
+    CertAuthority,
+    Revoked,
+}
+
+#[derive(Debug, Clone)]
+pub enum HostField {
+    /// Comma-separated names/patterns (`host`, `[host]:2222`, `*.example.com`, `!bad.example.com`).
+    Patterns(Vec<String>),
+    /// `|1|<salt>|<HMAC-SHA1(salt, name)>` as written with `HashKnownHosts yes`.
+    Hashed { salt: Vec<u8>, hash: Vec<u8> },
+}
+
+#[derive(Debug, Clone)]
+pub struct KnownHostsLine {
+    /// 1-based line number in the source file
+    pub line: usize,
+    pub marker: Option<Marker>,
+    pub hosts: HostField,
+    pub key: PublicKey,
+}
+
+/// A line we could not use, with the reason.
+#[derive(Debug, Clone)]
+pub struct SkippedLine {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Name OpenSSH uses for `host:port` in known_hosts: `host` on port 22, else `[host]:port`.
+pub fn host_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.trim().to_string()
+    } else {
+        format!("[{}]:{}", host.trim(), port)
+    }
+}
+
+/// Split a known_hosts name back into host and port (`[host]:port` or bare `host`).
+pub fn split_host_name(name: &str) -> (String, u16) {
+    if let Some(rest) = name.strip_prefix('[') {
+        if let Some((host, port)) = rest.split_once("]:") {
+            if let Ok(port) = port.parse() {
+                return (host.to_string(), port);
+            }
+        }
+    }
+    (name.to_string(), 22)
+}
+
+/// `|1|salt|hash` for `name`, with a fresh random salt. OpenSSH hashes the lowercased name.
+pub fn hash_host_name(name: &str) -> String {
+    let salt: [u8; 20] = rand::random();
+    format!(
+        "|1|{}|{}",
+        BASE64.encode(salt),
+        BASE64.encode(hmac_sha1(&salt, &name.to_ascii_lowercase()))
+    )
+}
+
+fn hmac_sha1(salt: &[u8], name: &str) -> Vec<u8> {
+    let mut mac = HmacSha1::new_from_slice(salt).expect("HMAC accepts any key length");
+    mac.update(name.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+impl HostField {
+    fn parse(field: &str) -> Result<Self, String> {
+        if let Some(rest) = field.strip_prefix("|1|") {
+            let (salt, hash) = rest
+                .split_once('|')
+                .ok_or_else(|| "malformed hashed hostname".to_string())?;
+            let salt = BASE64.decode(salt).map_err(|e| format!("bad hostname salt: {}", e))?;
+            let hash = BASE64.decode(hash).map_err(|e| format!("bad hostname hash: {}", e))?;
+            return Ok(HostField::Hashed { salt, hash });
+        }
+        if field.starts_with('|') {
+            return Err("unsupported hostname hash format".to_string());
+        }
+        Ok(HostField::Patterns(
+            field
+                .split(',')
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ))
+    }
+
+    /// Whether this field covers `host:port`. Names compare case-insensitively, as in OpenSSH.
+    pub fn matches(&self, host: &str, port: u16) -> bool {
+        let name = host_name(&host.to_ascii_lowercase(), port);
+        match self {
+            HostField::Patterns(patterns) => match_pattern_list(&name, patterns),
+            HostField::Hashed { salt, hash } => hmac_sha1(salt, &name) == *hash,
+        }
+    }
+
+    /// Concrete host names (no wildcards or negations), as `(host, port)`.
+    pub fn literal_hosts(&self) -> Vec<(String, u16)> {
+        match self {
+            HostField::Patterns(patterns) => patterns
+                .iter()
+                .filter(|p| !p.starts_with('!') && !p.contains(['*', '?']))
+                .map(|p| split_host_name(p))
+                .collect(),
+            HostField::Hashed { .. } => Vec::new(),
+        }
+    }
+}
+
+/// Parse a known_hosts file. Unusable lines are reported rather than failing the whole file.
+pub fn parse(text: &str) -> (Vec<KnownHostsLine>, Vec<SkippedLine>) {
+    let mut lines = Vec::new();
+    let mut skipped = Vec::new();
+
+    for (idx, raw) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        match parse_line(trimmed) {
+            Ok((marker, hosts, key)) => lines.push(KnownHostsLine {
+                line: line_no,
+                marker,
+                hosts,
+                key,
+            }),
+            Err(reason) => skipped.push(SkippedLine { line: line_no, reason }),
+        }
+    }
+
+    (lines, skipped)
+}
+
+fn parse_line(line: &str) -> Result<(Option<Marker>, HostField, PublicKey), String> {
+    let mut fields = line.split_whitespace();
+    let mut first = fields.next().ok_or_else(|| "empty line".to_string())?;
+
+    let marker = match first {
+        "@cert-authority" => Some(Marker::CertAuthority),
+        "@revoked" => Some(Marker::Revoked),
+        m if m.starts_with('@') => return Err(format!("unknown marker {}", m)),
+        _ => None,
+    };
+    if marker.is_some() {
+        first = fields.next().ok_or_else(|| "missing host field".to_string())?;
+    }
+
+    let hosts = HostField::parse(first)?;
+    let key_type = fields.next().ok_or_else(|| "missing key type".to_string())?;
+    let key_data = fields.next().ok_or_else(|| "missing key data".to_string())?;
+    let key = PublicKey::from_openssh(&format!("{} {}", key_type, key_data))
+        .map_err(|e| format!("unsupported or invalid {} key: {}", key_type, e))?;
+
+    Ok((marker, hosts, key))
+}
+
+/// Format one known_hosts line. `names` are already-formatted host names or patterns.
+pub fn format_line(marker: Option<Marker>, names: &[String], key: &PublicKey, hash: bool) -> Option<String> {
+    let key = key.to_openssh().ok()?;
+    // Patterns cannot be hashed; OpenSSH hashes each concrete name on its own line.
+    let host_field = if hash && names.len() == 1 && !names[0].contains(['*', '?', '!']) {
+        hash_host_name(&names[0])
+    } else {
+        names.join(",")
+    };
+    let prefix = match marker {
+        Some(Marker::CertAuthority) => "@cert-authority ",
+        Some(Marker::Revoked) => "@revoked ",
+        None => "",
+    };
+    Some(format!("{}{} {}", prefix, host_field, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIBlegA+uZadBCQpTwU1RYUPbGykhWOkw/OL1/rOHF5Ao";
+    const KEY_B: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIPdb3ir1BnwFAHzk+HsyIb7sHWrrPqwmq0XCc2BsWKc8";
+
+    fn parse_one(line: &str) -> KnownHostsLine {
+        let (mut lines, skipped) = parse(line);
+        assert!(skipped.is_empty(), "{:?}", skipped);
+        assert_eq!(lines.len(), 1);
+        lines.remove(0)
+    }
+
+    #[test]
+    fn plain_names_and_aliases() {
+        let line = parse_one(&format!("web.example.com,10.0.0.5 {}", KEY_A));
+        assert_eq!(line.marker, None);
+        assert!(line.hosts.matches("web.example.com", 22));
+        assert!(line.hosts.matches("WEB.Example.com", 22));
+        assert!(line.hosts.matches("10.0.0.5", 22));
+        assert!(!line.hosts.matches("web.example.com", 2222));
+        assert!(!line.hosts.matches("db.example.com", 22));
+        assert_eq!(
+            line.hosts.literal_hosts(),
+            vec![("web.example.com".to_string(), 22), ("10.0.0.5".to_string(), 22)]
+        );
+    }
+
+    #[test]
+    fn hashed_names_are_lowercased() {
+        let salt = [7u8; 20];
+        let hashed = format!(
+            "|1|{}|{}",
+            BASE64.encode(salt),
+            BASE64.encode(hmac_sha1(&salt, "web.example.com"))
+        );
+        let line = parse_one(&format!("{} {}", hashed, KEY_A));
+        assert!(matches!(line.hosts, HostField::Hashed { .. }));
+        assert!(line.hosts.matches("web.example.com", 22));
+        assert!(line.hosts.matches("Web.Example.COM", 22));
+        assert!(!line.hosts.matches("web.example.com", 2222));
+        assert!(line.hosts.literal_hosts().is_empty());
+
+        let written = parse_one(&format!("{} {}", hash_host_name("[Web.Example.com]:2222"), KEY_A));
+        assert!(written.hosts.matches("web.example.com", 2222));
+    }
+
+    #[test]
+    fn wildcard_patterns() {
+        let line = parse_one(&format!("*.example.com,db-?.internal {}", KEY_A));
+        assert!(line.hosts.matches("web.example.com", 22));
+        assert!(line.hosts.matches("db-1.internal", 22));
+        assert!(!line.hosts.matches("db-10.internal", 22));
+        assert!(!line.hosts.matches("example.org", 22));
+        assert!(line.hosts.literal_hosts().is_empty());
+    }
+
+    #[test]
+    fn bracketed_host_and_port() {
+        let line = parse_one(&format!("[git.example.com]:2222 {}", KEY_A));
+        assert!(line.hosts.matches("git.example.com", 2222));
+        assert!(!line.hosts.matches("git.example.com", 22));
+        assert_eq!(line.hosts.literal_hosts(), vec![("git.example.com".to_string(), 2222)]);
+        assert_eq!(host_name("git.example.com", 2222), "[git.example.com]:2222");
+        assert_eq!(host_name("git.example.com", 22), "git.example.com");
+        assert_eq!(split_host_name("[::1]:2200"), ("::1".to_string(), 2200));
+    }
+
+    #[test]
+    fn markers() {
+        let revoked = parse_one(&format!("@revoked * {}", KEY_B));
+        assert_eq!(revoked.marker, Some(Marker::Revoked));
+        assert!(revoked.hosts.matches("anything.example.com", 22));
+
+        let ca = parse_one(&format!("@cert-authority *.example.com {}", KEY_A));
+        assert_eq!(ca.marker, Some(Marker::CertAuthority));
+
+        let (lines, skipped) = parse(&format!("@bogus host {}\n# comment\n\nhost ssh-ed25519\n", KEY_A));
+        assert!(lines.is_empty());
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].line, 1);
+        assert_eq!(skipped[1].line, 4);
+
+        let key = PublicKey::from_openssh(KEY_B).unwrap();
+        assert_eq!(
+            format_line(Some(Marker::Revoked), &["*".to_string()], &key, true).unwrap(),
+            format!("@revoked * {}", KEY_B)
+        );
+    }
+
+    #[test]
+    fn negated_patterns() {
+        let line = parse_one(&format!("*.example.com,!bastion.example.com {}", KEY_A));
+        assert!(line.hosts.matches("web.example.com", 22));
+        assert!(!line.hosts.matches("bastion.example.com", 22));
+        assert!(!line.hosts.matches("Bastion.Example.com", 22));
+        // A negation alone never matches.
+        let only_negated = parse_one(&format!("!web.example.com {}", KEY_A));
+        assert!(!only_negated.hosts.matches("db.example.com", 22));
+    }
+}
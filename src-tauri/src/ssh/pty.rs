@@ -5,7 +5,7 @@ use tokio::io::AsyncWriteExt;
 use tokio::sync::mpsc;
 
 /// Escape a path for use in shell commands
-fn shell_escape(s: &str) -> String {
+pub(crate) fn shell_escape(s: &str) -> String {
     // Wrap in single quotes and escape any single quotes in the string
     format!("'{}'", s.replace('\'', "'\\''"))
 }
@@ -0,0 +1,747 @@
+//! Project-wide content search on the server.
+//!
+//! A search runs `rg --json` over an exec channel, or `grep -rn` when ripgrep is not installed.
+//! Where neither can run (no exec, e.g. an SFTP-only account), files are scanned over a dedicated
+//! SFTP channel instead. Include/exclude globs are applied here to every engine's results, so they
+//! mean the same thing whichever one ran. Matches stream out in batches as `search_results`
+//! events, followed by one `search_done`.
+//!
+//! Every engine searches hidden and VCS-ignored files alike, skipping only `.git`. Regex queries
+//! are validated as Rust `regex` syntax, which ripgrep and the SFTP scan use as is; grep runs them
+//! with `-P` (PCRE) where it supports that and falls back to `-E` (POSIX ERE, no `\d`, lookaround
+//! or lazy quantifiers) otherwise. The engine that ran is reported in [`SearchInfo::engine`].
+
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::SshError;
+use crate::ssh::pty::shell_escape;
+use crate::ssh::transfer::{is_eof, list_remote_dir};
+use crate::state::AppState;
+use crate::trace::{emit_trace, TraceEvent};
+use glob::Pattern;
+use regex::{Regex, RegexBuilder};
+use russh::ChannelMsg;
+use russh_sftp::client::RawSftpSession;
+use russh_sftp::protocol::{FileAttributes, FileType, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tokio::sync::{oneshot, watch, Mutex};
+use uuid::Uuid;
+
+const DEFAULT_MAX_RESULTS: usize = 2000;
+/// Longest preview sent per match, in characters
+const PREVIEW_LIMIT: usize = 500;
+const BATCH_SIZE: usize = 200;
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+/// Output lines longer than this are dropped rather than buffered.
+const MAX_LINE: usize = 1024 * 1024;
+const STDERR_LIMIT: usize = 2048;
+/// The SFTP scan skips larger files.
+const MAX_SCAN_FILE_SIZE: u64 = 1024 * 1024;
+const SCAN_CHUNK: u32 = 32 * 1024;
+const SCAN_REQUEST_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error("Connection not found")]
+    ConnectionNotFound,
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error("Invalid search pattern: {0}")]
+    InvalidPattern(String),
+    #[error("Search cancelled")]
+    Cancelled,
+    #[error("Search failed: {0}")]
+    Failed(String),
+    #[error(transparent)]
+    Ssh(#[from] SshError),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchOptions {
+    pub query: String,
+    /// Treat `query` as a regular expression rather than a literal string
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Globs a file must match one of. Without a `/` a glob matches the file name, with one the
+    /// path relative to the search root.
+    pub include: Vec<String>,
+    /// Globs for files and directories to skip; without a `/` they match any path component.
+    pub exclude: Vec<String>,
+    pub max_results: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            regex: false,
+            case_sensitive: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_results: DEFAULT_MAX_RESULTS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEngine {
+    Ripgrep,
+    /// `grep -P`: Perl-compatible regexes
+    GrepPcre,
+    /// `grep -E` (or `-F` for literals): POSIX extended regexes
+    Grep,
+    Sftp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchInfo {
+    pub id: String,
+    pub connection_id: String,
+    pub root: String,
+    pub options: SearchOptions,
+    pub status: SearchStatus,
+    pub engine: Option<SearchEngine>,
+    pub match_count: usize,
+    /// Stopped at `maxResults`
+    pub truncated: bool,
+    pub created_at: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    pub path: String,
+    /// 1-based
+    pub line: u64,
+    /// 1-based, in characters
+    pub column: u64,
+    /// Length of the match in characters
+    pub match_length: u64,
+    /// The matching line without its line break, cut to 500 characters
+    pub preview: String,
+}
+
+/// Payload of `search_results`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResultsEvent {
+    pub search_id: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+struct SearchEntry {
+    info: SearchInfo,
+    cancel: watch::Sender<bool>,
+}
+
+static SEARCHES: OnceLock<StdMutex<HashMap<String, SearchEntry>>> = OnceLock::new();
+
+fn registry() -> &'static StdMutex<HashMap<String, SearchEntry>> {
+    SEARCHES.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Send a request to the connection's current actor and wait for its reply.
+async fn actor_request<T>(
+    app: &AppHandle,
+    connection_id: &str,
+    request: impl FnOnce(oneshot::Sender<Result<T, SshError>>) -> ConnectionRequest,
+) -> Result<T, SearchError> {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    }
+    .ok_or(SearchError::ConnectionNotFound)?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| SearchError::ConnectionClosed)?;
+    Ok(rx.await.map_err(|_| SearchError::ConnectionClosed)??)
+}
+
+/// Resolves once the search is cancelled. Wraps `wait_for`, whose guard is not `Send`.
+async fn cancel_requested(cancel: &mut watch::Receiver<bool>) {
+    let _ = cancel.wait_for(|c| *c).await;
+}
+
+/// Include/exclude globs, matched against paths relative to the search root.
+struct PathFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl PathFilter {
+    /// Fails with a message naming the invalid glob.
+    fn new(options: &SearchOptions) -> Result<Self, String> {
+        let compile = |globs: &[String]| -> Result<Vec<Pattern>, String> {
+            globs
+                .iter()
+                .filter(|g| !g.is_empty())
+                .map(|g| Pattern::new(g.trim_matches('/')).map_err(|e| format!("{}: {}", g, e)))
+                .collect()
+        };
+        Ok(Self {
+            include: compile(&options.include)?,
+            exclude: compile(&options.exclude)?,
+        })
+    }
+
+    fn excludes(&self, rel: &str) -> bool {
+        self.exclude.iter().any(|pattern| {
+            if pattern.as_str().contains('/') {
+                // The path itself or one of its parent directories
+                let mut prefix = rel;
+                loop {
+                    if pattern.matches(prefix) {
+                        return true;
+                    }
+                    match prefix.rsplit_once('/') {
+                        Some((parent, _)) => prefix = parent,
+                        None => return false,
+                    }
+                }
+            } else {
+                rel.split('/').any(|component| pattern.matches(component))
+            }
+        })
+    }
+
+    fn allows_file(&self, rel: &str) -> bool {
+        if self.excludes(rel) {
+            return false;
+        }
+        let name = rel.rsplit('/').next().unwrap_or(rel);
+        self.include.is_empty()
+            || self.include.iter().any(|pattern| {
+                if pattern.as_str().contains('/') {
+                    pattern.matches(rel)
+                } else {
+                    pattern.matches(name)
+                }
+            })
+    }
+}
+
+/// Collects matches, enforces `max_results` and sends them out in batches.
+struct Sink {
+    app: AppHandle,
+    search_id: String,
+    max_results: usize,
+    batch: Vec<SearchMatch>,
+    last_flush: Instant,
+    count: usize,
+    truncated: bool,
+}
+
+impl Sink {
+    /// Queue a match; returns `false` once `max_results` is reached and the search should stop.
+    fn push(&mut self, found: SearchMatch) -> bool {
+        if self.count >= self.max_results {
+            self.truncated = true;
+            return false;
+        }
+        self.batch.push(found);
+        self.count += 1;
+        if self.batch.len() >= BATCH_SIZE || self.last_flush.elapsed() >= BATCH_INTERVAL {
+            self.flush();
+        }
+        true
+    }
+
+    fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        if self.batch.is_empty() {
+            return;
+        }
+        let event = SearchResultsEvent {
+            search_id: self.search_id.clone(),
+            matches: std::mem::take(&mut self.batch),
+        };
+        if let Err(e) = self.app.emit("search_results", event) {
+            log::error!("Failed to emit search results: {}", e);
+        }
+        if let Some(entry) = registry().lock().unwrap_or_else(|e| e.into_inner()).get_mut(&self.search_id) {
+            entry.info.match_count = self.count;
+        }
+    }
+}
+
+/// Everything a running search needs besides the sink.
+struct Search {
+    app: AppHandle,
+    connection_id: String,
+    root: String,
+    regex: Regex,
+    filter: PathFilter,
+    options: SearchOptions,
+}
+
+impl Search {
+    fn relative<'a>(&self, path: &'a str) -> &'a str {
+        path.strip_prefix(self.root.as_str())
+            .map(|rel| rel.trim_start_matches('/'))
+            .unwrap_or(path)
+    }
+
+    /// Build a match for `text` (one line, without its line break) from a byte range within it,
+    /// or from the first match of the pattern when the engine does not report one.
+    fn to_match(&self, path: &str, line: u64, text: &str, range: Option<(usize, usize)>) -> SearchMatch {
+        let text = text.trim_end_matches(['\r', '\n']);
+        let (start, end) = range
+            .filter(|&(start, end)| start <= end && end <= text.len() && text.is_char_boundary(start) && text.is_char_boundary(end))
+            .or_else(|| self.regex.find(text).map(|m| (m.start(), m.end())))
+            .unwrap_or((0, 0));
+        SearchMatch {
+            path: path.to_string(),
+            line,
+            column: text[..start].chars().count() as u64 + 1,
+            match_length: text[start..end].chars().count() as u64,
+            preview: text.chars().take(PREVIEW_LIMIT).collect(),
+        }
+    }
+
+    fn rg_command(&self) -> String {
+        let mut args = vec![
+            "rg".to_string(),
+            "--json".to_string(),
+            "--no-messages".to_string(),
+            "--max-columns".to_string(),
+            "1000".to_string(),
+            "--max-columns-preview".to_string(),
+            // Search what grep and the SFTP scan search: hidden and ignored files, but not `.git`.
+            "--hidden".to_string(),
+            "--no-ignore".to_string(),
+            "--glob='!.git'".to_string(),
+        ];
+        if !self.options.regex {
+            args.push("--fixed-strings".to_string());
+        }
+        args.push(if self.options.case_sensitive { "--case-sensitive" } else { "--ignore-case" }.to_string());
+        for glob in &self.options.include {
+            args.push(format!("--glob={}", shell_escape(glob)));
+        }
+        for glob in &self.options.exclude {
+            args.push(format!("--glob={}", shell_escape(&format!("!{}", glob))));
+        }
+        args.push(format!("-e {}", shell_escape(&self.options.query)));
+        args.push(format!("-- {}", shell_escape(&self.root)));
+        args.join(" ")
+    }
+
+    /// `--include`/`--exclude` only see file names, so path globs are left to [`PathFilter`].
+    ///
+    /// With `pcre`, a grep built without `-P` support exits 127 before searching, so the caller
+    /// falls back to the `-E` command.
+    fn grep_command(&self, pcre: bool) -> String {
+        let mut args = vec!["grep -rnIH --null --exclude-dir=.git".to_string()];
+        let syntax = match (self.options.regex, pcre) {
+            (false, _) => "-F",
+            (true, true) => "-P",
+            (true, false) => "-E",
+        };
+        args.push(syntax.to_string());
+        if !self.options.case_sensitive {
+            args.push("-i".to_string());
+        }
+        for glob in self.options.include.iter().filter(|g| !g.contains('/')) {
+            args.push(format!("--include={}", shell_escape(glob)));
+        }
+        for glob in self.options.exclude.iter().filter(|g| !g.contains('/')) {
+            args.push(format!("--exclude={0} --exclude-dir={0}", shell_escape(glob)));
+        }
+        args.push(format!("-e {}", shell_escape(&self.options.query)));
+        args.push(format!("-- {}", shell_escape(&self.root)));
+        let command = args.join(" ");
+        if !pcre {
+            return command;
+        }
+        // `grep -P` on an empty file exits 1 (no match) when supported and 2 when not.
+        let script = format!(
+            "grep -qP '' /dev/null 2>/dev/null; [ $? -le 1 ] || exit 127; exec {}",
+            command
+        );
+        format!("sh -c {}", shell_escape(&script))
+    }
+
+    /// One line of `rg --json` output; only `match` messages carry results.
+    fn parse_rg(&self, line: &str) -> Option<SearchMatch> {
+        let message: serde_json::Value = serde_json::from_str(line).ok()?;
+        if message.get("type")?.as_str()? != "match" {
+            return None;
+        }
+        let data = message.get("data")?;
+        // Paths and lines that are not UTF-8 come as base64 `bytes`, and are skipped.
+        let path = data.get("path")?.get("text")?.as_str()?;
+        let text = data.get("lines")?.get("text")?.as_str()?;
+        let line_number = data.get("line_number")?.as_u64()?;
+        let range = data
+            .get("submatches")
+            .and_then(|s| s.get(0))
+            .and_then(|s| Some((s.get("start")?.as_u64()? as usize, s.get("end")?.as_u64()? as usize)));
+        Some(self.to_match(path, line_number, text, range))
+    }
+
+    /// One line of `grep --null -n` output: `path\0line:text`.
+    fn parse_grep(&self, line: &str) -> Option<SearchMatch> {
+        let (path, rest) = line.split_once('\0')?;
+        let (line_number, text) = rest.split_once(':')?;
+        Some(self.to_match(path, line_number.parse().ok()?, text, None))
+    }
+}
+
+enum EngineOutcome {
+    /// The engine ran (to completion, or until `max_results`)
+    Done,
+    /// Not installed or exec not allowed; try the next engine
+    Unavailable,
+}
+
+async fn run_exec_engine(
+    search: &Search,
+    engine: SearchEngine,
+    sink: &mut Sink,
+    cancel: &mut watch::Receiver<bool>,
+) -> Result<EngineOutcome, SearchError> {
+    let command = match engine {
+        SearchEngine::Ripgrep => search.rg_command(),
+        SearchEngine::GrepPcre => search.grep_command(true),
+        _ => search.grep_command(false),
+    };
+    let opened = actor_request(&search.app, &search.connection_id, |respond_to| ConnectionRequest::OpenExecChannel {
+        command,
+        respond_to,
+    })
+    .await;
+    let mut channel = match opened {
+        Ok(channel) => channel,
+        Err(SearchError::Ssh(SshError::ChannelOpenFailed(_))) => return Ok(EngineOutcome::Unavailable),
+        Err(e) => return Err(e),
+    };
+    let _ = channel.eof().await;
+
+    let mut stdout: Vec<u8> = Vec::new();
+    let mut skipping_long_line = false;
+    let mut stderr: Vec<u8> = Vec::new();
+    let mut exit_status = None;
+    let mut found = 0usize;
+    let mut ticker = tokio::time::interval(BATCH_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = cancel_requested(cancel) => {
+                let _ = channel.close().await;
+                return Err(SearchError::Cancelled);
+            }
+            _ = ticker.tick() => sink.flush(),
+            msg = channel.wait() => match msg {
+                Some(ChannelMsg::Data { data }) => {
+                    stdout.extend_from_slice(&data);
+                    while let Some(newline) = stdout.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = stdout.drain(..=newline).collect();
+                        if std::mem::take(&mut skipping_long_line) {
+                            continue;
+                        }
+                        let line = String::from_utf8_lossy(&line[..newline]);
+                        let parsed = match engine {
+                            SearchEngine::Ripgrep => search.parse_rg(&line),
+                            _ => search.parse_grep(&line),
+                        };
+                        let Some(parsed) = parsed else { continue };
+                        if !search.filter.allows_file(search.relative(&parsed.path)) {
+                            continue;
+                        }
+                        found += 1;
+                        if !sink.push(parsed) {
+                            let _ = channel.close().await;
+                            return Ok(EngineOutcome::Done);
+                        }
+                    }
+                    if stdout.len() > MAX_LINE {
+                        stdout.clear();
+                        skipping_long_line = true;
+                    }
+                }
+                Some(ChannelMsg::ExtendedData { data, .. }) => {
+                    let remaining = STDERR_LIMIT.saturating_sub(stderr.len());
+                    stderr.extend_from_slice(&data[..data.len().min(remaining)]);
+                }
+                Some(ChannelMsg::ExitStatus { exit_status: status }) => exit_status = Some(status),
+                Some(ChannelMsg::Failure) => return Ok(EngineOutcome::Unavailable),
+                None | Some(ChannelMsg::Close) => break,
+                _ => {}
+            },
+        }
+    }
+
+    let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+    match exit_status {
+        Some(126 | 127) if found == 0 => Ok(EngineOutcome::Unavailable),
+        // 1 means no match. 2 with results means some files could not be read, which is normal
+        // for a tree; without results and with a message it is a usage or pattern error.
+        Some(2) if found == 0 && !stderr.is_empty() => Err(SearchError::Failed(stderr)),
+        _ => Ok(EngineOutcome::Done),
+    }
+}
+
+/// Read a whole (small) file over the raw session.
+async fn read_remote_file(sftp: &RawSftpSession, path: &str) -> Result<Vec<u8>, SshError> {
+    let handle = sftp
+        .open(path, OpenFlags::READ, FileAttributes::empty())
+        .await
+        .map_err(crate::ssh::client::map_sftp_error)?
+        .handle;
+    let mut data = Vec::new();
+    let read = loop {
+        match sftp.read(handle.as_str(), data.len() as u64, SCAN_CHUNK).await {
+            Ok(chunk) if chunk.data.is_empty() => break Ok(()),
+            Ok(chunk) if data.len() as u64 + chunk.data.len() as u64 > MAX_SCAN_FILE_SIZE => break Ok(()),
+            Ok(chunk) => data.extend_from_slice(&chunk.data),
+            Err(e) if is_eof(&e) => break Ok(()),
+            Err(e) => break Err(crate::ssh::client::map_sftp_error(e)),
+        }
+    };
+    let _ = sftp.close(handle).await;
+    read.map(|()| data)
+}
+
+/// Walk the tree over a dedicated SFTP channel and match each text file line by line. Symlinked
+/// directories and `.git` are not entered; unreadable directories and files are skipped.
+async fn scan_sftp(search: &Search, sink: &mut Sink, cancel: &mut watch::Receiver<bool>) -> Result<(), SearchError> {
+    let sftp = actor_request(&search.app, &search.connection_id, |respond_to| {
+        ConnectionRequest::OpenSftpChannel {
+            timeout_secs: SCAN_REQUEST_TIMEOUT_SECS,
+            respond_to,
+        }
+    })
+    .await?;
+
+    let root_attrs = sftp
+        .stat(search.root.as_str())
+        .await
+        .map_err(crate::ssh::client::map_sftp_error)?
+        .attrs;
+    let mut files: Vec<(String, u64)> = Vec::new();
+    let mut dirs = Vec::new();
+    if root_attrs.file_type() == FileType::Dir {
+        dirs.push(search.root.clone());
+    } else {
+        files.push((search.root.clone(), root_attrs.size.unwrap_or(0)));
+    }
+
+    loop {
+        if *cancel.borrow() {
+            return Err(SearchError::Cancelled);
+        }
+        if let Some((path, size)) = files.pop() {
+            if size > MAX_SCAN_FILE_SIZE {
+                continue;
+            }
+            let Ok(data) = read_remote_file(&sftp, &path).await else { continue };
+            // Same heuristic as grep -I
+            if data[..data.len().min(8192)].contains(&0) {
+                continue;
+            }
+            let text = String::from_utf8_lossy(&data);
+            for (index, line) in text.lines().enumerate() {
+                if let Some(m) = search.regex.find(line) {
+                    if !sink.push(search.to_match(&path, index as u64 + 1, line, Some((m.start(), m.end())))) {
+                        return Ok(());
+                    }
+                }
+            }
+            sink.flush();
+            continue;
+        }
+
+        let Some(dir) = dirs.pop() else { break };
+        let Ok(mut entries) = list_remote_dir(&sftp, &dir).await else {
+            if dir == search.root {
+                return Err(SearchError::Failed(format!("cannot list {}", dir)));
+            }
+            continue;
+        };
+        // Visit in name order: the stacks are popped from the end.
+        entries.sort_by(|a, b| b.0.cmp(&a.0));
+        for (name, attrs) in entries {
+            let path = format!("{}/{}", dir.trim_end_matches('/'), name);
+            let rel = search.relative(&path);
+            match attrs.file_type() {
+                FileType::Dir if name != ".git" && !search.filter.excludes(rel) => dirs.push(path),
+                FileType::File if search.filter.allows_file(rel) => files.push((path, attrs.size.unwrap_or(0))),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Start a search under `root`; results arrive as `search_results` events.
+pub async fn start(
+    app: AppHandle,
+    connection_id: String,
+    root: String,
+    options: SearchOptions,
+) -> Result<SearchInfo, SearchError> {
+    if options.query.is_empty() {
+        return Err(SearchError::InvalidPattern("empty query".to_string()));
+    }
+    let pattern = if options.regex {
+        options.query.clone()
+    } else {
+        regex::escape(&options.query)
+    };
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| SearchError::InvalidPattern(e.to_string()))?;
+    let filter = PathFilter::new(&options).map_err(SearchError::InvalidPattern)?;
+
+    {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state
+            .get_connection_sender(&connection_id)
+            .ok_or(SearchError::ConnectionNotFound)?;
+    }
+
+    let root = match root.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    let info = SearchInfo {
+        id: Uuid::new_v4().to_string(),
+        connection_id: connection_id.clone(),
+        root: root.clone(),
+        options: options.clone(),
+        status: SearchStatus::Running,
+        engine: None,
+        match_count: 0,
+        truncated: false,
+        created_at: now_ms(),
+        error: None,
+    };
+    let (cancel, cancelled) = watch::channel(false);
+    registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(info.id.clone(), SearchEntry { info: info.clone(), cancel });
+
+    emit_trace(
+        &app,
+        TraceEvent::new("search", "start", "Searching")
+            .with_correlation_id(&info.id)
+            .with_detail(format!("{:?} under {} via {}", options.query, root, connection_id)),
+    );
+
+    let sink = Sink {
+        app: app.clone(),
+        search_id: info.id.clone(),
+        max_results: options.max_results,
+        batch: Vec::new(),
+        last_flush: Instant::now(),
+        count: 0,
+        truncated: false,
+    };
+    let search = Search {
+        app,
+        connection_id,
+        root,
+        regex,
+        filter,
+        options,
+    };
+    tauri::async_runtime::spawn(run(search, sink, cancelled));
+    Ok(info)
+}
+
+async fn run(search: Search, mut sink: Sink, mut cancel: watch::Receiver<bool>) {
+    let mut engine = SearchEngine::Ripgrep;
+    let result: Result<(), SearchError> = async {
+        for candidate in [SearchEngine::Ripgrep, SearchEngine::GrepPcre, SearchEngine::Grep] {
+            // `-P` only changes how regexes are read; literals go straight to `grep -F`.
+            if candidate == SearchEngine::GrepPcre && !search.options.regex {
+                continue;
+            }
+            engine = candidate;
+            if let EngineOutcome::Done = run_exec_engine(&search, candidate, &mut sink, &mut cancel).await? {
+                return Ok(());
+            }
+        }
+        engine = SearchEngine::Sftp;
+        scan_sftp(&search, &mut sink, &mut cancel).await
+    }
+    .await;
+    sink.flush();
+
+    let (status, error) = match result {
+        Ok(()) => (SearchStatus::Completed, None),
+        Err(SearchError::Cancelled) => (SearchStatus::Cancelled, None),
+        Err(e) => (SearchStatus::Failed, Some(e.to_string())),
+    };
+    let info = {
+        let mut searches = registry().lock().unwrap_or_else(|e| e.into_inner());
+        let Some(mut entry) = searches.remove(&sink.search_id) else {
+            return;
+        };
+        entry.info.status = status;
+        entry.info.engine = Some(engine);
+        entry.info.match_count = sink.count;
+        entry.info.truncated = sink.truncated;
+        entry.info.error = error;
+        entry.info
+    };
+
+    emit_trace(
+        &search.app,
+        TraceEvent::new("search", "done", &format!("Search {:?}", info.status))
+            .with_correlation_id(&info.id)
+            .with_detail(format!("{} matches via {:?}", info.match_count, engine)),
+    );
+    if let Err(e) = search.app.emit("search_done", info) {
+        log::error!("Failed to emit search done: {}", e);
+    }
+}
+
+/// Cancel a running search; its `search_done` event reports `cancelled`. Returns `false` if the
+/// search does not exist (or already finished).
+pub fn cancel(id: &str) -> bool {
+    let searches = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let Some(entry) = searches.get(id) else {
+        return false;
+    };
+    entry.cancel.send_replace(true);
+    true
+}
+
+/// Cancel every search of a connection (on explicit disconnect).
+pub fn cancel_for_connection(connection_id: &str) {
+    let searches = registry().lock().unwrap_or_else(|e| e.into_inner());
+    for entry in searches.values().filter(|entry| entry.info.connection_id == connection_id) {
+        entry.cancel.send_replace(true);
+    }
+}
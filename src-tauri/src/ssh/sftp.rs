@@ -1,13 +1,78 @@
+use crate::ssh::client::{map_sftp_error, SshError};
+use futures::stream::{self, StreamExt};
+use russh_sftp::client::error::Error as SftpClientError;
+use russh_sftp::client::{RawSftpSession, SftpSession};
+use russh_sftp::extensions::FSYNC;
+use russh_sftp::protocol::{File, FileAttributes, FileType, OpenFlags, Packet, StatusCode};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::watch;
 
 /// Represents a file/directory entry from SFTP
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SftpEntry {
     pub name: String,
+    /// The entry itself is a directory; false for a symlink to one, so walkers do not follow links
     pub is_directory: bool,
+    pub is_symlink: bool,
+    /// A symlink whose target is a directory
+    pub target_is_directory: bool,
     pub size: u64,
     pub mtime: i64,
     pub permissions: Option<String>,
+    /// Type of the entry itself (not of a symlink's target)
+    pub file_type: EntryType,
+    pub symlink_target: Option<String>,
+    /// A symlink whose target does not exist
+    pub broken_link: bool,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// Owner and group names, when the server's listing provides them
+    pub user: Option<String>,
+    pub group: Option<String>,
+    pub atime: Option<i64>,
+}
+
+/// File type from the `S_IFMT` bits of a mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryType {
+    File,
+    Directory,
+    Symlink,
+    Socket,
+    Fifo,
+    BlockDevice,
+    CharDevice,
+    Unknown,
+}
+
+impl EntryType {
+    pub fn from_mode(mode: Option<u32>) -> Self {
+        match mode.unwrap_or(0) & 0o170000 {
+            0o100000 => Self::File,
+            0o040000 => Self::Directory,
+            0o120000 => Self::Symlink,
+            0o140000 => Self::Socket,
+            0o010000 => Self::Fifo,
+            0o060000 => Self::BlockDevice,
+            0o020000 => Self::CharDevice,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Owner and group names from an `ls -l` style `longname`, as sent by OpenSSH for each entry.
+pub fn owner_from_longname(longname: &str) -> Option<(String, String)> {
+    let fields: Vec<&str> = longname.split_whitespace().collect();
+    let looks_like_mode = fields.first().is_some_and(|mode| mode.len() >= 10);
+    if !looks_like_mode || fields.len() < 4 {
+        return None;
+    }
+    Some((fields[2].to_string(), fields[3].to_string()))
 }
 
 /// File metadata from SFTP
@@ -16,3 +81,943 @@ pub struct SftpStat {
     pub size: u64,
     pub mtime: i64,
 }
+
+/// Lowercase hex SHA-256 of file content, as used by [`WritePrecondition::expected_hash`].
+pub fn sha256_hex(content: &[u8]) -> String {
+    hex_digest(Sha256::digest(content).as_slice())
+}
+
+fn hex_digest(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+const HASH_CHUNK: usize = 64 * 1024;
+
+/// SHA-256 of a remote file, read in chunks rather than into memory.
+async fn sha256_remote(sftp: &SftpSession, path: &str) -> Result<String, SshError> {
+    let mut file = sftp.open(path).await.map_err(map_sftp_error)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; HASH_CHUNK];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .await
+            .map_err(|e| SshError::SftpError(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex_digest(hasher.finalize().as_slice()))
+}
+
+/// What the writer believes the remote file currently is. Every field that is set must match,
+/// otherwise the write is refused with [`SshError::WriteConflict`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WritePrecondition {
+    pub expected_mtime: Option<i64>,
+    pub expected_size: Option<u64>,
+    /// See [`sha256_hex`]. Checked by reading the whole remote file, however large.
+    pub expected_hash: Option<String>,
+}
+
+impl WritePrecondition {
+    pub fn is_empty(&self) -> bool {
+        self.expected_mtime.is_none() && self.expected_size.is_none() && self.expected_hash.is_none()
+    }
+}
+
+/// Remote state reported with a write conflict; `exists` is false if the file was deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteFileState {
+    pub exists: bool,
+    pub size: Option<u64>,
+    pub mtime: Option<i64>,
+    pub hash: Option<String>,
+}
+
+/// Check `precondition` against the current remote file. Run under the same SFTP lock as the
+/// write that follows so nothing of ours can interleave.
+pub async fn check_precondition(
+    sftp: &SftpSession,
+    path: &str,
+    precondition: &WritePrecondition,
+) -> Result<(), SshError> {
+    if precondition.is_empty() {
+        return Ok(());
+    }
+
+    let metadata = match sftp.metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(SftpClientError::Status(status)) if status.status_code == StatusCode::NoSuchFile => {
+            return Err(SshError::WriteConflict {
+                path: path.to_string(),
+                current: RemoteFileState {
+                    exists: false,
+                    size: None,
+                    mtime: None,
+                    hash: None,
+                },
+            });
+        }
+        Err(e) => return Err(map_sftp_error(e)),
+    };
+
+    let size = metadata.size.unwrap_or(0);
+    let mtime = metadata.mtime.map(|t| t as i64).unwrap_or(0);
+    let mut matches = precondition.expected_size.map_or(true, |expected| expected == size)
+        && precondition.expected_mtime.map_or(true, |expected| expected == mtime);
+
+    // Only hash when the cheap checks passed; a conflict is reported either way.
+    let mut hash = None;
+    if let (true, Some(expected)) = (matches, &precondition.expected_hash) {
+        let current = sha256_remote(sftp, path).await?;
+        matches = current.eq_ignore_ascii_case(expected.trim());
+        hash = Some(current);
+    }
+
+    if matches {
+        return Ok(());
+    }
+    Err(SshError::WriteConflict {
+        path: path.to_string(),
+        current: RemoteFileState {
+            exists: true,
+            size: Some(size),
+            mtime: Some(mtime),
+            hash,
+        },
+    })
+}
+
+/// How a file save was carried out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SaveStrategy {
+    /// Written to a temp sibling, then swapped in with `posix-rename@openssh.com`.
+    Atomic,
+    /// Target truncated and overwritten in place (the pre-existing behaviour).
+    InPlace,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveReport {
+    pub strategy: SaveStrategy,
+    /// Data was flushed with `fsync@openssh.com` before the rename
+    pub fsynced: bool,
+    /// Why the atomic strategy was not used
+    pub fallback_reason: Option<String>,
+}
+
+const POSIX_RENAME: &str = "posix-rename@openssh.com";
+/// Server-side copy between two open handles (OpenSSH 9.0+).
+pub const COPY_DATA: &str = "copy-data";
+
+/// A raw SFTP session next to the main `SftpSession`, for the server's advertised extensions
+/// and the ones `SftpSession` does not expose (`posix-rename@openssh.com`, `copy-data`).
+pub struct SftpExtensions {
+    raw: RawSftpSession,
+    advertised: HashMap<String, String>,
+}
+
+impl SftpExtensions {
+    pub async fn open<S>(stream: S, timeout_secs: u64) -> Result<Self, SftpClientError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let raw = RawSftpSession::new(stream);
+        raw.set_timeout(timeout_secs).await;
+        let version = raw.init().await?;
+        Ok(Self {
+            raw,
+            advertised: version.extensions,
+        })
+    }
+
+    pub fn supports(&self, name: &str) -> bool {
+        self.advertised.contains_key(name)
+    }
+
+    /// List a directory keeping each entry's `longname`, which `SftpSession::read_dir` drops.
+    pub async fn read_dir(&self, path: &str) -> Result<Vec<File>, SftpClientError> {
+        let handle = self.raw.opendir(path).await?.handle;
+        let mut files = Vec::new();
+        let listed = loop {
+            match self.raw.readdir(handle.as_str()).await {
+                Ok(name) => files.extend(name.files.into_iter().filter(|f| f.filename != "." && f.filename != "..")),
+                Err(SftpClientError::Status(status)) if status.status_code == StatusCode::Eof => break Ok(files),
+                Err(e) => break Err(e),
+            }
+        };
+        let _ = self.raw.close(handle).await;
+        listed
+    }
+
+    /// Rename that replaces `newpath` atomically if it exists (POSIX `rename(2)` semantics).
+    pub async fn posix_rename(&self, oldpath: &str, newpath: &str) -> Result<(), SftpClientError> {
+        let mut data = Vec::with_capacity(8 + oldpath.len() + newpath.len());
+        put_string(&mut data, oldpath);
+        put_string(&mut data, newpath);
+        expect_ok(self.raw.extended(POSIX_RENAME, data).await?)
+    }
+
+    /// Copy `from` into a new file `to` without the data leaving the server (`copy-data`), then
+    /// apply `attrs` (e.g. mode and times) to the copy, best effort.
+    pub async fn copy_file(&self, from: &str, to: &str, attrs: FileAttributes) -> Result<(), SftpClientError> {
+        let source = self.raw.open(from, OpenFlags::READ, FileAttributes::empty()).await?.handle;
+        let target = match self
+            .raw
+            .open(to, OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE, FileAttributes::empty())
+            .await
+        {
+            Ok(target) => target.handle,
+            Err(e) => {
+                let _ = self.raw.close(source).await;
+                return Err(e);
+            }
+        };
+
+        // read-from-handle, read-from-offset, read-data-length (0: to EOF), write-to-handle,
+        // write-to-offset
+        let mut data = Vec::with_capacity(40 + source.len() + target.len());
+        put_string(&mut data, &source);
+        data.extend_from_slice(&0u64.to_be_bytes());
+        data.extend_from_slice(&0u64.to_be_bytes());
+        put_string(&mut data, &target);
+        data.extend_from_slice(&0u64.to_be_bytes());
+
+        let copied = self.raw.extended(COPY_DATA, data).await.and_then(expect_ok);
+        if copied.is_ok() {
+            let _ = self.raw.fsetstat(target.as_str(), attrs).await;
+        }
+        let _ = self.raw.close(source).await;
+        let closed = self.raw.close(target).await;
+        copied?;
+        closed.map(|_| ())
+    }
+}
+
+fn put_string(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(&(s.len() as u32).to_be_bytes());
+    data.extend_from_slice(s.as_bytes());
+}
+
+fn expect_ok(reply: Packet) -> Result<(), SftpClientError> {
+    match reply {
+        Packet::Status(status) if status.status_code == StatusCode::Ok => Ok(()),
+        Packet::Status(status) => Err(SftpClientError::Status(status)),
+        _ => Err(SftpClientError::UnexpectedPacket),
+    }
+}
+
+/// `dir/.name.<random>.tmp` next to `path`, so the final rename stays on one filesystem.
+fn temp_sibling(path: &str) -> String {
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), path),
+    };
+    let suffix = &uuid::Uuid::new_v4().simple().to_string()[..8];
+    format!("{}.{}.{}.tmp", dir, name, suffix)
+}
+
+fn is_connection_error(error: &SshError) -> bool {
+    matches!(error, SshError::SftpTimeout | SshError::SftpSessionClosed)
+}
+
+/// Truncate and overwrite `path` in place.
+pub async fn write_in_place(sftp: &SftpSession, path: &str, content: &[u8]) -> Result<(), SshError> {
+    let mut file = sftp.create(path).await.map_err(map_sftp_error)?;
+
+    file.write_all(content)
+        .await
+        .map_err(|e| SshError::SftpError(e.to_string()))?;
+    file.shutdown()
+        .await
+        .map_err(|e| SshError::SftpError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Save `content` to `path` without ever leaving a half-written target: write a temp sibling
+/// carrying the original mode and ownership, fsync it when the server allows, then
+/// `posix-rename` it over the target. Falls back to [`write_in_place`] when the server lacks the
+/// extension, the target is a symlink, the directory is not writable, or ownership cannot be kept.
+pub async fn write_atomic(
+    sftp: &SftpSession,
+    extensions: Option<&SftpExtensions>,
+    path: &str,
+    content: &[u8],
+) -> Result<SaveReport, SshError> {
+    let fallback = |reason: String| async move {
+        write_in_place(sftp, path, content).await?;
+        Ok(SaveReport {
+            strategy: SaveStrategy::InPlace,
+            fsynced: false,
+            fallback_reason: Some(reason),
+        })
+    };
+
+    let Some(ext) = extensions.filter(|e| e.supports(POSIX_RENAME)) else {
+        return fallback(format!("server does not support {}", POSIX_RENAME)).await;
+    };
+
+    let original = match sftp.symlink_metadata(path).await {
+        Ok(metadata) => Some(metadata),
+        Err(SftpClientError::Status(status)) if status.status_code == StatusCode::NoSuchFile => None,
+        Err(e) => return Err(map_sftp_error(e)),
+    };
+    if original.as_ref().is_some_and(|m| m.is_symlink()) {
+        // Renaming over a symlink would replace the link itself.
+        return fallback("target is a symlink".to_string()).await;
+    }
+
+    let temp = temp_sibling(path);
+    let mut create_attrs = FileAttributes::empty();
+    create_attrs.permissions = original.as_ref().and_then(|m| m.permissions).map(|p| p & 0o7777);
+    let mut file = match sftp
+        .open_with_flags_and_attributes(
+            temp.clone(),
+            OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE,
+            create_attrs,
+        )
+        .await
+        .map_err(map_sftp_error)
+    {
+        Ok(file) => file,
+        Err(e) if is_connection_error(&e) => return Err(e),
+        Err(e) => return fallback(format!("cannot create temp file: {}", e)).await,
+    };
+
+    let written: Result<(bool, bool), SshError> = async {
+        file.write_all(content)
+            .await
+            .map_err(|e| SshError::SftpError(e.to_string()))?;
+
+        let fsynced = ext.supports(FSYNC);
+        if fsynced {
+            file.sync_all().await.map_err(map_sftp_error)?;
+        }
+
+        let mut owner_kept = true;
+        if let Some(original) = &original {
+            // The create mode is subject to the server umask; set it explicitly.
+            let mut attrs = FileAttributes::empty();
+            attrs.permissions = original.permissions.map(|p| p & 0o7777);
+            file.set_metadata(attrs).await.map_err(map_sftp_error)?;
+
+            let current = file.metadata().await.map_err(map_sftp_error)?;
+            if (current.uid, current.gid) != (original.uid, original.gid) {
+                let mut attrs = FileAttributes::empty();
+                attrs.uid = original.uid;
+                attrs.gid = original.gid;
+                owner_kept = file.set_metadata(attrs).await.is_ok();
+            }
+        }
+
+        file.shutdown()
+            .await
+            .map_err(|e| SshError::SftpError(e.to_string()))?;
+        Ok((fsynced, owner_kept))
+    }
+    .await;
+
+    let (fsynced, owner_kept) = match written {
+        Ok(result) => result,
+        Err(e) => {
+            let _ = sftp.remove_file(temp).await;
+            return Err(e);
+        }
+    };
+
+    if !owner_kept {
+        let _ = sftp.remove_file(temp).await;
+        return fallback("cannot preserve file ownership".to_string()).await;
+    }
+
+    if let Err(e) = ext.posix_rename(&temp, path).await {
+        let _ = sftp.remove_file(temp).await;
+        return Err(map_sftp_error(e));
+    }
+
+    Ok(SaveReport {
+        strategy: SaveStrategy::Atomic,
+        fsynced,
+        fallback_reason: None,
+    })
+}
+
+/// Symlinks resolved at once by [`list_dir`]
+const SYMLINK_CONCURRENCY: usize = 16;
+
+/// List `path` with full metadata. Symlinks are resolved for their target and whether it is a
+/// directory or missing, several at a time; owner names come from `longname`, so need the
+/// extension session.
+pub async fn list_dir(
+    sftp: &SftpSession,
+    extensions: Option<&SftpExtensions>,
+    path: &str,
+) -> Result<Vec<SftpEntry>, SshError> {
+    let listing: Vec<(String, FileAttributes, Option<String>)> = match extensions {
+        Some(ext) => ext
+            .read_dir(path)
+            .await
+            .map_err(map_sftp_error)?
+            .into_iter()
+            .map(|f| (f.filename, f.attrs, Some(f.longname)))
+            .collect(),
+        None => sftp
+            .read_dir(path)
+            .await
+            .map_err(map_sftp_error)?
+            .map(|entry| (entry.file_name(), entry.metadata(), None))
+            .collect(),
+    };
+
+    let mut result = Vec::with_capacity(listing.len());
+    let mut symlinks = Vec::new();
+    for (name, metadata, longname) in listing {
+        let file_type = EntryType::from_mode(metadata.permissions);
+        let (user, group) = longname
+            .as_deref()
+            .and_then(owner_from_longname)
+            .map_or((None, None), |(user, group)| (Some(user), Some(group)));
+        let entry = SftpEntry {
+            is_directory: file_type == EntryType::Directory,
+            is_symlink: file_type == EntryType::Symlink,
+            target_is_directory: false,
+            size: metadata.size.unwrap_or(0),
+            mtime: metadata.mtime.map(|t| t as i64).unwrap_or(0),
+            permissions: metadata.permissions.map(|p| format!("{:o}", p)),
+            file_type,
+            symlink_target: None,
+            broken_link: false,
+            uid: metadata.uid,
+            gid: metadata.gid,
+            user,
+            group,
+            atime: metadata.atime.map(|t| t as i64),
+            name,
+        };
+
+        if file_type == EntryType::Symlink {
+            symlinks.push(result.len());
+        }
+        result.push(entry);
+    }
+
+    // Each link costs a `readlink` and a `stat`; pipeline them instead of waiting on each in turn.
+    let resolved: Vec<_> = stream::iter(symlinks)
+        .map(|idx| {
+            let full_path = format!("{}/{}", path.trim_end_matches('/'), result[idx].name);
+            async move {
+                let (target, metadata) =
+                    tokio::join!(sftp.read_link(full_path.as_str()), sftp.metadata(full_path.as_str()));
+                (idx, target.ok(), metadata)
+            }
+        })
+        .buffer_unordered(SYMLINK_CONCURRENCY)
+        .collect()
+        .await;
+
+    for (idx, target, metadata) in resolved {
+        let entry = &mut result[idx];
+        entry.symlink_target = target;
+        match metadata {
+            Ok(target) => entry.target_is_directory = target.file_type() == FileType::Dir,
+            Err(SftpClientError::Status(status)) if status.status_code == StatusCode::NoSuchFile => {
+                entry.broken_link = true;
+            }
+            Err(e) => {
+                let e = map_sftp_error(e);
+                if is_connection_error(&e) {
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// Set the permission bits of `path`, following symlinks like `chmod(1)`.
+pub async fn chmod(sftp: &SftpSession, path: &str, mode: u32) -> Result<(), SshError> {
+    let mut attrs = FileAttributes::empty();
+    attrs.permissions = Some(mode & 0o7777);
+    sftp.set_metadata(path, attrs).await.map_err(map_sftp_error)
+}
+
+/// Change the owner and/or group of `path`. SFTP v3 sets both at once, so the one not given is
+/// kept at its current value.
+pub async fn chown(sftp: &SftpSession, path: &str, uid: Option<u32>, gid: Option<u32>) -> Result<(), SshError> {
+    let (uid, gid) = match (uid, gid) {
+        (None, None) => return Ok(()),
+        (Some(uid), Some(gid)) => (uid, gid),
+        _ => {
+            let current = sftp.metadata(path).await.map_err(map_sftp_error)?;
+            match (uid.or(current.uid), gid.or(current.gid)) {
+                (Some(uid), Some(gid)) => (uid, gid),
+                _ => return Err(SshError::SftpError(format!("{}: server did not report ownership", path))),
+            }
+        }
+    };
+
+    let mut attrs = FileAttributes::empty();
+    attrs.uid = Some(uid);
+    attrs.gid = Some(gid);
+    sftp.set_metadata(path, attrs).await.map_err(map_sftp_error)
+}
+
+/// Like `touch(1)`: set the access and modification times of `path` to `mtime` (seconds since
+/// the epoch, now if `None`), creating an empty file if it does not exist.
+pub async fn touch(sftp: &SftpSession, path: &str, mtime: Option<i64>) -> Result<(), SshError> {
+    if !exists(sftp, path).await? {
+        let mut file = sftp
+            .open_with_flags(path, OpenFlags::CREATE | OpenFlags::WRITE)
+            .await
+            .map_err(map_sftp_error)?;
+        file.shutdown()
+            .await
+            .map_err(|e| SshError::SftpError(e.to_string()))?;
+    }
+
+    let time = match mtime {
+        Some(t) => t.clamp(0, u32::MAX as i64) as u32,
+        None => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as u32,
+    };
+    let mut attrs = FileAttributes::empty();
+    attrs.atime = Some(time);
+    attrs.mtime = Some(time);
+    sftp.set_metadata(path, attrs).await.map_err(map_sftp_error)
+}
+
+/// Upper bound on the bytes returned by one ranged or tail read.
+pub const MAX_RANGE_LEN: u64 = 4 * 1024 * 1024;
+/// Step size when scanning backwards from EOF for line breaks.
+const TAIL_BLOCK: u64 = 64 * 1024;
+
+/// A slice of a remote file, plus the file's size and mtime when it was read.
+#[derive(Debug, Clone)]
+pub struct RangeRead {
+    pub data: Vec<u8>,
+    /// Offset of `data[0]` in the file
+    pub offset: u64,
+    pub size: u64,
+    pub mtime: i64,
+}
+
+/// Read up to `len` bytes (capped at [`MAX_RANGE_LEN`]) starting at `offset`. Reading at or past
+/// EOF returns no data rather than an error.
+pub async fn read_range(sftp: &SftpSession, path: &str, offset: u64, len: u64) -> Result<RangeRead, SshError> {
+    let metadata = sftp.metadata(path).await.map_err(map_sftp_error)?;
+    let size = metadata.size.unwrap_or(0);
+    let len = len.min(MAX_RANGE_LEN).min(size.saturating_sub(offset));
+
+    let mut data = Vec::with_capacity(len as usize);
+    if len > 0 {
+        let mut file = sftp.open(path).await.map_err(map_sftp_error)?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|e| SshError::SftpError(e.to_string()))?;
+        file.take(len)
+            .read_to_end(&mut data)
+            .await
+            .map_err(|e| SshError::SftpError(e.to_string()))?;
+    }
+
+    Ok(RangeRead {
+        data,
+        offset,
+        size,
+        mtime: metadata.mtime.map(|t| t as i64).unwrap_or(0),
+    })
+}
+
+/// Read the last `lines` lines by scanning backwards from EOF, so only the tail is transferred.
+/// If they do not fit in [`MAX_RANGE_LEN`], as many whole lines as fit are returned.
+pub async fn read_tail(sftp: &SftpSession, path: &str, lines: usize) -> Result<RangeRead, SshError> {
+    let metadata = sftp.metadata(path).await.map_err(map_sftp_error)?;
+    let size = metadata.size.unwrap_or(0);
+    let mtime = metadata.mtime.map(|t| t as i64).unwrap_or(0);
+
+    let mut file = sftp.open(path).await.map_err(map_sftp_error)?;
+    let mut start = size;
+    let mut data: Vec<u8> = Vec::new();
+    loop {
+        if let Some(cut) = tail_start(&data, lines) {
+            data.drain(..cut);
+            start += cut as u64;
+            break;
+        }
+        if start == 0 {
+            break;
+        }
+        if data.len() as u64 >= MAX_RANGE_LEN {
+            // Out of budget: drop the partial first line.
+            if let Some(newline) = data.iter().position(|&b| b == b'\n') {
+                data.drain(..=newline);
+                start += newline as u64 + 1;
+            }
+            break;
+        }
+
+        let block = TAIL_BLOCK.min(start);
+        start -= block;
+        let mut chunk = vec![0u8; block as usize];
+        file.seek(SeekFrom::Start(start))
+            .await
+            .map_err(|e| SshError::SftpError(e.to_string()))?;
+        file.read_exact(&mut chunk)
+            .await
+            .map_err(|e| SshError::SftpError(e.to_string()))?;
+        chunk.extend_from_slice(&data);
+        data = chunk;
+    }
+
+    Ok(RangeRead {
+        data,
+        offset: start,
+        size,
+        mtime,
+    })
+}
+
+/// Index in `data` (which ends at EOF) where the last `lines` lines begin, if `data` reaches back
+/// far enough to tell. A trailing newline ends the last line rather than starting an empty one.
+fn tail_start(data: &[u8], lines: usize) -> Option<usize> {
+    if lines == 0 {
+        return Some(data.len());
+    }
+    let end = match data.last() {
+        Some(b'\n') => data.len() - 1,
+        _ => data.len(),
+    };
+    data[..end]
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, &b)| b == b'\n')
+        .nth(lines - 1)
+        .map(|(i, _)| i + 1)
+}
+
+/// An entry found while walking a tree. Symlinks are listed, never followed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeEntry {
+    pub path: String,
+    pub is_directory: bool,
+    pub is_symlink: bool,
+    pub size: u64,
+}
+
+impl TreeEntry {
+    fn new(path: &str, attrs: &FileAttributes) -> Self {
+        let file_type = attrs.file_type();
+        Self {
+            path: path.to_string(),
+            is_directory: file_type == FileType::Dir,
+            is_symlink: file_type == FileType::Symlink,
+            size: if file_type == FileType::File { attrs.size.unwrap_or(0) } else { 0 },
+        }
+    }
+}
+
+/// What a recursive delete removed, or would remove for a dry run.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteReport {
+    pub dry_run: bool,
+    /// Children before their directory, in deletion order
+    pub entries: Vec<TreeEntry>,
+    pub files: u64,
+    pub directories: u64,
+    pub bytes: u64,
+}
+
+/// How a copy was carried out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyStrategy {
+    /// Files copied on the server with the `copy-data` extension.
+    CopyData,
+    /// `cp -Rp` over an exec channel.
+    Exec,
+    /// Files streamed through this client with SFTP reads and writes.
+    Sftp,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyReport {
+    pub strategy: CopyStrategy,
+    pub files: u64,
+    pub directories: u64,
+    pub bytes: u64,
+    /// Sockets, FIFOs and devices, which are not copied
+    pub skipped: u64,
+    /// Why a server-side strategy was not used
+    pub fallback_reason: Option<String>,
+}
+
+/// Outcome of a move: a plain rename, or a copy followed by deleting the source.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveReport {
+    pub renamed: bool,
+    /// Set when `rename` failed (e.g. across filesystems) and the tree was copied instead
+    pub copy: Option<CopyReport>,
+}
+
+/// Prefix errors with the path they concern, keeping session errors intact for the retry logic.
+fn at_path(path: &str, error: SshError) -> SshError {
+    match error {
+        SshError::SftpError(message) => SshError::SftpError(format!("{}: {}", path, message)),
+        other => other,
+    }
+}
+
+fn trim_path(path: &str) -> &str {
+    match path.trim_end_matches('/') {
+        "" if path.starts_with('/') => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// Whether anything (including a dangling symlink) exists at `path`.
+pub async fn exists(sftp: &SftpSession, path: &str) -> Result<bool, SshError> {
+    match sftp.symlink_metadata(path).await {
+        Ok(_) => Ok(true),
+        Err(SftpClientError::Status(status)) if status.status_code == StatusCode::NoSuchFile => Ok(false),
+        Err(e) => Err(map_sftp_error(e)),
+    }
+}
+
+fn cancel_requested(cancel: Option<&watch::Receiver<bool>>) -> bool {
+    cancel.is_some_and(|cancel| *cancel.borrow())
+}
+
+/// Every entry under and including `root`, each directory after its contents. Stops with
+/// `SshError::Cancelled` once `cancel` is set.
+async fn walk_tree(
+    sftp: &SftpSession,
+    root: &str,
+    cancel: Option<&watch::Receiver<bool>>,
+) -> Result<Vec<(String, FileAttributes)>, SshError> {
+    let root = trim_path(root);
+    let attrs = sftp.symlink_metadata(root).await.map_err(map_sftp_error)?;
+    let mut tree = Vec::new();
+    let mut stack = vec![(root.to_string(), attrs, false)];
+    while let Some((path, attrs, expanded)) = stack.pop() {
+        if attrs.file_type() == FileType::Dir && !expanded {
+            if cancel_requested(cancel) {
+                return Err(SshError::Cancelled);
+            }
+            let listing = sftp
+                .read_dir(path.as_str())
+                .await
+                .map_err(|e| at_path(&path, map_sftp_error(e)))?;
+            let children: Vec<_> = listing
+                .map(|entry| {
+                    let child = format!("{}/{}", path.trim_end_matches('/'), entry.file_name());
+                    (child, entry.metadata(), false)
+                })
+                .collect();
+            stack.push((path, attrs, true));
+            stack.extend(children);
+            continue;
+        }
+        tree.push((path, attrs));
+    }
+    Ok(tree)
+}
+
+/// Delete `path` and everything under it, or with `dry_run` only list what would go. Setting
+/// `cancel` stops between entries, leaving whatever was not removed yet.
+pub async fn delete_tree(
+    sftp: &SftpSession,
+    path: &str,
+    dry_run: bool,
+    cancel: Option<&watch::Receiver<bool>>,
+) -> Result<DeleteReport, SshError> {
+    if trim_path(path) == "/" || path.is_empty() {
+        return Err(SshError::SftpError("Refusing to delete the root directory".to_string()));
+    }
+
+    let tree = walk_tree(sftp, path, cancel).await?;
+    if !dry_run {
+        for (entry, attrs) in &tree {
+            if cancel_requested(cancel) {
+                return Err(SshError::Cancelled);
+            }
+            let removed = if attrs.file_type() == FileType::Dir {
+                sftp.remove_dir(entry.as_str()).await
+            } else {
+                sftp.remove_file(entry.as_str()).await
+            };
+            removed.map_err(|e| at_path(entry, map_sftp_error(e)))?;
+        }
+    }
+
+    let entries: Vec<TreeEntry> = tree.iter().map(|(path, attrs)| TreeEntry::new(path, attrs)).collect();
+    Ok(DeleteReport {
+        dry_run,
+        files: entries.iter().filter(|e| !e.is_directory).count() as u64,
+        directories: entries.iter().filter(|e| e.is_directory).count() as u64,
+        bytes: entries.iter().map(|e| e.size).sum(),
+        entries,
+    })
+}
+
+/// A validated copy of `from` to the new path `to`, with the source tree listed parents first.
+pub struct CopyPlan {
+    from: String,
+    to: String,
+    entries: Vec<(String, FileAttributes)>,
+}
+
+impl CopyPlan {
+    pub fn from(&self) -> &str {
+        &self.from
+    }
+
+    pub fn to(&self) -> &str {
+        &self.to
+    }
+
+    pub fn report(&self, strategy: CopyStrategy, fallback_reason: Option<String>) -> CopyReport {
+        let mut report = CopyReport {
+            strategy,
+            files: 0,
+            directories: 0,
+            bytes: 0,
+            skipped: 0,
+            fallback_reason,
+        };
+        for (_, attrs) in &self.entries {
+            match attrs.file_type() {
+                FileType::Dir => report.directories += 1,
+                FileType::File => {
+                    report.files += 1;
+                    report.bytes += attrs.size.unwrap_or(0);
+                }
+                FileType::Symlink => report.files += 1,
+                FileType::Other => report.skipped += 1,
+            }
+        }
+        report
+    }
+}
+
+/// Check that `from` can be copied to `to`, which must not exist yet, and list the source tree.
+pub async fn plan_copy(sftp: &SftpSession, from: &str, to: &str) -> Result<CopyPlan, SshError> {
+    let (from, to) = (trim_path(from), trim_path(to));
+    if from == "/" || from == to || to.starts_with(&format!("{}/", from)) {
+        return Err(SshError::SftpError(format!("Cannot copy {} into itself", from)));
+    }
+    if exists(sftp, to).await? {
+        return Err(SshError::TargetExists(to.to_string()));
+    }
+
+    let mut entries = walk_tree(sftp, from, None).await?;
+    entries.reverse();
+    Ok(CopyPlan {
+        from: from.to_string(),
+        to: to.to_string(),
+        entries,
+    })
+}
+
+/// Mode and times of `attrs`, for applying to a copy.
+fn preserved_attrs(attrs: &FileAttributes) -> FileAttributes {
+    let mut preserved = FileAttributes::empty();
+    preserved.permissions = attrs.permissions.map(|p| p & 0o7777);
+    if attrs.atime.is_some() && attrs.mtime.is_some() {
+        preserved.atime = attrs.atime;
+        preserved.mtime = attrs.mtime;
+    }
+    preserved
+}
+
+/// Create a symlink at `link` pointing to `target`. OpenSSH's sftp-server reads the two
+/// `SSH_FXP_SYMLINK` paths in the reverse of the draft's order, which russh-sftp follows.
+pub async fn create_symlink(sftp: &SftpSession, link: &str, target: &str) -> Result<(), SshError> {
+    sftp.symlink(target, link).await.map_err(map_sftp_error)
+}
+
+/// Stream one file through this client into the new file `to`.
+async fn copy_file_sftp(sftp: &SftpSession, from: &str, to: &str) -> Result<(), SshError> {
+    let source = sftp.open(from).await.map_err(map_sftp_error)?;
+    let mut target = sftp
+        .open_with_flags_and_attributes(
+            to,
+            OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE,
+            FileAttributes::empty(),
+        )
+        .await
+        .map_err(map_sftp_error)?;
+
+    let mut source = BufReader::with_capacity(COPY_BUFFER, source);
+    tokio::io::copy_buf(&mut source, &mut target)
+        .await
+        .map_err(|e| SshError::SftpError(e.to_string()))?;
+    target
+        .shutdown()
+        .await
+        .map_err(|e| SshError::SftpError(e.to_string()))
+}
+
+const COPY_BUFFER: usize = 64 * 1024;
+
+/// Copy a planned tree entry by entry, with `copy-data` for file contents when `copy_data` is
+/// given and SFTP reads and writes otherwise. Modes and times are carried over best effort.
+pub async fn copy_tree(
+    sftp: &SftpSession,
+    copy_data: Option<&SftpExtensions>,
+    plan: &CopyPlan,
+    fallback_reason: Option<String>,
+) -> Result<CopyReport, SshError> {
+    let mut dirs = Vec::new();
+    for (source, attrs) in &plan.entries {
+        let target = format!("{}{}", plan.to, &source[plan.from.len()..]);
+        let copied = match attrs.file_type() {
+            FileType::Dir => {
+                let created = sftp.create_dir(target.as_str()).await.map_err(map_sftp_error);
+                // Applied once the contents are in, so a read-only mode cannot get in the way.
+                dirs.push((target.clone(), attrs));
+                created
+            }
+            FileType::Symlink => match sftp.read_link(source.as_str()).await {
+                Ok(link_target) => create_symlink(sftp, &target, &link_target).await,
+                Err(e) => Err(map_sftp_error(e)),
+            },
+            FileType::File => match copy_data {
+                Some(ext) => ext
+                    .copy_file(source, &target, preserved_attrs(attrs))
+                    .await
+                    .map_err(map_sftp_error),
+                None => match copy_file_sftp(sftp, source, &target).await {
+                    Ok(()) => {
+                        let _ = sftp.set_metadata(target.as_str(), preserved_attrs(attrs)).await;
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                },
+            },
+            FileType::Other => Ok(()),
+        };
+        copied.map_err(|e| at_path(&target, e))?;
+    }
+
+    for (dir, attrs) in dirs.into_iter().rev() {
+        let _ = sftp.set_metadata(dir, preserved_attrs(attrs)).await;
+    }
+
+    let strategy = if copy_data.is_some() { CopyStrategy::CopyData } else { CopyStrategy::Sftp };
+    Ok(plan.report(strategy, fallback_reason))
+}
@@ -0,0 +1,594 @@
+//! Project tasks: build/test commands declared in the remote project root.
+//!
+//! Tasks are discovered from the root's `Makefile`, `package.json`, `Cargo.toml` and `justfile`
+//! by reading the files over SFTP, so none of the tools has to be queried. A task runs through
+//! [`exec`] in the project root; its run ID is the exec ID, so output arrives as `exec_output`
+//! events, diagnostics as `exec_problems` and the exit status with `exec_done`.
+
+use crate::problems::MatcherKind;
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::SshError;
+use crate::ssh::exec::{self, ExecError, ExecInfo, ExecOptions};
+use crate::ssh::pty::shell_escape;
+use crate::ssh::sftp::SftpEntry;
+use crate::state::AppState;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+use tokio::sync::{oneshot, Mutex};
+use uuid::Uuid;
+
+const MAKEFILES: [&str; 3] = ["GNUmakefile", "makefile", "Makefile"];
+const JUSTFILES: [&str; 3] = ["justfile", "Justfile", ".justfile"];
+const CARGO_TASKS: [&str; 5] = ["build", "check", "test", "clippy", "run"];
+
+#[derive(Debug, Error)]
+pub enum TaskError {
+    #[error("Connection not found")]
+    ConnectionNotFound,
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error("Task not found: {0}")]
+    NotFound(String),
+    #[error("Task name {0} is ambiguous; use one of: {1}")]
+    Ambiguous(String, String),
+    #[error("No task has been run on this connection")]
+    NoLastTask,
+    #[error(transparent)]
+    Exec(#[from] ExecError),
+    #[error(transparent)]
+    Ssh(#[from] SshError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSource {
+    Make,
+    Npm,
+    Cargo,
+    Just,
+}
+
+impl TaskSource {
+    fn prefix(self) -> &'static str {
+        match self {
+            TaskSource::Make => "make",
+            TaskSource::Npm => "npm",
+            TaskSource::Cargo => "cargo",
+            TaskSource::Just => "just",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskDef {
+    /// `<source>:<name>`, e.g. `npm:build`
+    pub id: String,
+    pub source: TaskSource,
+    pub name: String,
+    /// Shell command that runs the task from the project root
+    pub command: String,
+    pub description: Option<String>,
+    /// File the task was found in
+    pub file: String,
+}
+
+/// A started task. `id` (from the flattened exec) is the run ID.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+    pub task: TaskDef,
+    #[serde(flatten)]
+    pub exec: ExecInfo,
+}
+
+/// A task as it was started, to start it again the same way.
+#[derive(Clone)]
+struct LastTask {
+    root: String,
+    task: TaskDef,
+    env: BTreeMap<String, String>,
+}
+
+/// Tasks of running commands by run ID; each removes itself when its command finishes.
+static RUNS: OnceLock<StdMutex<HashMap<String, TaskDef>>> = OnceLock::new();
+/// Last task started per connection
+static LAST: OnceLock<StdMutex<HashMap<String, LastTask>>> = OnceLock::new();
+
+fn runs() -> &'static StdMutex<HashMap<String, TaskDef>> {
+    RUNS.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+fn last() -> &'static StdMutex<HashMap<String, LastTask>> {
+    LAST.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+/// Send a request to the connection's current actor and wait for its reply.
+async fn actor_request<T>(
+    app: &AppHandle,
+    connection_id: &str,
+    request: impl FnOnce(oneshot::Sender<Result<T, SshError>>) -> ConnectionRequest,
+) -> Result<T, TaskError> {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    }
+    .ok_or(TaskError::ConnectionNotFound)?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| TaskError::ConnectionClosed)?;
+    Ok(rx.await.map_err(|_| TaskError::ConnectionClosed)??)
+}
+
+fn join(root: &str, name: &str) -> String {
+    format!("{}/{}", root.trim_end_matches('/'), name)
+}
+
+fn task(source: TaskSource, name: &str, command: String, description: Option<String>, file: &str) -> TaskDef {
+    TaskDef {
+        id: format!("{}:{}", source.prefix(), name),
+        source,
+        name: name.to_string(),
+        command,
+        description,
+        file: file.to_string(),
+    }
+}
+
+/// Whether `rest` (what follows a target or recipe name) starts a rule rather than an
+/// assignment such as `:=` or `::=`.
+fn is_rule_colon(rest: &str) -> bool {
+    let rest = rest.trim_start();
+    let rest = match rest.strip_prefix("::") {
+        Some(rest) => rest,
+        None => match rest.strip_prefix(':') {
+            Some(rest) => rest,
+            None => return false,
+        },
+    };
+    !rest.starts_with('=')
+}
+
+/// Explicit targets of a Makefile. File targets (with `.` or `/` in the name) are only listed when
+/// declared `.PHONY`; pattern rules, special targets and targets built from variables are
+/// skipped. A trailing `## text` on the rule line becomes the description.
+fn parse_makefile(content: &str) -> Vec<(String, Option<String>)> {
+    let mut phony = HashSet::new();
+    let mut targets: Vec<(String, Option<String>)> = Vec::new();
+    let mut in_define = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if in_define {
+            in_define = trimmed != "endef";
+            continue;
+        }
+        if trimmed.starts_with("define ") || trimmed == "define" {
+            in_define = true;
+            continue;
+        }
+        if line.starts_with([' ', '\t']) || trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let Some(colon) = line.find(':') else { continue };
+        let (names, rest) = line.split_at(colon);
+        if names.contains('=') || !is_rule_colon(rest) {
+            continue;
+        }
+        let description = rest
+            .split_once("##")
+            .map(|(_, text)| text.trim().to_string())
+            .filter(|text| !text.is_empty());
+        if names.trim() == ".PHONY" {
+            let deps = rest.trim_start_matches(':').split('#').next().unwrap_or("");
+            phony.extend(deps.split_whitespace().map(str::to_string));
+            continue;
+        }
+        for name in names.split_whitespace() {
+            if name.starts_with('.') || name.contains(['%', '$', '(']) {
+                continue;
+            }
+            if !targets.iter().any(|(existing, _)| existing == name) {
+                targets.push((name.to_string(), description.clone()));
+            }
+        }
+    }
+
+    targets
+        .into_iter()
+        .filter(|(name, _)| phony.contains(name) || !name.contains(['.', '/']))
+        .collect()
+}
+
+/// Public recipes of a justfile, with the comment line right above each as its description.
+/// Recipes starting with `_` or marked `[private]` are skipped.
+fn parse_justfile(content: &str) -> Vec<(String, Option<String>)> {
+    const KEYWORDS: [&str; 6] = ["set", "alias", "export", "import", "mod", "unexport"];
+    let mut recipes = Vec::new();
+    let mut comment: Option<String> = None;
+    let mut private = false;
+
+    for line in content.lines() {
+        if line.starts_with([' ', '\t']) || line.trim().is_empty() {
+            comment = None;
+            private = false;
+            continue;
+        }
+        let line = line.trim_end();
+        if let Some(text) = line.strip_prefix('#') {
+            comment = Some(text.trim().to_string()).filter(|text| !text.is_empty() && !text.starts_with('!'));
+            continue;
+        }
+        if line.starts_with('[') {
+            private |= line.contains("private");
+            continue;
+        }
+        let recipe = line.trim_start_matches('@');
+        let name_len = recipe
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(recipe.len());
+        let (name, rest) = recipe.split_at(name_len);
+        let keyword = KEYWORDS.contains(&name) && rest.starts_with(' ');
+        // Parameters come between the name and the colon.
+        let params_end = rest.find(':').unwrap_or(rest.len());
+        if !name.is_empty() && !keyword && is_rule_colon(&rest[params_end..]) && !name.starts_with('_') && !private {
+            recipes.push((name.to_string(), comment.take()));
+        }
+        comment = None;
+        private = false;
+    }
+    recipes
+}
+
+/// `run` only exists for packages with a binary target.
+fn parse_cargo_toml(content: &str, has_main: bool) -> Vec<&'static str> {
+    let has_package = content.lines().any(|line| line.trim() == "[package]");
+    let has_workspace = content.lines().any(|line| line.trim() == "[workspace]");
+    let has_bin = has_main || content.lines().any(|line| line.trim() == "[[bin]]");
+    if !has_package && !has_workspace {
+        return Vec::new();
+    }
+    CARGO_TASKS
+        .into_iter()
+        .filter(|name| *name != "run" || has_bin)
+        .collect()
+}
+
+/// Scripts of a `package.json`, with the command each runs. Empty if the file is not valid JSON.
+fn parse_package_json(content: &str) -> Vec<(String, Option<String>)> {
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let Some(scripts) = package.get("scripts").and_then(|scripts| scripts.as_object()) else {
+        return Vec::new();
+    };
+    scripts
+        .iter()
+        .map(|(name, script)| (name.clone(), script.as_str().map(str::to_string)))
+        .collect()
+}
+
+/// The package manager whose lockfile is in the root.
+fn npm_runner(names: &HashSet<&str>) -> &'static str {
+    if names.contains("pnpm-lock.yaml") {
+        "pnpm"
+    } else if names.contains("yarn.lock") {
+        "yarn"
+    } else if names.contains("bun.lockb") || names.contains("bun.lock") {
+        "bun"
+    } else {
+        "npm"
+    }
+}
+
+/// Tasks declared in `root`. Unreadable or malformed files are skipped.
+pub async fn discover(app: &AppHandle, connection_id: &str, root: &str) -> Result<Vec<TaskDef>, TaskError> {
+    let entries: Vec<SftpEntry> = actor_request(app, connection_id, |respond_to| ConnectionRequest::ListDir {
+        path: root.to_string(),
+        respond_to,
+    })
+    .await?;
+    let names: HashSet<&str> = entries
+        .iter()
+        .filter(|entry| !entry.is_directory)
+        .map(|entry| entry.name.as_str())
+        .collect();
+    let read = |name: &str| {
+        let path = join(root, name);
+        async move {
+            actor_request(app, connection_id, |respond_to| ConnectionRequest::ReadFile {
+                path,
+                respond_to,
+            })
+            .await
+            .ok()
+        }
+    };
+    let mut tasks = Vec::new();
+
+    // make picks the first of these that exists.
+    if let Some(name) = MAKEFILES.into_iter().find(|name| names.contains(name)) {
+        if let Some(content) = read(name).await {
+            for (target, description) in parse_makefile(&content) {
+                let command = format!("make {}", shell_escape(&target));
+                tasks.push(task(TaskSource::Make, &target, command, description, &join(root, name)));
+            }
+        }
+    }
+
+    if names.contains("package.json") {
+        let scripts = read("package.json")
+            .await
+            .map(|content| parse_package_json(&content))
+            .unwrap_or_default();
+        let runner = npm_runner(&names);
+        let file = join(root, "package.json");
+        for (name, script) in scripts {
+            let command = format!("{} run {}", runner, shell_escape(&name));
+            tasks.push(task(TaskSource::Npm, &name, command, script, &file));
+        }
+    }
+
+    if names.contains("Cargo.toml") {
+        if let Some(content) = read("Cargo.toml").await {
+            let has_main = actor_request(app, connection_id, |respond_to| ConnectionRequest::Stat {
+                path: join(root, "src/main.rs"),
+                respond_to,
+            })
+            .await
+            .is_ok();
+            let file = join(root, "Cargo.toml");
+            for name in parse_cargo_toml(&content, has_main) {
+                let command = format!("cargo {}", name);
+                tasks.push(task(TaskSource::Cargo, name, command, None, &file));
+            }
+        }
+    }
+
+    if let Some(name) = JUSTFILES.into_iter().find(|name| names.contains(name)) {
+        if let Some(content) = read(name).await {
+            for (recipe, description) in parse_justfile(&content) {
+                let command = format!("just {}", shell_escape(&recipe));
+                tasks.push(task(TaskSource::Just, &recipe, command, description, &join(root, name)));
+            }
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Find `name` among `tasks`: either a task ID (`npm:test`) or a bare name declared by exactly
+/// one source. Otherwise returns every task with that name (none, or several).
+fn resolve(tasks: Vec<TaskDef>, name: &str) -> Result<TaskDef, Vec<TaskDef>> {
+    if let Some(found) = tasks.iter().find(|task| task.id == name) {
+        return Ok(found.clone());
+    }
+    let mut matching: Vec<TaskDef> = tasks.into_iter().filter(|task| task.name == name).collect();
+    if matching.len() == 1 {
+        Ok(matching.remove(0))
+    } else {
+        Err(matching)
+    }
+}
+
+async fn start(
+    app: AppHandle,
+    connection_id: String,
+    root: String,
+    task: TaskDef,
+    env: BTreeMap<String, String>,
+) -> Result<TaskRun, TaskError> {
+    let problem_matchers = match task.source {
+        TaskSource::Cargo => vec![MatcherKind::Rustc],
+        TaskSource::Npm => vec![MatcherKind::Tsc, MatcherKind::Eslint],
+        // Anything could run; try every matcher.
+        TaskSource::Make | TaskSource::Just => Vec::new(),
+    };
+    let id = Uuid::new_v4().to_string();
+    let options = ExecOptions {
+        id: Some(id.clone()),
+        cwd: Some(root.clone()),
+        env: env.clone(),
+        problem_matchers: Some(problem_matchers),
+        ..ExecOptions::default()
+    };
+    // Registered before the command starts, so a quick finish cannot come first.
+    runs()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id.clone(), task.clone());
+    let done_id = id.clone();
+    let started = exec::start_with_done(app, connection_id.clone(), task.command.clone(), options, move |_| {
+        runs().lock().unwrap_or_else(|e| e.into_inner()).remove(&done_id);
+    })
+    .await;
+    let exec = match started {
+        Ok(exec) => exec,
+        Err(e) => {
+            runs().lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+            return Err(e.into());
+        }
+    };
+    last().lock().unwrap_or_else(|e| e.into_inner()).insert(
+        connection_id,
+        LastTask {
+            root,
+            task: task.clone(),
+            env,
+        },
+    );
+    Ok(TaskRun { task, exec })
+}
+
+/// Run the task `name` (a task ID or unique name) in `root`. Several tasks may run at once.
+pub async fn run(
+    app: AppHandle,
+    connection_id: String,
+    root: String,
+    name: &str,
+    env: BTreeMap<String, String>,
+) -> Result<TaskRun, TaskError> {
+    let task = resolve(discover(&app, &connection_id, &root).await?, name).map_err(|matching| {
+        if matching.is_empty() {
+            TaskError::NotFound(name.to_string())
+        } else {
+            let ids: Vec<&str> = matching.iter().map(|task| task.id.as_str()).collect();
+            TaskError::Ambiguous(name.to_string(), ids.join(", "))
+        }
+    })?;
+    start(app, connection_id, root, task, env).await
+}
+
+/// Run the connection's last task again, in the same root and with the same environment.
+pub async fn rerun(app: AppHandle, connection_id: String) -> Result<TaskRun, TaskError> {
+    let LastTask { root, task, env } = last()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&connection_id)
+        .cloned()
+        .ok_or(TaskError::NoLastTask)?;
+    start(app, connection_id, root, task, env).await
+}
+
+/// Tasks still running, optionally only those of one connection.
+pub fn running(connection_id: Option<&str>) -> Vec<TaskRun> {
+    // Already sorted by start time.
+    let execs = exec::list(connection_id);
+    let runs = runs().lock().unwrap_or_else(|e| e.into_inner());
+    execs
+        .into_iter()
+        .filter_map(|exec| {
+            let task = runs.get(&exec.id)?.clone();
+            Some(TaskRun { task, exec })
+        })
+        .collect()
+}
+
+/// Stop a running task by run ID. Returns `false` if it is not running.
+pub fn kill(id: &str) -> bool {
+    runs().lock().unwrap_or_else(|e| e.into_inner()).contains_key(id) && exec::cancel(id)
+}
+
+/// Forget the last task of a connection (on explicit disconnect).
+pub fn forget_connection(connection_id: &str) {
+    last().lock().unwrap_or_else(|e| e.into_inner()).remove(connection_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(list: &[(String, Option<String>)]) -> Vec<(&str, Option<&str>)> {
+        list.iter().map(|(name, text)| (name.as_str(), text.as_deref())).collect()
+    }
+
+    #[test]
+    fn makefile_targets() {
+        let content = "\
+CC := gcc
+VERSION ::= 1.0
+.PHONY: all test dist/clean
+
+all: build ## Build everything
+build: main.o
+\t$(CC) -o app main.o
+main.o: main.c
+test check:: all
+dist/clean:
+%.o: %.c
+$(OUT): all
+.DEFAULT_GOAL := all
+
+define RECIPE
+inside: define
+endef
+";
+        assert_eq!(
+            named(&parse_makefile(content)),
+            vec![
+                ("all", Some("Build everything")),
+                ("build", None),
+                ("test", None),
+                ("check", None),
+                ("dist/clean", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn makefile_keeps_first_definition() {
+        let content = "lint: ## First\nlint: extra\n";
+        assert_eq!(named(&parse_makefile(content)), vec![("lint", Some("First"))]);
+    }
+
+    #[test]
+    fn justfile_recipes() {
+        let content = "\
+set shell := [\"bash\", \"-c\"]
+alias b := build
+export RUST_LOG := \"info\"
+
+# Build the project
+build profile='dev':
+    cargo build --profile {{profile}}
+
+#!/usr/bin/env bash
+@test *args: build
+    cargo test {{args}}
+
+_helper:
+    true
+
+[private]
+secret:
+    true
+
+[group('ci')]
+# Run the linters
+lint:
+    cargo clippy
+version := \"1\"
+";
+        assert_eq!(
+            named(&parse_justfile(content)),
+            vec![("build", Some("Build the project")), ("test", None), ("lint", Some("Run the linters"))]
+        );
+    }
+
+    #[test]
+    fn package_json_scripts() {
+        let content = r#"{
+            "name": "app",
+            "scripts": { "build": "vite build", "test": "vitest run", "odd": 3 }
+        }"#;
+        let mut scripts = parse_package_json(content);
+        scripts.sort();
+        assert_eq!(
+            named(&scripts),
+            vec![("build", Some("vite build")), ("odd", None), ("test", Some("vitest run"))]
+        );
+    }
+
+    #[test]
+    fn package_json_without_scripts() {
+        assert!(parse_package_json(r#"{ "name": "app" }"#).is_empty());
+        assert!(parse_package_json(r#"{ "scripts": [] }"#).is_empty());
+        assert!(parse_package_json("{ not json").is_empty());
+    }
+
+    #[test]
+    fn cargo_tasks() {
+        assert_eq!(parse_cargo_toml("[package]\nname = \"app\"\n", true), CARGO_TASKS.to_vec());
+        assert_eq!(
+            parse_cargo_toml("[workspace]\nmembers = [\"a\"]\n", false),
+            vec!["build", "check", "test", "clippy"]
+        );
+        assert!(parse_cargo_toml("# empty\n", true).is_empty());
+    }
+}
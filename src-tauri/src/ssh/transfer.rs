@@ -0,0 +1,1166 @@
+//! Streaming file transfers between the server and local paths.
+//!
+//! A transfer copies one file, or a directory tree recursively. Files are streamed one after the
+//! other over a dedicated SFTP channel that keeps up to [`MAX_IN_FLIGHT`] chunk requests
+//! outstanding, so throughput is bound by bandwidth rather than round trips, and memory by the
+//! request window rather than the file size. Progress goes out as `transfer_progress` events.
+//!
+//! Like port forwards, transfers live in a global registry keyed by ID. A failed or cancelled
+//! transfer remembers which files are finished and how many bytes of the current one were
+//! committed in order at the destination, and [`resume`] continues from there on the connection's
+//! current session (e.g. after `ssh_reconnect`).
+
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::{map_sftp_error, SshError};
+use crate::state::AppState;
+use crate::trace::{emit_trace, TraceEvent};
+use russh_sftp::client::error::Error as SftpClientError;
+use russh_sftp::client::RawSftpSession;
+use russh_sftp::protocol::{FileAttributes, FileType, OpenFlags, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::{oneshot, watch, Mutex};
+use uuid::Uuid;
+
+/// Bytes per SFTP read/write request; 32 KiB is the packet size every server must accept.
+const CHUNK_SIZE: u32 = 32 * 1024;
+/// Chunk requests kept outstanding per transfer.
+const MAX_IN_FLIGHT: usize = 16;
+/// Applies to each SFTP request on the transfer's channel, never to the transfer as a whole.
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+/// Minimum spacing between `transfer_progress` events for one transfer.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// `name (1).ext` .. `name (N).ext` are tried for [`ConflictPolicy::Rename`].
+const MAX_RENAME_ATTEMPTS: u32 = 999;
+
+#[derive(Debug, Error)]
+pub enum TransferError {
+    #[error("Connection not found")]
+    ConnectionNotFound,
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error("Transfer not found")]
+    NotFound,
+    #[error("Transfer is {0:?} and cannot be resumed")]
+    NotResumable(TransferStatus),
+    #[error("Transfer was cancelled")]
+    Cancelled,
+    #[error("No free name next to {0}")]
+    NoFreeName(String),
+    #[error("Local file error: {0}")]
+    Local(#[from] std::io::Error),
+    #[error(transparent)]
+    Ssh(#[from] SshError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferDirection {
+    /// Remote file or directory to a local path
+    Download,
+    /// Local file or directory to a remote path
+    Upload,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// What to do when a destination file already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    #[default]
+    Overwrite,
+    /// Keep the existing file and move on
+    Skip,
+    /// Keep both, writing to `name (1).ext`, `name (2).ext`, ...
+    Rename,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferOptions {
+    pub conflict: ConflictPolicy,
+    /// Give each destination file its source's modification time (best effort)
+    pub preserve_mtime: bool,
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        Self {
+            conflict: ConflictPolicy::Overwrite,
+            preserve_mtime: true,
+        }
+    }
+}
+
+/// Snapshot of a transfer; also the `transfer_progress` event payload.
+///
+/// `remote_path` and `local_path` are the roots: for a directory, the destination root receives
+/// the source's contents and existing directories are merged into.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferInfo {
+    pub id: String,
+    pub connection_id: String,
+    pub direction: TransferDirection,
+    pub status: TransferStatus,
+    pub remote_path: String,
+    pub local_path: String,
+    pub options: TransferOptions,
+    /// Bytes committed at the destination, over all files
+    pub bytes_transferred: u64,
+    /// Size of everything to copy, excluding skipped files; `None` until the source has been
+    /// walked, or if the server did not report a size.
+    pub total_bytes: Option<u64>,
+    /// Bytes already in place when the current run started (non-zero after a resume)
+    pub start_offset: u64,
+    /// Average rate of the current run.
+    pub bytes_per_second: u64,
+    pub files_total: u64,
+    pub files_done: u64,
+    /// Files left alone under [`ConflictPolicy::Skip`]
+    pub files_skipped: u64,
+    /// Source path of the file being copied
+    pub current_file: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub error: Option<String>,
+}
+
+impl TransferInfo {
+    fn new(
+        connection_id: String,
+        direction: TransferDirection,
+        remote_path: String,
+        local_path: String,
+        options: TransferOptions,
+    ) -> Self {
+        let now = now_ms();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            connection_id,
+            direction,
+            status: TransferStatus::Running,
+            remote_path,
+            local_path,
+            options,
+            bytes_transferred: 0,
+            total_bytes: None,
+            start_offset: 0,
+            bytes_per_second: 0,
+            files_total: 0,
+            files_done: 0,
+            files_skipped: 0,
+            current_file: None,
+            created_at: now,
+            updated_at: now,
+            error: None,
+        }
+    }
+
+    fn destination_root(&self) -> &str {
+        match self.direction {
+            TransferDirection::Download => &self.local_path,
+            TransferDirection::Upload => &self.remote_path,
+        }
+    }
+}
+
+/// Work already done, so that [`resume`] neither repeats it nor re-applies the conflict policy.
+/// Files are keyed by their `/`-separated path relative to the source root (`""` for a file root).
+#[derive(Default)]
+struct Checkpoint {
+    done: HashSet<String>,
+    skipped: HashSet<String>,
+    /// Bytes of the files in `done`
+    done_bytes: u64,
+    current: Option<PartialFile>,
+}
+
+/// The file in progress. Its destination is kept because a resume must continue writing there
+/// rather than treat its own partial output as a conflict.
+struct PartialFile {
+    rel: String,
+    destination: String,
+    committed: u64,
+}
+
+impl Checkpoint {
+    /// A fresh transfer that continues a partial file already at `destination`.
+    fn continuing(destination: &str, offset: u64) -> Self {
+        Self {
+            current: (offset > 0).then(|| PartialFile {
+                rel: String::new(),
+                destination: destination.to_string(),
+                committed: offset,
+            }),
+            ..Self::default()
+        }
+    }
+}
+
+struct TransferEntry {
+    info: TransferInfo,
+    checkpoint: Checkpoint,
+    cancel: watch::Sender<bool>,
+}
+
+static TRANSFERS: OnceLock<StdMutex<HashMap<String, TransferEntry>>> = OnceLock::new();
+
+fn registry() -> &'static StdMutex<HashMap<String, TransferEntry>> {
+    TRANSFERS.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Apply `update` to a registered transfer and return the new snapshot.
+fn update(id: &str, update: impl FnOnce(&mut TransferInfo, &mut Checkpoint)) -> Option<TransferInfo> {
+    let mut transfers = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let entry = transfers.get_mut(id)?;
+    update(&mut entry.info, &mut entry.checkpoint);
+    entry.info.updated_at = now_ms();
+    Some(entry.info.clone())
+}
+
+fn emit_progress(app: &AppHandle, info: &TransferInfo) {
+    if let Err(e) = app.emit("transfer_progress", info) {
+        log::error!("Failed to emit transfer progress: {}", e);
+    }
+}
+
+/// Ask the connection's current actor for a dedicated SFTP session.
+async fn open_sftp_channel(app: &AppHandle, connection_id: &str) -> Result<Arc<RawSftpSession>, TransferError> {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    }
+    .ok_or(TransferError::ConnectionNotFound)?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(ConnectionRequest::OpenSftpChannel {
+        timeout_secs: REQUEST_TIMEOUT_SECS,
+        respond_to,
+    })
+    .await
+    .map_err(|_| TransferError::ConnectionClosed)?;
+    let sftp = rx.await.map_err(|_| TransferError::ConnectionClosed)??;
+    Ok(Arc::new(sftp))
+}
+
+/// Drop the actor's cached listings of `path`, its subtree and its parent directory, which an
+/// upload just changed.
+async fn invalidate_listing(app: &AppHandle, connection_id: &str, path: &str) {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    };
+    let Some(tx) = tx else { return };
+    let (respond_to, _) = oneshot::channel();
+    let _ = tx
+        .send(ConnectionRequest::InvalidateDirCache {
+            paths: vec![path.to_string()],
+            respond_to,
+        })
+        .await;
+}
+
+fn remote_join(root: &str, rel: &str) -> String {
+    if rel.is_empty() {
+        root.to_string()
+    } else {
+        format!("{}/{}", root.trim_end_matches('/'), rel)
+    }
+}
+
+fn local_join(root: &str, rel: &str) -> PathBuf {
+    rel.split('/')
+        .filter(|part| !part.is_empty())
+        .fold(PathBuf::from(root), |path, part| path.join(part))
+}
+
+fn source_path(info: &TransferInfo, rel: &str) -> String {
+    match info.direction {
+        TransferDirection::Download => remote_join(&info.remote_path, rel),
+        TransferDirection::Upload => local_join(&info.local_path, rel).to_string_lossy().into_owned(),
+    }
+}
+
+fn destination_path(info: &TransferInfo, rel: &str) -> String {
+    match info.direction {
+        TransferDirection::Download => local_join(&info.local_path, rel).to_string_lossy().into_owned(),
+        TransferDirection::Upload => remote_join(&info.remote_path, rel),
+    }
+}
+
+/// `dir/name (n).ext`; the extension is whatever follows the last dot, except a leading one.
+fn numbered(path: &str, n: u32) -> String {
+    let (dir, name) = match path.rfind(['/', '\\']) {
+        Some(i) => path.split_at(i + 1),
+        None => ("", path),
+    };
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => format!("{}{} ({}){}", dir, &name[..dot], n, &name[dot..]),
+        _ => format!("{}{} ({})", dir, name, n),
+    }
+}
+
+fn is_status(error: &SftpClientError, code: StatusCode) -> bool {
+    matches!(error, SftpClientError::Status(status) if status.status_code == code)
+}
+
+pub(crate) fn is_eof(error: &SftpClientError) -> bool {
+    is_status(error, StatusCode::Eof)
+}
+
+fn unix_secs(time: std::io::Result<SystemTime>) -> Option<u32> {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as u32)
+}
+
+struct SourceFile {
+    rel: String,
+    size: Option<u64>,
+    mtime: Option<u32>,
+    atime: Option<u32>,
+}
+
+/// Everything under a source root: directories parents-first, then files.
+#[derive(Default)]
+struct Plan {
+    dirs: Vec<String>,
+    files: Vec<SourceFile>,
+}
+
+impl Plan {
+    fn sort(&mut self) {
+        self.dirs.sort();
+        self.files.sort_by(|a, b| a.rel.cmp(&b.rel));
+    }
+}
+
+fn child_rel(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+/// Symlinks to files are copied as files; symlinked directories are skipped, since following
+/// them can loop. Other special files are skipped too.
+async fn plan_local(root: &str) -> Result<Plan, TransferError> {
+    let local_source = |rel: String, meta: &std::fs::Metadata| SourceFile {
+        rel,
+        size: Some(meta.len()),
+        mtime: unix_secs(meta.modified()),
+        atime: unix_secs(meta.accessed()),
+    };
+
+    let meta = tokio::fs::metadata(root).await?;
+    let mut plan = Plan::default();
+    if !meta.is_dir() {
+        plan.files.push(local_source(String::new(), &meta));
+        return Ok(plan);
+    }
+
+    plan.dirs.push(String::new());
+    let mut stack = vec![String::new()];
+    while let Some(dir) = stack.pop() {
+        let mut entries = tokio::fs::read_dir(local_join(root, &dir)).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let rel = child_rel(&dir, &entry.file_name().to_string_lossy());
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                plan.dirs.push(rel.clone());
+                stack.push(rel);
+            } else if file_type.is_file() {
+                plan.files.push(local_source(rel, &entry.metadata().await?));
+            } else if file_type.is_symlink() {
+                if let Ok(meta) = tokio::fs::metadata(entry.path()).await {
+                    if meta.is_file() {
+                        plan.files.push(local_source(rel, &meta));
+                    }
+                }
+            }
+        }
+    }
+    plan.sort();
+    Ok(plan)
+}
+
+pub(crate) async fn list_remote_dir(sftp: &RawSftpSession, path: &str) -> Result<Vec<(String, FileAttributes)>, SshError> {
+    let handle = sftp.opendir(path).await.map_err(map_sftp_error)?.handle;
+    let mut entries = Vec::new();
+    let listed = loop {
+        match sftp.readdir(handle.as_str()).await {
+            Ok(name) => entries.extend(
+                name.files
+                    .into_iter()
+                    .filter(|f| f.filename != "." && f.filename != "..")
+                    .map(|f| (f.filename, f.attrs)),
+            ),
+            Err(e) if is_eof(&e) => break Ok(entries),
+            Err(e) => break Err(map_sftp_error(e)),
+        }
+    };
+    let _ = sftp.close(handle).await;
+    listed
+}
+
+/// Same rules as [`plan_local`].
+async fn plan_remote(sftp: &RawSftpSession, root: &str) -> Result<Plan, TransferError> {
+    let remote_source = |rel: String, attrs: &FileAttributes| SourceFile {
+        rel,
+        size: attrs.size,
+        mtime: attrs.mtime,
+        atime: attrs.atime,
+    };
+
+    let attrs = sftp.stat(root).await.map_err(map_sftp_error)?.attrs;
+    let mut plan = Plan::default();
+    if attrs.file_type() != FileType::Dir {
+        plan.files.push(remote_source(String::new(), &attrs));
+        return Ok(plan);
+    }
+
+    plan.dirs.push(String::new());
+    let mut stack = vec![String::new()];
+    while let Some(dir) = stack.pop() {
+        for (name, attrs) in list_remote_dir(sftp, &remote_join(root, &dir)).await? {
+            let rel = child_rel(&dir, &name);
+            match attrs.file_type() {
+                FileType::Dir => {
+                    plan.dirs.push(rel.clone());
+                    stack.push(rel);
+                }
+                FileType::File => plan.files.push(remote_source(rel, &attrs)),
+                FileType::Symlink => {
+                    if let Ok(target) = sftp.stat(remote_join(root, &rel)).await {
+                        if target.attrs.file_type() == FileType::File {
+                            plan.files.push(remote_source(rel, &target.attrs));
+                        }
+                    }
+                }
+                FileType::Other => {}
+            }
+        }
+    }
+    plan.sort();
+    Ok(plan)
+}
+
+async fn destination_exists(sftp: &RawSftpSession, direction: TransferDirection, path: &str) -> Result<bool, TransferError> {
+    match direction {
+        TransferDirection::Download => match tokio::fs::symlink_metadata(path).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        },
+        TransferDirection::Upload => match sftp.lstat(path).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_status(&e, StatusCode::NoSuchFile) => Ok(false),
+            Err(e) => Err(map_sftp_error(e).into()),
+        },
+    }
+}
+
+/// Where `rel` should be written under the conflict policy; `None` to skip it.
+async fn resolve_destination(
+    sftp: &RawSftpSession,
+    info: &TransferInfo,
+    rel: &str,
+) -> Result<Option<String>, TransferError> {
+    let destination = destination_path(info, rel);
+    if !destination_exists(sftp, info.direction, &destination).await? {
+        return Ok(Some(destination));
+    }
+    match info.options.conflict {
+        ConflictPolicy::Overwrite => Ok(Some(destination)),
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::Rename => {
+            for n in 1..=MAX_RENAME_ATTEMPTS {
+                let candidate = numbered(&destination, n);
+                if !destination_exists(sftp, info.direction, &candidate).await? {
+                    return Ok(Some(candidate));
+                }
+            }
+            Err(TransferError::NoFreeName(destination))
+        }
+    }
+}
+
+async fn create_destination_dir(sftp: &RawSftpSession, info: &TransferInfo, rel: &str) -> Result<(), TransferError> {
+    let path = destination_path(info, rel);
+    match info.direction {
+        TransferDirection::Download => Ok(tokio::fs::create_dir_all(&path).await?),
+        TransferDirection::Upload => match sftp.mkdir(path.as_str(), FileAttributes::empty()).await {
+            Ok(_) => Ok(()),
+            // Most servers report an existing directory as a generic failure; merge into it.
+            Err(e) => match sftp.stat(path.as_str()).await {
+                Ok(existing) if existing.attrs.file_type() == FileType::Dir => Ok(()),
+                _ => Err(map_sftp_error(e).into()),
+            },
+        },
+    }
+}
+
+/// Best effort: a server that refuses `setstat`, or a filesystem without mtimes, does not fail
+/// the transfer.
+async fn preserve_mtime(sftp: &RawSftpSession, direction: TransferDirection, destination: &str, file: &SourceFile) {
+    let Some(mtime) = file.mtime else {
+        return;
+    };
+    let result = match direction {
+        TransferDirection::Download => {
+            let path = PathBuf::from(destination);
+            tokio::task::spawn_blocking(move || {
+                let file = std::fs::OpenOptions::new().write(true).open(path)?;
+                file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime as u64))
+            })
+            .await
+            .map_err(std::io::Error::other)
+            .and_then(|r| r)
+            .map_err(|e| e.to_string())
+        }
+        TransferDirection::Upload => {
+            // SFTP v3 sets access and modification time together.
+            let attrs = FileAttributes {
+                atime: Some(file.atime.unwrap_or(mtime)),
+                mtime: Some(mtime),
+                ..FileAttributes::empty()
+            };
+            sftp.setstat(destination, attrs).await.map(|_| ()).map_err(|e| e.to_string())
+        }
+    };
+    if let Err(e) = result {
+        log::warn!("Could not preserve mtime of {}: {}", destination, e);
+    }
+}
+
+/// An open source/destination pair, positioned to continue at `offset`.
+struct Prepared {
+    sftp: Arc<RawSftpSession>,
+    handle: String,
+    local: File,
+    offset: u64,
+    total: Option<u64>,
+}
+
+/// The requested offset is clamped to what the destination actually holds, so a resume never
+/// leaves a hole.
+async fn prepare_download(
+    sftp: &Arc<RawSftpSession>,
+    remote: &str,
+    local: &str,
+    requested_offset: u64,
+) -> Result<Prepared, TransferError> {
+    let handle = sftp
+        .open(remote, OpenFlags::READ, FileAttributes::empty())
+        .await
+        .map_err(map_sftp_error)?
+        .handle;
+    let total = sftp.fstat(handle.as_str()).await.ok().and_then(|a| a.attrs.size);
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(local)
+        .await?;
+    let present = file.metadata().await?.len();
+    let offset = requested_offset.min(present).min(total.unwrap_or(u64::MAX));
+    file.set_len(offset).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+
+    Ok(Prepared {
+        sftp: sftp.clone(),
+        handle,
+        local: file,
+        offset,
+        total,
+    })
+}
+
+/// See [`prepare_download`].
+async fn prepare_upload(
+    sftp: &Arc<RawSftpSession>,
+    local: &str,
+    remote: &str,
+    requested_offset: u64,
+) -> Result<Prepared, TransferError> {
+    let mut file = File::open(local).await?;
+    let total = file.metadata().await?.len();
+
+    let flags = if requested_offset == 0 {
+        OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE
+    } else {
+        OpenFlags::WRITE | OpenFlags::CREATE
+    };
+    let handle = sftp
+        .open(remote, flags, FileAttributes::empty())
+        .await
+        .map_err(map_sftp_error)?
+        .handle;
+    let present = if requested_offset == 0 {
+        0
+    } else {
+        sftp.fstat(handle.as_str())
+            .await
+            .map_err(map_sftp_error)?
+            .attrs
+            .size
+            .unwrap_or(0)
+    };
+    let offset = requested_offset.min(present).min(total);
+    file.seek(SeekFrom::Start(offset)).await?;
+
+    Ok(Prepared {
+        sftp: sftp.clone(),
+        handle,
+        local: file,
+        offset,
+        total: Some(total),
+    })
+}
+
+/// Copy `remote_path` (a file or directory) to `local_path`. A non-zero `offset` continues a
+/// partial download of a single file already present at `local_path`.
+pub async fn start_download(
+    app: AppHandle,
+    connection_id: String,
+    remote_path: String,
+    local_path: String,
+    offset: u64,
+    options: TransferOptions,
+) -> Result<TransferInfo, TransferError> {
+    let sftp = open_sftp_channel(&app, &connection_id).await?;
+    // Report a missing source to the caller rather than only through events.
+    sftp.stat(remote_path.as_str()).await.map_err(map_sftp_error)?;
+
+    let info = TransferInfo::new(connection_id, TransferDirection::Download, remote_path, local_path, options);
+    let checkpoint = Checkpoint::continuing(info.destination_root(), offset);
+    Ok(launch(app, info, checkpoint, sftp))
+}
+
+/// Copy `local_path` (a file or directory) to `remote_path`. A non-zero `offset` continues a
+/// partial upload of a single file already present at `remote_path`.
+pub async fn start_upload(
+    app: AppHandle,
+    connection_id: String,
+    local_path: String,
+    remote_path: String,
+    offset: u64,
+    options: TransferOptions,
+) -> Result<TransferInfo, TransferError> {
+    tokio::fs::metadata(&local_path).await?;
+    let sftp = open_sftp_channel(&app, &connection_id).await?;
+
+    let info = TransferInfo::new(connection_id, TransferDirection::Upload, remote_path, local_path, options);
+    let checkpoint = Checkpoint::continuing(info.destination_root(), offset);
+    Ok(launch(app, info, checkpoint, sftp))
+}
+
+/// Restart a failed or cancelled transfer, skipping finished files and continuing the one that
+/// was interrupted from the bytes it already committed.
+pub async fn resume(app: AppHandle, id: &str) -> Result<TransferInfo, TransferError> {
+    let info = {
+        let mut transfers = registry().lock().unwrap_or_else(|e| e.into_inner());
+        let entry = transfers.get_mut(id).ok_or(TransferError::NotFound)?;
+        match entry.info.status {
+            TransferStatus::Failed | TransferStatus::Cancelled => {}
+            status => return Err(TransferError::NotResumable(status)),
+        }
+        // Claim the transfer so a concurrent resume is refused; a cancel from here on sticks.
+        entry.info.status = TransferStatus::Running;
+        entry.info.error = None;
+        entry.cancel.send_replace(false);
+        entry.info.clone()
+    };
+
+    match open_sftp_channel(&app, &info.connection_id).await {
+        Ok(sftp) => Ok(launch(app, info, Checkpoint::default(), sftp)),
+        Err(e) => {
+            finish(&app, id, TransferStatus::Failed, Some(e.to_string()));
+            Err(e)
+        }
+    }
+}
+
+/// Register (or, on resume, reuse) the entry and spawn the run. A resumed entry keeps its
+/// checkpoint; `checkpoint` only seeds new ones.
+fn launch(app: AppHandle, info: TransferInfo, checkpoint: Checkpoint, sftp: Arc<RawSftpSession>) -> TransferInfo {
+    let (info, mut cancelled) = {
+        let mut transfers = registry().lock().unwrap_or_else(|e| e.into_inner());
+        let entry = transfers.entry(info.id.clone()).or_insert_with(|| TransferEntry {
+            info,
+            checkpoint,
+            cancel: watch::channel(false).0,
+        });
+        let already = entry.checkpoint.done_bytes + entry.checkpoint.current.as_ref().map_or(0, |c| c.committed);
+        entry.info.status = TransferStatus::Running;
+        entry.info.start_offset = already;
+        entry.info.bytes_transferred = already;
+        entry.info.bytes_per_second = 0;
+        entry.info.updated_at = now_ms();
+        (entry.info.clone(), entry.cancel.subscribe())
+    };
+
+    emit_trace(
+        &app,
+        TraceEvent::new("transfer", "start", "Transfer started")
+            .with_correlation_id(&info.id)
+            .with_detail(format!(
+                "{:?} {} <-> {} from byte {} via {}",
+                info.direction, info.remote_path, info.local_path, info.start_offset, info.connection_id
+            )),
+    );
+    emit_progress(&app, &info);
+
+    let task_app = app.clone();
+    let id = info.id.clone();
+    tauri::async_runtime::spawn(async move {
+        match run(&task_app, &id, sftp, &mut cancelled).await {
+            Ok(()) => finish(&task_app, &id, TransferStatus::Completed, None),
+            Err(TransferError::Cancelled) => finish(&task_app, &id, TransferStatus::Cancelled, None),
+            Err(e) => finish(&task_app, &id, TransferStatus::Failed, Some(e.to_string())),
+        }
+    });
+
+    info
+}
+
+async fn run(
+    app: &AppHandle,
+    id: &str,
+    sftp: Arc<RawSftpSession>,
+    cancelled: &mut watch::Receiver<bool>,
+) -> Result<(), TransferError> {
+    let info = update(id, |_, _| {}).ok_or(TransferError::NotFound)?;
+    let plan = match info.direction {
+        TransferDirection::Download => plan_remote(&sftp, &info.remote_path).await?,
+        TransferDirection::Upload => plan_local(&info.local_path).await?,
+    };
+
+    let mut progress = Progress::new(app.clone(), id.to_string(), info.start_offset);
+    progress.report(update(id, |info, checkpoint| {
+        info.files_total = plan.files.len() as u64;
+        info.total_bytes = plan
+            .files
+            .iter()
+            .filter(|f| !checkpoint.skipped.contains(&f.rel))
+            .map(|f| f.size)
+            .sum();
+    }));
+
+    for dir in &plan.dirs {
+        create_destination_dir(&sftp, &info, dir).await?;
+    }
+    if info.direction == TransferDirection::Upload && !plan.dirs.is_empty() {
+        invalidate_listing(app, &info.connection_id, &info.remote_path).await;
+    }
+
+    for file in &plan.files {
+        if *cancelled.borrow() {
+            return Err(TransferError::Cancelled);
+        }
+
+        let (finished, partial) = {
+            let transfers = registry().lock().unwrap_or_else(|e| e.into_inner());
+            let checkpoint = &transfers.get(id).ok_or(TransferError::NotFound)?.checkpoint;
+            let finished = checkpoint.done.contains(&file.rel) || checkpoint.skipped.contains(&file.rel);
+            let partial = checkpoint
+                .current
+                .as_ref()
+                .filter(|c| c.rel == file.rel)
+                .map(|c| (c.destination.clone(), c.committed));
+            (finished, partial)
+        };
+        if finished {
+            continue;
+        }
+
+        let (destination, offset) = match partial {
+            Some(partial) => partial,
+            None => match resolve_destination(&sftp, &info, &file.rel).await? {
+                Some(destination) => (destination, 0),
+                None => {
+                    progress.report(update(id, |info, checkpoint| {
+                        checkpoint.skipped.insert(file.rel.clone());
+                        info.files_skipped += 1;
+                        info.total_bytes = info.total_bytes.map(|t| t.saturating_sub(file.size.unwrap_or(0)));
+                    }));
+                    continue;
+                }
+            },
+        };
+
+        let source = source_path(&info, &file.rel);
+        update(id, |info, checkpoint| {
+            checkpoint.current = Some(PartialFile {
+                rel: file.rel.clone(),
+                destination: destination.clone(),
+                committed: offset,
+            });
+            info.current_file = Some(source.clone());
+        });
+
+        let committed = match info.direction {
+            TransferDirection::Download => {
+                let prepared = prepare_download(&sftp, &source, &destination, offset).await?;
+                download(prepared, &mut progress, cancelled).await?
+            }
+            TransferDirection::Upload => {
+                let prepared = prepare_upload(&sftp, &source, &destination, offset).await?;
+                upload(prepared, &mut progress, cancelled).await?
+            }
+        };
+        if info.options.preserve_mtime {
+            preserve_mtime(&sftp, info.direction, &destination, file).await;
+        }
+        if info.direction == TransferDirection::Upload {
+            invalidate_listing(app, &info.connection_id, &destination).await;
+        }
+
+        progress.report(update(id, |info, checkpoint| {
+            checkpoint.done.insert(file.rel.clone());
+            checkpoint.done_bytes += committed;
+            checkpoint.current = None;
+            info.files_done += 1;
+            info.current_file = None;
+        }));
+    }
+    Ok(())
+}
+
+fn finish(app: &AppHandle, id: &str, status: TransferStatus, error: Option<String>) {
+    let Some(info) = update(id, |info, _| {
+        info.status = status;
+        info.error = error;
+    }) else {
+        return;
+    };
+
+    let event = TraceEvent::new("transfer", "finish", &format!("Transfer {:?}", status)).with_correlation_id(id);
+    let event = match &info.error {
+        Some(error) => event
+            .with_detail(format!(
+                "{} bytes, {} files: {}",
+                info.bytes_transferred, info.files_done, error
+            ))
+            .error(),
+        None => event.with_detail(format!("{} bytes, {} files", info.bytes_transferred, info.files_done)),
+    };
+    emit_trace(app, event);
+    emit_progress(app, &info);
+
+    // A completed transfer can't be resumed, so nothing needs its checkpoint once the final
+    // progress event is out. Failed and cancelled ones stay until resumed or cleared.
+    if status == TransferStatus::Completed {
+        registry().lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+    }
+}
+
+/// Records committed bytes in the registry and throttles `transfer_progress` events.
+struct Progress {
+    app: AppHandle,
+    id: String,
+    start_offset: u64,
+    started: Instant,
+    last_emit: Instant,
+}
+
+impl Progress {
+    fn new(app: AppHandle, id: String, start_offset: u64) -> Self {
+        let now = Instant::now();
+        Self {
+            app,
+            id,
+            start_offset,
+            started: now,
+            last_emit: now,
+        }
+    }
+
+    /// `committed` counts bytes of the current file only.
+    fn advance(&mut self, committed: u64) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let start_offset = self.start_offset;
+        let info = update(&self.id, |info, checkpoint| {
+            if let Some(current) = checkpoint.current.as_mut() {
+                current.committed = committed;
+            }
+            info.bytes_transferred = checkpoint.done_bytes + committed;
+            info.bytes_per_second = if elapsed > 0.0 {
+                (info.bytes_transferred.saturating_sub(start_offset) as f64 / elapsed) as u64
+            } else {
+                0
+            };
+        });
+        self.report(info);
+    }
+
+    fn report(&mut self, info: Option<TransferInfo>) {
+        if let Some(info) = info {
+            if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+                self.last_emit = Instant::now();
+                emit_progress(&self.app, &info);
+            }
+        }
+    }
+}
+
+/// In-flight chunk requests in file order, each tagged with its offset (reads) or length (writes).
+type Pending<T> = VecDeque<(u64, JoinHandle<Result<T, SftpClientError>>)>;
+
+fn abort_all<T>(pending: Pending<T>) {
+    for (_, task) in pending {
+        task.abort();
+    }
+}
+
+/// Returns the destination file's final length.
+async fn download(
+    prepared: Prepared,
+    progress: &mut Progress,
+    cancelled: &mut watch::Receiver<bool>,
+) -> Result<u64, TransferError> {
+    let Prepared {
+        sftp,
+        handle,
+        mut local,
+        offset,
+        total,
+    } = prepared;
+
+    // Consumed in order, so data is always written to the local file sequentially.
+    let mut pending: Pending<Vec<u8>> = VecDeque::new();
+    let mut next = offset;
+    let mut committed = offset;
+    let mut eof = false;
+
+    let result = 'pump: loop {
+        while !eof && pending.len() < MAX_IN_FLIGHT && total.map_or(true, |t| next < t) {
+            let (sftp, handle, at) = (sftp.clone(), handle.clone(), next);
+            let task = tauri::async_runtime::spawn(async move {
+                sftp.read(handle, at, CHUNK_SIZE).await.map(|d| d.data)
+            });
+            pending.push_back((at, task));
+            next += CHUNK_SIZE as u64;
+        }
+
+        let Some((chunk_offset, mut task)) = pending.pop_front() else {
+            break Ok(());
+        };
+        let joined = tokio::select! {
+            joined = &mut task => joined,
+            Ok(_) = cancelled.wait_for(|c| *c) => {
+                task.abort();
+                break 'pump Err(TransferError::Cancelled);
+            }
+        };
+        let mut data = match joined {
+            Ok(Ok(data)) => data,
+            Ok(Err(e)) if is_eof(&e) => {
+                eof = true;
+                Vec::new()
+            }
+            Ok(Err(e)) => break Err(map_sftp_error(e).into()),
+            Err(e) => break Err(SshError::SftpError(e.to_string()).into()),
+        };
+
+        // Servers may return fewer bytes than asked for before EOF; fill the gap before moving on.
+        while !eof && data.len() < CHUNK_SIZE as usize {
+            let at = chunk_offset + data.len() as u64;
+            match sftp.read(handle.as_str(), at, CHUNK_SIZE - data.len() as u32).await {
+                Ok(more) if !more.data.is_empty() => data.extend_from_slice(&more.data),
+                Ok(_) => eof = true,
+                Err(e) if is_eof(&e) => eof = true,
+                Err(e) => break 'pump Err(map_sftp_error(e).into()),
+            }
+        }
+
+        if let Err(e) = local.write_all(&data).await {
+            break Err(e.into());
+        }
+        committed += data.len() as u64;
+        progress.advance(committed);
+
+        if eof {
+            break Ok(());
+        }
+    };
+
+    abort_all(pending);
+    let _ = sftp.close(handle).await;
+    result?;
+
+    local.flush().await?;
+    local.sync_all().await?;
+    Ok(committed)
+}
+
+/// Read up to one chunk, short only at end of file.
+async fn read_chunk(local: &mut File) -> std::io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; CHUNK_SIZE as usize];
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = local.read(&mut buf[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    buf.truncate(filled);
+    Ok(buf)
+}
+
+/// Returns the destination file's final length.
+async fn upload(
+    prepared: Prepared,
+    progress: &mut Progress,
+    cancelled: &mut watch::Receiver<bool>,
+) -> Result<u64, TransferError> {
+    let Prepared {
+        sftp,
+        handle,
+        mut local,
+        offset,
+        ..
+    } = prepared;
+
+    // `committed` only advances past chunks acknowledged in order.
+    let mut pending: Pending<()> = VecDeque::new();
+    let mut next = offset;
+    let mut committed = offset;
+    let mut local_eof = false;
+
+    let result = 'pump: loop {
+        while !local_eof && pending.len() < MAX_IN_FLIGHT {
+            let chunk = match read_chunk(&mut local).await {
+                Ok(chunk) => chunk,
+                Err(e) => break 'pump Err(e.into()),
+            };
+            if chunk.is_empty() {
+                local_eof = true;
+                break;
+            }
+            let len = chunk.len() as u64;
+            let (sftp, handle, at) = (sftp.clone(), handle.clone(), next);
+            let task = tauri::async_runtime::spawn(async move {
+                sftp.write(handle, at, chunk).await.map(|_| ())
+            });
+            pending.push_back((len, task));
+            next += len;
+        }
+
+        let Some((len, mut task)) = pending.pop_front() else {
+            break Ok(());
+        };
+        let joined = tokio::select! {
+            joined = &mut task => joined,
+            Ok(_) = cancelled.wait_for(|c| *c) => {
+                task.abort();
+                break 'pump Err(TransferError::Cancelled);
+            }
+        };
+        match joined {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => break Err(map_sftp_error(e).into()),
+            Err(e) => break Err(SshError::SftpError(e.to_string()).into()),
+        }
+
+        committed += len;
+        progress.advance(committed);
+    };
+
+    abort_all(pending);
+    if let Err(e) = result {
+        let _ = sftp.close(handle).await;
+        return Err(e);
+    }
+
+    // A resumed upload may land on a longer partial file; cut it to what was sent.
+    if offset > 0 {
+        let attrs = FileAttributes {
+            size: Some(next),
+            ..FileAttributes::empty()
+        };
+        sftp.fsetstat(handle.as_str(), attrs).await.map_err(map_sftp_error)?;
+    }
+    // Servers may report deferred write errors on close.
+    sftp.close(handle).await.map_err(map_sftp_error)?;
+    Ok(committed)
+}
+
+/// Transfers, optionally only those of one connection.
+pub fn list(connection_id: Option<&str>) -> Vec<TransferInfo> {
+    let transfers = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let mut infos: Vec<TransferInfo> = transfers
+        .values()
+        .filter(|entry| connection_id.map_or(true, |id| entry.info.connection_id == id))
+        .map(|entry| entry.info.clone())
+        .collect();
+    infos.sort_by_key(|info| info.created_at);
+    infos
+}
+
+/// Ask a running transfer to stop; it reports `cancelled` once its in-flight requests are dropped.
+/// The committed bytes are kept, so the transfer can still be resumed.
+pub fn cancel(id: &str) -> Option<TransferInfo> {
+    let transfers = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let entry = transfers.get(id)?;
+    if entry.info.status == TransferStatus::Running {
+        entry.cancel.send_replace(true);
+    }
+    Some(entry.info.clone())
+}
+
+/// Forget failed and cancelled transfers (optionally only those of one connection), giving up the
+/// ability to resume them. Returns the IDs removed.
+pub fn clear(connection_id: Option<&str>) -> Vec<String> {
+    let mut transfers = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let ids: Vec<String> = transfers
+        .values()
+        .filter(|entry| entry.info.status != TransferStatus::Running)
+        .filter(|entry| connection_id.map_or(true, |id| entry.info.connection_id == id))
+        .map(|entry| entry.info.id.clone())
+        .collect();
+    for id in &ids {
+        transfers.remove(id);
+    }
+    ids
+}
+
+/// Cancel all running transfers of a connection (on explicit disconnect).
+pub fn cancel_for_connection(connection_id: &str) {
+    let transfers = registry().lock().unwrap_or_else(|e| e.into_inner());
+    for entry in transfers.values() {
+        if entry.info.connection_id == connection_id && entry.info.status == TransferStatus::Running {
+            entry.cancel.send_replace(true);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Copies, moves and recursive deletes of remote trees.
+//!
+//! All of them run on their own SFTP session (and exec channel for `cp`) rather than in the
+//! connection actor: a large tree, or a copy the server cannot do itself and that streams every
+//! byte through this client, must not hold up the connection's other requests for its duration.
+//! A copy prefers the server's `copy-data` extension, then `cp -Rp` over an exec channel, and
+//! falls back to SFTP reads and writes. A move tries `rename` first and only copies when that
+//! fails (e.g. across filesystems). A delete given an operation ID can be cancelled with
+//! [`cancel_delete`] between entries.
+
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::{map_sftp_error, SshError};
+use crate::ssh::pty::shell_escape;
+use crate::ssh::sftp::{self, CopyReport, CopyStrategy, DeleteReport, MoveReport, SftpExtensions};
+use russh::ChannelMsg;
+use russh_sftp::client::error::Error as SftpClientError;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::StatusCode;
+use std::collections::HashMap;
+use std::sync::{Mutex as StdMutex, OnceLock};
+use tokio::sync::{mpsc, oneshot, watch};
+
+/// Applies to each SFTP request on the operation's session, never to the operation as a whole.
+const REQUEST_TIMEOUT_SECS: u64 = 180;
+/// Output of `cp` kept for error messages.
+const COMMAND_OUTPUT_LIMIT: usize = 4096;
+
+/// Cancel senders of running deletes, by the caller's operation ID.
+static DELETES: OnceLock<StdMutex<HashMap<String, watch::Sender<bool>>>> = OnceLock::new();
+
+fn deletes() -> &'static StdMutex<HashMap<String, watch::Sender<bool>>> {
+    DELETES.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+/// Delete `path` and everything under it, or with `dry_run` only list what would go. With an
+/// `operation_id`, [`cancel_delete`] stops it with `SshError::Cancelled`.
+pub async fn delete(
+    tx: &mpsc::Sender<ConnectionRequest>,
+    path: &str,
+    dry_run: bool,
+    operation_id: Option<&str>,
+) -> Result<DeleteReport, SshError> {
+    let (cancel, cancelled) = watch::channel(false);
+    if let Some(id) = operation_id {
+        deletes()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id.to_string(), cancel);
+    }
+
+    let result = match open_session(tx, false).await {
+        Ok((sftp, _)) => sftp::delete_tree(&sftp, path, dry_run, Some(&cancelled)).await,
+        Err(e) => Err(e),
+    };
+
+    if let Some(id) = operation_id {
+        deletes().lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+    }
+    // Even a failed or cancelled delete may have removed part of the tree.
+    if !dry_run {
+        invalidate(tx, vec![path.to_string()]).await;
+    }
+    result
+}
+
+/// Stop the delete started with `operation_id`. Returns `false` if it is not running.
+pub fn cancel_delete(operation_id: &str) -> bool {
+    let deletes = deletes().lock().unwrap_or_else(|e| e.into_inner());
+    let Some(cancel) = deletes.get(operation_id) else {
+        return false;
+    };
+    cancel.send_replace(true);
+    true
+}
+
+/// Copy a file or directory tree to `to`, which must not exist.
+pub async fn copy(tx: &mpsc::Sender<ConnectionRequest>, from: &str, to: &str) -> Result<CopyReport, SshError> {
+    let (sftp, extensions) = open_session(tx, true).await?;
+    let result = copy_on(tx, &sftp, extensions.as_ref(), from, to).await;
+    invalidate(tx, vec![to.to_string()]).await;
+    result
+}
+
+/// Move a file or directory tree to `to`, which must not exist. When the tree had to be copied
+/// and the source could not then be deleted, this fails with [`SshError::MoveIncomplete`]: the
+/// target is complete and what is left of the source still has to be removed.
+pub async fn move_path(tx: &mpsc::Sender<ConnectionRequest>, from: &str, to: &str) -> Result<MoveReport, SshError> {
+    let (sftp, extensions) = open_session(tx, true).await?;
+    let result = move_on(tx, &sftp, extensions.as_ref(), from, to).await;
+    invalidate(tx, vec![from.to_string(), to.to_string()]).await;
+    result
+}
+
+async fn move_on(
+    tx: &mpsc::Sender<ConnectionRequest>,
+    sftp: &SftpSession,
+    extensions: Option<&SftpExtensions>,
+    from: &str,
+    to: &str,
+) -> Result<MoveReport, SshError> {
+    if sftp::exists(sftp, to).await? {
+        return Err(SshError::TargetExists(to.to_string()));
+    }
+    match sftp.rename(from, to).await {
+        Ok(()) => {
+            return Ok(MoveReport {
+                renamed: true,
+                copy: None,
+            })
+        }
+        // SFTP v3 reports EXDEV only as a generic failure.
+        Err(SftpClientError::Status(status)) if status.status_code == StatusCode::Failure => {}
+        Err(e) => return Err(map_sftp_error(e)),
+    }
+
+    let copy = match copy_on(tx, sftp, extensions, from, to).await {
+        Ok(copy) => copy,
+        Err(e) => {
+            // Leave the source as the only copy rather than a half-written target.
+            if !matches!(e, SshError::TargetExists(_)) {
+                let _ = sftp::delete_tree(sftp, to, false, None).await;
+            }
+            return Err(e);
+        }
+    };
+    if let Err(e) = sftp::delete_tree(sftp, from, false, None).await {
+        return Err(SshError::MoveIncomplete {
+            from: from.to_string(),
+            to: to.to_string(),
+            detail: e.to_string(),
+        });
+    }
+    Ok(MoveReport {
+        renamed: false,
+        copy: Some(copy),
+    })
+}
+
+async fn copy_on(
+    tx: &mpsc::Sender<ConnectionRequest>,
+    sftp: &SftpSession,
+    extensions: Option<&SftpExtensions>,
+    from: &str,
+    to: &str,
+) -> Result<CopyReport, SshError> {
+    let plan = sftp::plan_copy(sftp, from, to).await?;
+    let copy_data = extensions.filter(|e| e.supports(sftp::COPY_DATA));
+    if copy_data.is_some() {
+        return sftp::copy_tree(sftp, copy_data, &plan, None).await;
+    }
+
+    let command = format!(
+        "cp -Rp -- {} {}",
+        shell_escape(plan.from()),
+        shell_escape(plan.to())
+    );
+    let output = run_command(tx, &command).await?;
+    let fallback_reason = if !output.accepted {
+        "server refused exec".to_string()
+    } else {
+        let created = sftp::exists(sftp, plan.to()).await?;
+        match output.exit_status {
+            Some(0) if created => return Ok(plan.report(CopyStrategy::Exec, None)),
+            // e.g. a forced `internal-sftp` command ran instead of cp
+            Some(0) | None if !created => "cp did not create the target".to_string(),
+            Some(126 | 127) => "cp is not available".to_string(),
+            status => {
+                return Err(SshError::SftpError(format!(
+                    "cp failed (exit status {:?}): {}",
+                    status,
+                    output.text().trim()
+                )))
+            }
+        }
+    };
+    sftp::copy_tree(sftp, None, &plan, Some(fallback_reason)).await
+}
+
+/// Ask the connection's actor for a dedicated SFTP session, with the extensions session next to
+/// it when `extensions` is set and the server allows one.
+async fn open_session(
+    tx: &mpsc::Sender<ConnectionRequest>,
+    extensions: bool,
+) -> Result<(SftpSession, Option<SftpExtensions>), SshError> {
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(ConnectionRequest::OpenSftpSession {
+        timeout_secs: REQUEST_TIMEOUT_SECS,
+        extensions,
+        respond_to,
+    })
+    .await
+    .map_err(|_| SshError::SftpSessionClosed)?;
+    rx.await.map_err(|_| SshError::SftpSessionClosed)?
+}
+
+/// Drop the actor's cached listings of `paths`, which an operation changed, even partially.
+async fn invalidate(tx: &mpsc::Sender<ConnectionRequest>, paths: Vec<String>) {
+    let (respond_to, _) = oneshot::channel();
+    let _ = tx.send(ConnectionRequest::InvalidateDirCache { paths, respond_to }).await;
+}
+
+/// Result of [`run_command`].
+struct CommandOutput {
+    /// False if the server refused the exec request
+    accepted: bool,
+    exit_status: Option<u32>,
+    /// Interleaved stdout and stderr, truncated to `COMMAND_OUTPUT_LIMIT`
+    output: Vec<u8>,
+}
+
+impl CommandOutput {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.output).into_owned()
+    }
+}
+
+/// Run `command` on a non-PTY exec channel with stdin closed, collecting its exit status and
+/// the first few KiB of its output.
+async fn run_command(tx: &mpsc::Sender<ConnectionRequest>, command: &str) -> Result<CommandOutput, SshError> {
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(ConnectionRequest::OpenExecChannel {
+        command: command.to_string(),
+        respond_to,
+    })
+    .await
+    .map_err(|_| SshError::SftpSessionClosed)?;
+    let mut channel = rx.await.map_err(|_| SshError::SftpSessionClosed)??;
+    let _ = channel.eof().await;
+
+    let mut output = CommandOutput {
+        accepted: true,
+        exit_status: None,
+        output: Vec::new(),
+    };
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            ChannelMsg::Failure => {
+                output.accepted = false;
+                break;
+            }
+            ChannelMsg::Data { data } | ChannelMsg::ExtendedData { data, .. } => {
+                let remaining = COMMAND_OUTPUT_LIMIT.saturating_sub(output.output.len());
+                output.output.extend_from_slice(&data[..data.len().min(remaining)]);
+            }
+            ChannelMsg::ExitStatus { exit_status } => output.exit_status = Some(exit_status),
+            ChannelMsg::Close => break,
+            _ => {}
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,642 @@
+//! Watching remote paths for changes made outside the editor (a build, `git checkout`, ...).
+//!
+//! Each connection has at most one watcher, given the open files and expanded directories by the
+//! UI. It runs `inotifywait` over an exec channel, and polls over a dedicated SFTP channel instead
+//! when that is not installed or cannot watch the paths. Changes are batched into `fs_changed` events
+//! and the affected `DirectoryCache` entries are dropped. Like followers, the watcher asks
+//! `AppState` for the current actor each time, so it picks up again after `ssh_reconnect`.
+
+use crate::ssh::actor::ConnectionRequest;
+use crate::ssh::client::{map_sftp_error, SshError};
+use crate::ssh::transfer::list_remote_dir;
+use crate::state::AppState;
+use crate::trace::{emit_trace, TraceEvent};
+use futures::stream::{self, StreamExt};
+use russh::ChannelMsg;
+use russh_sftp::client::RawSftpSession;
+use russh_sftp::protocol::FileAttributes;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use thiserror::Error;
+use tokio::sync::{oneshot, watch, Mutex};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// Every round lists or stats each path, so large sets are cut off.
+const MAX_POLLED_PATHS: usize = 512;
+/// Paths polled at once on the watcher's SFTP channel
+const POLL_CONCURRENCY: usize = 8;
+const POLL_REQUEST_TIMEOUT_SECS: u64 = 30;
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+const RESTART_DELAY: Duration = Duration::from_secs(2);
+/// An `inotifywait` that exits sooner than this could not set up its watches.
+const STARTUP_GRACE: Duration = Duration::from_secs(2);
+const STDERR_LIMIT: usize = 2048;
+
+/// Paths to watch are written to stdin, one per line. Output is `events<TAB>watched<TAB>name`,
+/// where `name` is empty for events on a watched file itself.
+const INOTIFY_COMMAND: &str = "exec inotifywait -m -q \
+     -e modify,attrib,close_write,move,create,delete,delete_self,move_self \
+     --format '%e\t%w\t%f' --fromfile -";
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error("Connection not found")]
+    ConnectionNotFound,
+    #[error("Connection is closed")]
+    ConnectionClosed,
+    #[error(transparent)]
+    Ssh(#[from] SshError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchMode {
+    Inotify,
+    Poll,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchInfo {
+    pub connection_id: String,
+    pub mode: WatchMode,
+    pub directories: Vec<String>,
+    pub files: Vec<String>,
+    /// Why polling is used instead of `inotifywait`
+    pub fallback_reason: Option<String>,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Attributes,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsChange {
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+/// Payload of `fs_changed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsChangedEvent {
+    pub connection_id: String,
+    pub source: WatchMode,
+    pub changes: Vec<FsChange>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct WatchPaths {
+    directories: Vec<String>,
+    files: Vec<String>,
+}
+
+impl WatchPaths {
+    fn all(&self) -> impl Iterator<Item = &String> {
+        self.directories.iter().chain(self.files.iter())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.directories.is_empty() && self.files.is_empty()
+    }
+}
+
+struct WatchEntry {
+    info: WatchInfo,
+    paths: watch::Sender<WatchPaths>,
+    stop: watch::Sender<bool>,
+}
+
+static WATCHES: OnceLock<StdMutex<HashMap<String, WatchEntry>>> = OnceLock::new();
+
+fn registry() -> &'static StdMutex<HashMap<String, WatchEntry>> {
+    WATCHES.get_or_init(|| StdMutex::new(HashMap::new()))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn normalize(paths: Vec<String>) -> Vec<String> {
+    let unique: BTreeSet<String> = paths
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .map(|p| match p.trim_end_matches('/') {
+            "" => "/".to_string(),
+            trimmed => trimmed.to_string(),
+        })
+        .collect();
+    unique.into_iter().collect()
+}
+
+/// Send a request to the connection's current actor and wait for its reply.
+async fn actor_request<T>(
+    app: &AppHandle,
+    connection_id: &str,
+    request: impl FnOnce(oneshot::Sender<Result<T, SshError>>) -> ConnectionRequest,
+) -> Result<T, WatchError> {
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    }
+    .ok_or(WatchError::ConnectionNotFound)?;
+
+    let (respond_to, rx) = oneshot::channel();
+    tx.send(request(respond_to))
+        .await
+        .map_err(|_| WatchError::ConnectionClosed)?;
+    Ok(rx.await.map_err(|_| WatchError::ConnectionClosed)??)
+}
+
+
+/// Watch exactly these directories (their entries, not recursively) and files, replacing the
+/// connection's previous set. An empty set keeps the watcher idle.
+pub fn set(app: AppHandle, connection_id: String, directories: Vec<String>, files: Vec<String>) -> WatchInfo {
+    let paths = WatchPaths {
+        directories: normalize(directories),
+        files: normalize(files),
+    };
+
+    let mut watches = registry().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(entry) = watches.get_mut(&connection_id) {
+        entry.info.directories = paths.directories.clone();
+        entry.info.files = paths.files.clone();
+        entry.paths.send_if_modified(|current| {
+            let modified = *current != paths;
+            *current = paths;
+            modified
+        });
+        return entry.info.clone();
+    }
+
+    let info = WatchInfo {
+        connection_id: connection_id.clone(),
+        mode: WatchMode::Inotify,
+        directories: paths.directories.clone(),
+        files: paths.files.clone(),
+        fallback_reason: None,
+        created_at: now_ms(),
+    };
+    let (paths_tx, paths_rx) = watch::channel(paths);
+    let (stop, stopped) = watch::channel(false);
+    watches.insert(
+        connection_id.clone(),
+        WatchEntry {
+            info: info.clone(),
+            paths: paths_tx,
+            stop,
+        },
+    );
+    drop(watches);
+
+    emit_trace(
+        &app,
+        TraceEvent::new("watch", "start", "Watching remote paths")
+            .with_correlation_id(&connection_id)
+            .with_detail(format!("{} directories, {} files", info.directories.len(), info.files.len())),
+    );
+    tauri::async_runtime::spawn(watch_loop(app, connection_id, paths_rx, stopped));
+    info
+}
+
+fn set_mode(app: &AppHandle, connection_id: &str, mode: WatchMode, fallback_reason: Option<&str>) {
+    let mut watches = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let Some(entry) = watches.get_mut(connection_id) else {
+        return;
+    };
+    if entry.info.mode == mode && entry.info.fallback_reason.as_deref() == fallback_reason {
+        return;
+    }
+    entry.info.mode = mode;
+    entry.info.fallback_reason = fallback_reason.map(str::to_string);
+    drop(watches);
+
+    let mut event = TraceEvent::new("watch", "mode", &format!("Watching with {:?}", mode)).with_correlation_id(connection_id);
+    if let Some(reason) = fallback_reason {
+        event = event.with_detail(reason);
+    }
+    emit_trace(app, event);
+}
+
+/// Resolves once the watcher is stopped. Wraps `wait_for`, whose guard is not `Send`.
+async fn stop_requested(stopped: &mut watch::Receiver<bool>) {
+    let _ = stopped.wait_for(|s| *s).await;
+}
+
+enum Outcome {
+    Stopped,
+    PathsChanged,
+    /// Run `inotifywait` again after a pause (connection lost, or a watched file was replaced)
+    Restart,
+    /// Poll instead; `permanent` if `inotifywait` cannot work on this server at all
+    Fallback { reason: String, permanent: bool },
+}
+
+async fn watch_loop(
+    app: AppHandle,
+    connection_id: String,
+    mut paths: watch::Receiver<WatchPaths>,
+    mut stopped: watch::Receiver<bool>,
+) {
+    let mut fallback: Option<String> = None;
+    let mut inotify_unavailable = false;
+
+    loop {
+        let watched = paths.borrow_and_update().clone();
+        let outcome = if watched.is_empty() {
+            tokio::select! {
+                _ = stop_requested(&mut stopped) => Outcome::Stopped,
+                changed = paths.changed() => if changed.is_ok() { Outcome::PathsChanged } else { Outcome::Stopped },
+            }
+        } else if let Some(reason) = &fallback {
+            set_mode(&app, &connection_id, WatchMode::Poll, Some(reason));
+            poll(&app, &connection_id, &watched, &mut paths, &mut stopped).await
+        } else {
+            set_mode(&app, &connection_id, WatchMode::Inotify, None);
+            run_inotify(&app, &connection_id, &watched, &mut paths, &mut stopped).await
+        };
+
+        match outcome {
+            Outcome::Stopped => break,
+            Outcome::PathsChanged => {
+                // The new set may be watchable even if the old one was not.
+                if !inotify_unavailable {
+                    fallback = None;
+                }
+            }
+            Outcome::Restart => {
+                tokio::select! {
+                    _ = tokio::time::sleep(RESTART_DELAY) => {}
+                    _ = stop_requested(&mut stopped) => break,
+                    changed = paths.changed() => if changed.is_err() { break },
+                }
+            }
+            Outcome::Fallback { reason, permanent } => {
+                fallback = Some(reason);
+                inotify_unavailable |= permanent;
+            }
+        }
+    }
+}
+
+/// Changes seen since the last flush, merged per path.
+type PendingChanges = HashMap<String, ChangeKind>;
+
+fn record(pending: &mut PendingChanges, path: String, kind: ChangeKind) {
+    let merged = match pending.get(&path) {
+        None => kind,
+        Some(&previous) => match (previous, kind) {
+            (_, ChangeKind::Deleted) => ChangeKind::Deleted,
+            // Deleted and recreated, e.g. replaced by an atomic save
+            (ChangeKind::Deleted, _) => ChangeKind::Modified,
+            (ChangeKind::Created, _) | (_, ChangeKind::Created) => ChangeKind::Created,
+            (ChangeKind::Modified, ChangeKind::Attributes) => ChangeKind::Modified,
+            (_, next) => next,
+        },
+    };
+    pending.insert(path, merged);
+}
+
+/// Drop cached listings for the changed paths, then tell the UI. The invalidation is queued
+/// before the event is emitted, so a listing requested in response sees fresh data.
+async fn flush(app: &AppHandle, connection_id: &str, source: WatchMode, pending: &mut PendingChanges) {
+    if pending.is_empty() {
+        return;
+    }
+    let changes: Vec<FsChange> = pending.drain().map(|(path, kind)| FsChange { path, kind }).collect();
+
+    let tx = {
+        let state = app.state::<Arc<Mutex<AppState>>>();
+        let app_state = state.lock().await;
+        app_state.get_connection_sender(connection_id)
+    };
+    if let Some(tx) = tx {
+        let (respond_to, _) = oneshot::channel();
+        let _ = tx
+            .send(ConnectionRequest::InvalidateDirCache {
+                paths: changes.iter().map(|c| c.path.clone()).collect(),
+                respond_to,
+            })
+            .await;
+    }
+
+    let event = FsChangedEvent {
+        connection_id: connection_id.to_string(),
+        source,
+        changes,
+    };
+    if let Err(e) = app.emit("fs_changed", event) {
+        log::error!("Failed to emit fs change: {}", e);
+    }
+}
+
+/// Parse one line of [`INOTIFY_COMMAND`] output.
+fn parse_event(line: &str) -> Option<(String, ChangeKind)> {
+    let mut fields = line.splitn(3, '\t');
+    let (events, watched, name) = (fields.next()?, fields.next()?, fields.next().unwrap_or(""));
+    let path = if name.is_empty() {
+        watched.to_string()
+    } else {
+        format!("{}/{}", watched.trim_end_matches('/'), name)
+    };
+    let kind = events.split(',').find_map(|event| match event {
+        "CREATE" | "MOVED_TO" => Some(ChangeKind::Created),
+        "DELETE" | "DELETE_SELF" | "MOVED_FROM" | "MOVE_SELF" => Some(ChangeKind::Deleted),
+        "MODIFY" | "CLOSE_WRITE" => Some(ChangeKind::Modified),
+        "ATTRIB" => Some(ChangeKind::Attributes),
+        _ => None,
+    })?;
+    Some((path, kind))
+}
+
+async fn run_inotify(
+    app: &AppHandle,
+    connection_id: &str,
+    watched: &WatchPaths,
+    paths: &mut watch::Receiver<WatchPaths>,
+    stopped: &mut watch::Receiver<bool>,
+) -> Outcome {
+    let opened = actor_request(app, connection_id, |respond_to| ConnectionRequest::OpenExecChannel {
+        command: INOTIFY_COMMAND.to_string(),
+        respond_to,
+    })
+    .await;
+    let mut channel = match opened {
+        Ok(channel) => channel,
+        Err(WatchError::Ssh(SshError::ChannelOpenFailed(reason))) => {
+            return Outcome::Fallback {
+                reason: format!("cannot open exec channel: {}", reason),
+                permanent: false,
+            }
+        }
+        Err(_) => return Outcome::Restart,
+    };
+
+    let list: String = watched.all().map(|path| format!("{}\n", path)).collect();
+    if channel.data(list.as_bytes()).await.is_err() {
+        return Outcome::Restart;
+    }
+    let _ = channel.eof().await;
+
+    let started = Instant::now();
+    let mut pending = PendingChanges::new();
+    let mut stdout: Vec<u8> = Vec::new();
+    let mut stderr: Vec<u8> = Vec::new();
+    let mut exit_status = None;
+    let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+
+    let outcome = loop {
+        tokio::select! {
+            _ = stop_requested(stopped) => break Outcome::Stopped,
+            changed = paths.changed() => {
+                break if changed.is_ok() { Outcome::PathsChanged } else { Outcome::Stopped };
+            }
+            _ = ticker.tick() => flush(app, connection_id, WatchMode::Inotify, &mut pending).await,
+            msg = channel.wait() => match msg {
+                Some(ChannelMsg::Data { data }) => {
+                    stdout.extend_from_slice(&data);
+                    let mut replaced = false;
+                    while let Some(newline) = stdout.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = stdout.drain(..=newline).collect();
+                        if let Some((path, kind)) = parse_event(&String::from_utf8_lossy(&line[..newline])) {
+                            // The watch went with the old inode; a new watch is needed for the path.
+                            replaced |= kind == ChangeKind::Deleted && watched.files.contains(&path);
+                            record(&mut pending, path, kind);
+                        }
+                    }
+                    if replaced {
+                        break Outcome::Restart;
+                    }
+                }
+                Some(ChannelMsg::ExtendedData { data, .. }) => {
+                    let remaining = STDERR_LIMIT.saturating_sub(stderr.len());
+                    stderr.extend_from_slice(&data[..data.len().min(remaining)]);
+                }
+                Some(ChannelMsg::ExitStatus { exit_status: status }) => exit_status = Some(status),
+                Some(ChannelMsg::Failure) => {
+                    break Outcome::Fallback {
+                        reason: "server refused exec".to_string(),
+                        permanent: true,
+                    };
+                }
+                None | Some(ChannelMsg::Close) => {
+                    let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+                    break match exit_status {
+                        Some(126 | 127) => Outcome::Fallback {
+                            reason: "inotifywait is not installed".to_string(),
+                            permanent: true,
+                        },
+                        Some(status) if started.elapsed() < STARTUP_GRACE => Outcome::Fallback {
+                            reason: format!("inotifywait exited with status {}: {}", status, stderr),
+                            permanent: false,
+                        },
+                        _ => Outcome::Restart,
+                    };
+                }
+                _ => {}
+            },
+        }
+    };
+
+    flush(app, connection_id, WatchMode::Inotify, &mut pending).await;
+    let _ = channel.close().await;
+    outcome
+}
+
+/// What polling compares for a file or directory entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Signature {
+    size: u64,
+    mtime: i64,
+    permissions: Option<u32>,
+}
+
+impl Signature {
+    fn of(attrs: &FileAttributes) -> Self {
+        Self {
+            size: attrs.size.unwrap_or(0),
+            mtime: attrs.mtime.map(|t| t as i64).unwrap_or(0),
+            permissions: attrs.permissions,
+        }
+    }
+
+    /// How `self` changed into `current`, if it did.
+    fn change(&self, current: &Signature) -> Option<ChangeKind> {
+        if self.size != current.size || self.mtime != current.mtime {
+            Some(ChangeKind::Modified)
+        } else if self.permissions != current.permissions {
+            Some(ChangeKind::Attributes)
+        } else {
+            None
+        }
+    }
+}
+
+/// The state of a watched path in one polling round.
+#[derive(Debug, Clone, PartialEq)]
+enum Polled {
+    Missing,
+    File(Signature),
+    Dir(HashMap<String, Signature>),
+}
+
+/// Read one watched path: a directory's listing, or a file's attributes. Paths that cannot be read
+/// count as missing; connection-level errors are returned.
+async fn poll_path(sftp: &RawSftpSession, path: &str, directory: bool) -> Result<Polled, SshError> {
+    let polled = if directory {
+        list_remote_dir(sftp, path).await.map(|entries| {
+            Polled::Dir(
+                entries
+                    .into_iter()
+                    .map(|(name, attrs)| (name, Signature::of(&attrs)))
+                    .collect(),
+            )
+        })
+    } else {
+        sftp.stat(path)
+            .await
+            .map(|attrs| Polled::File(Signature::of(&attrs.attrs)))
+            .map_err(map_sftp_error)
+    };
+    match polled {
+        Err(SshError::SftpError(_)) => Ok(Polled::Missing),
+        other => other,
+    }
+}
+
+fn child_path(directory: &str, name: &str) -> String {
+    format!("{}/{}", directory.trim_end_matches('/'), name)
+}
+
+/// Record the difference between two rounds of `path`. For directories, added, removed and changed
+/// entries are named individually.
+fn diff(pending: &mut PendingChanges, path: &str, previous: &Polled, current: &Polled) {
+    match (previous, current) {
+        (Polled::Missing, Polled::Missing) => {}
+        (Polled::Missing, _) => record(pending, path.to_string(), ChangeKind::Created),
+        (_, Polled::Missing) => record(pending, path.to_string(), ChangeKind::Deleted),
+        (Polled::File(before), Polled::File(after)) => {
+            if let Some(kind) = before.change(after) {
+                record(pending, path.to_string(), kind);
+            }
+        }
+        (Polled::Dir(before), Polled::Dir(after)) => {
+            for (name, signature) in after {
+                let kind = match before.get(name) {
+                    None => Some(ChangeKind::Created),
+                    Some(previous) => previous.change(signature),
+                };
+                if let Some(kind) = kind {
+                    record(pending, child_path(path, name), kind);
+                }
+            }
+            for name in before.keys().filter(|name| !after.contains_key(*name)) {
+                record(pending, child_path(path, name), ChangeKind::Deleted);
+            }
+        }
+        // Replaced by something of the other kind
+        _ => record(pending, path.to_string(), ChangeKind::Modified),
+    }
+}
+
+/// Every [`POLL_INTERVAL`], list each watched directory and stat each watched file on a dedicated
+/// SFTP channel, so polling never queues behind editor requests in the actor. Directory listings
+/// are diffed, so entries added, removed or changed are reported by name. The first round only
+/// records a baseline.
+async fn poll(
+    app: &AppHandle,
+    connection_id: &str,
+    watched: &WatchPaths,
+    paths: &mut watch::Receiver<WatchPaths>,
+    stopped: &mut watch::Receiver<bool>,
+) -> Outcome {
+    let targets: Vec<(String, bool)> = watched
+        .directories
+        .iter()
+        .map(|path| (path.clone(), true))
+        .chain(watched.files.iter().map(|path| (path.clone(), false)))
+        .take(MAX_POLLED_PATHS)
+        .collect();
+    let mut known: HashMap<String, Polled> = HashMap::new();
+    let mut sftp: Option<RawSftpSession> = None;
+
+    loop {
+        // (Re)open the channel lazily: after `ssh_reconnect` the next round uses the new session.
+        if sftp.is_none() {
+            sftp = actor_request(app, connection_id, |respond_to| ConnectionRequest::OpenSftpChannel {
+                timeout_secs: POLL_REQUEST_TIMEOUT_SECS,
+                respond_to,
+            })
+            .await
+            .ok();
+        }
+
+        if let Some(session) = &sftp {
+            let results: Vec<(String, Result<Polled, SshError>)> = stream::iter(targets.clone())
+                .map(|(path, directory)| async move {
+                    let result = poll_path(session, &path, directory).await;
+                    (path, result)
+                })
+                .buffer_unordered(POLL_CONCURRENCY)
+                .collect()
+                .await;
+
+            let mut pending = PendingChanges::new();
+            let mut lost = false;
+            for (path, result) in results {
+                match result {
+                    Ok(current) => {
+                        if let Some(previous) = known.get(&path) {
+                            diff(&mut pending, &path, previous, &current);
+                        }
+                        known.insert(path, current);
+                    }
+                    // A timeout or a closed channel: keep the last known state and reopen.
+                    Err(_) => lost = true,
+                }
+            }
+            if lost {
+                sftp = None;
+            }
+            flush(app, connection_id, WatchMode::Poll, &mut pending).await;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = stop_requested(stopped) => return Outcome::Stopped,
+            changed = paths.changed() => {
+                return if changed.is_ok() { Outcome::PathsChanged } else { Outcome::Stopped };
+            }
+        }
+    }
+}
+
+/// The watcher of a connection, if any.
+pub fn status(connection_id: &str) -> Option<WatchInfo> {
+    let watches = registry().lock().unwrap_or_else(|e| e.into_inner());
+    watches.get(connection_id).map(|entry| entry.info.clone())
+}
+
+/// Stop watching for a connection; returns the watcher's last state, or `None` if it had none.
+pub fn stop(connection_id: &str) -> Option<WatchInfo> {
+    let entry = registry()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(connection_id)?;
+    entry.stop.send_replace(true);
+    Some(entry.info)
+}
@@ -1,5 +1,5 @@
 import { writable, derived, get } from 'svelte/store';
-import type { ConnectionState, ConnectionProfile, ActiveConnection } from '$types';
+import type { ConnectionState, ConnectionProfile, ActiveConnection, ConnectionTestResult } from '$types';
 import { invoke, isTauri, listen, TauriCommandError } from '$utils/tauri';
 import { loadSavedConnections, saveConnections } from '$utils/storage';
 import { notificationsStore } from './notifications';
@@ -486,7 +486,8 @@ function createConnectionStore() {
 		 */
 		async testConnection(profile: ConnectionProfile, password?: string): Promise<boolean> {
 			try {
-				return await invoke<boolean>('ssh_test_connection', { profile, password });
+				const result = await invoke<ConnectionTestResult>('ssh_test_connection', { profile, password });
+				return result.success;
 			} catch {
 				return false;
 			}
@@ -1,6 +1,6 @@
 import { derived, get } from 'svelte/store';
 import type { FileState, FileEntry, OpenFile, SessionFileState } from '$types';
-import { invoke } from '$utils/tauri';
+import { invoke, TauriCommandError } from '$utils/tauri';
 import { workspaceStore, activeSession } from './workspace';
 import { detectLanguage } from '$utils/languages';
 import { sortEntries } from '$utils/file-tree';
@@ -532,11 +532,24 @@ function createFileStore() {
 				throw new Error('CONFLICT');
 			}
 
-			const result = await invoke<FileMeta>('sftp_write_file', {
-				connId,
-				path,
-				content: file.content
-			});
+			// The server re-checks these under the write lock, closing the gap between stat and write.
+			let result: FileMeta;
+			try {
+				result = await invoke<FileMeta>('sftp_write_file', {
+					connId,
+					path,
+					content: file.content,
+					precondition: {
+						expectedMtime: file.remoteMtime,
+						expectedSize: file.remoteSize
+					}
+				});
+			} catch (error) {
+				if (error instanceof TauriCommandError && error.code === 'write_conflict') {
+					throw new Error('CONFLICT');
+				}
+				throw error;
+			}
 
 			updateFileState(sessionId, (s) => {
 				const newOpenFiles = new Map(s.openFiles);
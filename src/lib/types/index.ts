@@ -10,6 +10,8 @@ export interface ConnectionProfile {
 	username: string;
 	authMethod: AuthMethod;
 	keyPath?: string;
+	/** OpenSSH user certificate; defaults to `<keyPath>-cert.pub` when present. */
+	certificatePath?: string;
 	/**
 	 * Trusted SSH server host key fingerprint (SHA256).
 	 * Used to derive stable tmux session names across hostname aliases (e.g. LAN hostname vs DDNS name).
@@ -19,6 +21,22 @@ export interface ConnectionProfile {
 	bookmarkedPaths: string[];
 }
 
+export interface CertificateInfo {
+	path: string;
+	keyId: string;
+	principals: string[];
+	validAfter: number;
+	/** Unix seconds; null when the certificate never expires. */
+	validBefore: number | null;
+	expired: boolean;
+	caFingerprintSha256: string;
+}
+
+export interface ConnectionTestResult {
+	success: boolean;
+	certificate: CertificateInfo | null;
+}
+
 // Legacy single-connection state (kept for backward compatibility during migration)
 export interface ConnectionStateLegacy {
 	status: ConnectionStatus;
@@ -41,9 +59,19 @@ export interface FileEntry {
 	name: string;
 	path: string;
 	isDirectory: boolean;
+	isSymlink?: boolean;
+	targetIsDirectory?: boolean;
 	size: number;
 	mtime: number;
 	permissions?: string;
+	fileType?: 'file' | 'directory' | 'symlink' | 'socket' | 'fifo' | 'block_device' | 'char_device' | 'unknown';
+	symlinkTarget?: string | null;
+	brokenLink?: boolean;
+	uid?: number | null;
+	gid?: number | null;
+	user?: string | null;
+	group?: string | null;
+	atime?: number | null;
 	children?: FileEntry[];
 }
 